@@ -47,8 +47,8 @@ impl GossipCommand {
         tracing::info!("Gossip driver started, receiving blocks.");
         loop {
             match recv.recv() {
-                Ok(block) => {
-                    tracing::info!("Received unsafe block: {:?}", block);
+                Ok(envelope) => {
+                    tracing::info!("Received unsafe block: {:?}", envelope.payload);
                 }
                 Err(e) => {
                     tracing::warn!("Failed to receive unsafe block: {:?}", e);
@@ -1,19 +1,31 @@
 //! CLI arguments for the Hilo Node.
 
-use std::{fs::File, path::PathBuf};
+use std::{fs::File, net::SocketAddr, path::PathBuf, time::Duration};
 
-use clap::Parser;
-use eyre::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use eyre::{bail, eyre, Context, Result};
+use serde::{Deserialize, Serialize};
 use serde_json::from_reader;
 use tracing::debug;
 use url::Url;
 
+use alloy_primitives::Address;
 use alloy_rpc_types_engine::JwtSecret;
 use op_alloy_genesis::RollupConfig;
 use op_alloy_registry::ROLLUP_CONFIGS;
+use superchain::{network_rollup_config, Registry, NETWORKS};
 
+use hilo_driver::L1ChainSpec;
 use hilo_engine::ValidationMode;
-use hilo_node::SyncMode;
+use hilo_node::{
+    parse_bootnodes, HealthConfig, NetworkConfig, SyncMode, DEFAULT_EL_SYNC_TIMEOUT,
+    DEFAULT_MAX_L1_SILENCE, DEFAULT_MAX_SAFE_LAG,
+};
+
+use crate::{
+    config::{display_from_str, ConfigCommand},
+    derive::DeriveCommand,
+};
 
 /// The default L2 chain ID to use. This corresponds to OP Mainnet.
 pub const DEFAULT_L2_CHAIN_ID: u64 = 10;
@@ -27,10 +39,30 @@ pub const DEFAULT_L2_RPC_URL: &str = "https://optimism.llamarpc.com/";
 /// The default L1 Beacon Client RPC URL to use.
 pub const DEFAULT_L1_BEACON_CLIENT_URL: &str = "http://localhost:5052/";
 
+/// The default address to listen for p2p gossip and discovery on.
+pub const DEFAULT_P2P_LISTEN_ADDR: &str = "0.0.0.0:9222";
+
 /// CLI Arguments.
-#[derive(Parser, Clone, Debug)]
+///
+/// Every argument can also be set in the `--config` file or through a `HILO_`
+/// prefixed environment variable, keyed by its field name (e.g. `l2_chain_id`
+/// and `HILO_L2_CHAIN_ID`). See [NodeArgs::layered] for the precedence.
+#[derive(Parser, Clone, Debug, Serialize, Deserialize)]
 #[command(author, version, about, long_about = None)]
 pub(crate) struct NodeArgs {
+    /// An optional subcommand to run instead of the node.
+    #[clap(subcommand)]
+    #[serde(skip)]
+    pub command: Option<NodeSubcommand>,
+
+    /// Path to a TOML configuration file.
+    ///
+    /// Values are resolved in the following order, later sources taking precedence:
+    /// built-in defaults, the configuration file, `HILO_*` environment variables,
+    /// and finally command line flags (including their own environment variables).
+    #[clap(long = "config", env = "HILO_CONFIG")]
+    pub config: Option<PathBuf>,
+
     /// A port to serve prometheus metrics on.
     #[clap(long, default_value = "9090", help = "The port to serve prometheus metrics on")]
     pub metrics_port: u16,
@@ -39,19 +71,75 @@ pub(crate) struct NodeArgs {
     #[clap(long = "checkpoint-sync-url")]
     pub checkpoint_sync_url: Option<Url>,
 
-    /// An RPC URL to run the node's rpc server.
+    /// Hash of the L2 block to checkpoint sync to.
+    /// Defaults to the latest finalized block of the checkpoint sync server.
+    #[clap(long = "checkpoint-hash")]
+    pub checkpoint_hash: Option<String>,
+
+    /// The address to serve the rollup node RPC on, e.g. `http://127.0.0.1:9545`.
+    ///
+    /// The `optimism` namespace is served over both HTTP and WebSocket.
     #[clap(long = "rpc-url")]
     pub rpc_url: Option<Url>,
 
+    /// The address to serve the admin RPC on, e.g. `http://127.0.0.1:9546`.
+    ///
+    /// The `admin` namespace controls derivation and the execution client,
+    /// so it is disabled unless set and should not be exposed publicly.
+    #[clap(long = "admin-rpc-url")]
+    pub admin_rpc_url: Option<Url>,
+
+    /// The address to serve the `/healthz` and `/readyz` probes on, e.g. `http://0.0.0.0:9547`.
+    ///
+    /// Both respond with JSON detail, and status 200 when healthy or 503 otherwise.
+    #[clap(long = "health-url")]
+    pub health_url: Option<Url>,
+
+    /// Seconds without a new L1 block after which the node is unhealthy.
+    #[clap(long = "health-max-l1-silence", default_value_t = DEFAULT_MAX_L1_SILENCE.as_secs())]
+    pub health_max_l1_silence: u64,
+
+    /// Age in seconds of the L2 safe head after which the node is not ready.
+    #[clap(long = "health-max-safe-lag", default_value_t = DEFAULT_MAX_SAFE_LAG.as_secs())]
+    pub health_max_safe_lag: u64,
+
     /// Chain ID of the L2 network
     #[clap(long = "l2-chain-id", default_value_t = DEFAULT_L2_CHAIN_ID)]
     pub l2_chain_id: u64,
 
+    /// Name of the L2 network in the superchain registry, e.g. `base-sepolia`.
+    /// Selects the chain instead of `--l2-chain-id`.
+    #[clap(long = "network", conflicts_with_all = ["l2_chain_id", "l2_config_file"])]
+    pub network: Option<String>,
+
+    /// Path to a local checkout of the superchain registry, whose chain configs
+    /// take precedence over the built-in ones.
+    #[clap(long = "superchain-registry")]
+    pub superchain_registry: Option<PathBuf>,
+
     /// Path to a custom L2 rollup configuration file
     /// (overrides the default rollup configuration from the registry)
     #[clap(long = "l2-config-file")]
     pub l2_config_file: Option<PathBuf>,
 
+    /// Runs the node against a local devnet.
+    ///
+    /// The rollup config must be given with `--l2-config-file`, so chain IDs outside
+    /// the superchain registry are accepted. Clients are polled more often, the default
+    /// bootnodes are skipped and an unreachable blob archiver is ignored.
+    #[clap(long = "devnet")]
+    pub devnet: bool,
+
+    /// Skips the startup checks that the L1 RPC, beacon node and execution client
+    /// serve the chains of the rollup config.
+    #[clap(long = "skip-preflight")]
+    pub skip_preflight: bool,
+
+    /// Path to a JSON file with the L1 `chain_id`, beacon `genesis_time` and
+    /// `seconds_per_slot`, used instead of querying the beacon node.
+    #[clap(long = "l1-chain-spec")]
+    pub l1_chain_spec: Option<PathBuf>,
+
     /// RPC URL of an L1 execution client
     /// (This is only needed when running in Standalone mode)
     #[clap(long = "l1-rpc-url", default_value = DEFAULT_L1_RPC_URL)]
@@ -65,9 +153,16 @@ pub(crate) struct NodeArgs {
     #[clap(long = "l2-rpc-url", default_value = DEFAULT_L2_RPC_URL)]
     pub l2_rpc_url: Url,
 
+    /// The sync mode: `full`, `checkpoint`, `fast` or `challenge`.
     #[clap(short = 'm', long, default_value = "full")]
+    #[serde(with = "display_from_str")]
     pub sync_mode: SyncMode,
 
+    /// Seconds the execution client is given to sync to a trusted block in checkpoint
+    /// and fast sync.
+    #[clap(long = "el-sync-timeout", default_value_t = DEFAULT_EL_SYNC_TIMEOUT.as_secs())]
+    pub el_sync_timeout: u64,
+
     /// URL of the blob archiver to fetch blobs that are expired on
     /// the beacon client but still needed for processing.
     ///
@@ -83,11 +178,12 @@ pub(crate) struct NodeArgs {
     /// - Engine API: use a local or remote engine API of an L2 execution client. Validation
     ///   happens by sending the `new_payload` to the API and expecting a VALID response.
     #[clap(long = "validation-mode", default_value = "engine-api")]
+    #[serde(with = "display_from_str")]
     pub validation_mode: ValidationMode,
 
-    /// URL of the engine API endpoint of an L2 execution client.
+    /// URL of the engine API endpoint of an L2 execution client. Required.
     #[clap(long = "l2-engine-api-url", env = "L2_ENGINE_API_URL")]
-    pub l2_engine_api_url: Url,
+    pub l2_engine_api_url: Option<Url>,
 
     /// JWT secret for the auth-rpc endpoint of the execution client.
     /// This MUST be a valid path to a file containing the hex-encoded JWT secret.
@@ -100,26 +196,118 @@ pub(crate) struct NodeArgs {
     /// When the limit is reached, the oldest blocks are discarded.
     #[clap(long = "l1-chain-cache-size", default_value_t = 256)]
     pub l1_chain_cache_size: usize,
+
+    /// Directory used to persist node state across restarts.
+    ///
+    /// When set, the derivation pipeline is periodically checkpointed here
+    /// and resumed from the newest valid checkpoint on startup.
+    #[clap(long = "data-dir", env = "DATA_DIR")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Enables p2p networking to receive unsafe blocks gossiped by the sequencer.
+    /// Requires the `--p2p-unsafe-block-signer`.
+    #[clap(long = "p2p-enabled")]
+    pub p2p_enabled: bool,
+
+    /// The address to listen for p2p gossip (TCP) and discovery (UDP) on.
+    #[clap(long = "p2p-listen-addr", default_value = DEFAULT_P2P_LISTEN_ADDR)]
+    pub p2p_listen_addr: SocketAddr,
+
+    /// The address of the sequencer key that signs unsafe blocks.
+    #[clap(long = "p2p-unsafe-block-signer")]
+    pub p2p_unsafe_block_signer: Option<Address>,
+
+    /// The interval in seconds between discovery lookups for new peers.
+    #[clap(long = "p2p-discovery-interval", default_value_t = 10)]
+    pub p2p_discovery_interval: u64,
+
+    /// Comma separated ENRs or multiaddrs replacing the default bootnodes of the chain.
+    #[clap(long = "p2p-bootnodes", value_delimiter = ',')]
+    pub p2p_bootnodes: Vec<String>,
+
+    /// Comma separated multiaddrs of peers that are always dialed. Peers are only
+    /// redialed after disconnecting if the address ends with a `/p2p/` peer id.
+    #[clap(long = "p2p-static-peers", value_delimiter = ',')]
+    pub p2p_static_peers: Vec<String>,
+
+    /// Disables the default bootnodes of the chain, e.g. for private chains.
+    #[clap(long = "p2p-no-default-bootnodes")]
+    pub p2p_no_default_bootnodes: bool,
+
+    /// Address of the L1 `L2OutputOracle` to verify output proposals against
+    /// in challenge sync mode.
+    #[clap(long = "l2-output-oracle")]
+    pub l2_output_oracle: Option<Address>,
+
+    /// Address of the L1 `DisputeGameFactory` to verify output proposals against
+    /// in challenge sync mode. Takes precedence over `--l2-output-oracle`.
+    #[clap(long = "dispute-game-factory")]
+    pub dispute_game_factory: Option<Address>,
+}
+
+/// Subcommands for the CLI.
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum NodeSubcommand {
+    /// Derive a bounded range of L2 blocks and exit.
+    Derive(DeriveCommand),
+    /// Inspect the effective configuration.
+    Config(ConfigCommand),
 }
 
 #[allow(unused)]
 impl NodeArgs {
     /// Get the L2 rollup config, either from a file or the superchain registry.
+    ///
+    /// Chains are looked up in the local `--superchain-registry` first, if given,
+    /// and in the registry built into hilo otherwise.
     pub fn get_l2_config(&self) -> Result<RollupConfig> {
-        match &self.l2_config_file {
+        if let Some(path) = &self.l2_config_file {
+            debug!("Loading l2 config from file: {:?}", path);
+            let file = File::open(path).wrap_err("Failed to open l2 config file")?;
+            return Ok(from_reader(file).wrap_err("Failed to read l2 config file")?);
+        }
+        if self.devnet {
+            bail!("Devnet mode requires an `--l2-config-file`");
+        }
+
+        let registry = match &self.superchain_registry {
             Some(path) => {
-                debug!("Loading l2 config from file: {:?}", path);
-                let file = File::open(path).wrap_err("Failed to open l2 config file")?;
-                Ok(from_reader(file).wrap_err("Failed to read l2 config file")?)
-            }
-            None => {
-                debug!("Loading l2 config from superchain registry");
-                let Some(cfg) = ROLLUP_CONFIGS.get(&self.l2_chain_id).cloned() else {
-                    bail!("Failed to find l2 config for chain ID {}", self.l2_chain_id);
-                };
-                Ok(cfg)
+                debug!("Loading superchain registry from: {:?}", path);
+                let registry =
+                    Registry::from_dir(path).wrap_err("Failed to load the superchain registry")?;
+                Some(registry)
             }
+            None => None,
+        };
+
+        debug!("Loading l2 config from superchain registry");
+        if let Some(name) = &self.network {
+            let local = registry.as_ref().and_then(|registry| registry.get(name).cloned());
+            let Some(cfg) = local.or_else(|| network_rollup_config(name)) else {
+                let mut names: Vec<_> = NETWORKS.iter().map(|(name, _)| *name).collect();
+                names.extend(registry.iter().flat_map(Registry::names));
+                names.sort_unstable();
+                names.dedup();
+                bail!("Unknown network `{}`, expected one of: {}", name, names.join(", "));
+            };
+            return Ok(cfg);
         }
+
+        let local = registry.and_then(|registry| registry.by_chain_id(self.l2_chain_id).cloned());
+        let Some(cfg) = local.or_else(|| ROLLUP_CONFIGS.get(&self.l2_chain_id).cloned()) else {
+            bail!("Failed to find l2 config for chain ID {}", self.l2_chain_id);
+        };
+        Ok(cfg)
+    }
+
+    /// Get the L1 chain spec from a file, if one is given.
+    pub fn get_l1_chain_spec(&self) -> Result<Option<L1ChainSpec>> {
+        let Some(path) = &self.l1_chain_spec else {
+            return Ok(None);
+        };
+        debug!("Loading l1 chain spec from file: {:?}", path);
+        let file = File::open(path).wrap_err("Failed to open l1 chain spec file")?;
+        Ok(Some(from_reader(file).wrap_err("Failed to read l1 chain spec file")?))
     }
 
     /// Returns the JWT secret for the engine API
@@ -157,24 +345,69 @@ impl NodeArgs {
     }
 }
 
-impl From<NodeArgs> for hilo_node::Config {
-    fn from(args: NodeArgs) -> Self {
-        let rollup_config = args.get_l2_config().unwrap();
-        let jwt_secret = args.jwt_secret().unwrap();
-        Self {
-            l2_chain_id: args.l2_chain_id,
+impl TryFrom<NodeArgs> for hilo_node::Config {
+    type Error = eyre::Report;
+
+    fn try_from(args: NodeArgs) -> Result<Self> {
+        let rollup_config = args.get_l2_config()?;
+        let l1_chain_spec = args.get_l1_chain_spec()?;
+        let jwt_secret = args.jwt_secret().ok_or_else(|| eyre!("Failed to load the JWT secret"))?;
+        let Some(l2_engine_url) = args.l2_engine_api_url else {
+            bail!(
+                "Missing the L2 engine API url, set `--l2-engine-api-url` or `l2_engine_api_url`"
+            );
+        };
+        let network = match (args.p2p_enabled, args.p2p_unsafe_block_signer) {
+            (false, _) => None,
+            (true, None) => bail!("P2P networking requires the `--p2p-unsafe-block-signer`"),
+            (true, Some(signer)) => {
+                parse_bootnodes(&args.p2p_bootnodes).wrap_err("Invalid `--p2p-bootnodes`")?;
+                parse_bootnodes(&args.p2p_static_peers).wrap_err("Invalid `--p2p-static-peers`")?;
+                if args.p2p_static_peers.iter().any(|peer| peer.starts_with("enr:")) {
+                    bail!("`--p2p-static-peers` must be multiaddrs, not ENRs");
+                }
+                Some(NetworkConfig {
+                    listen_addr: args.p2p_listen_addr,
+                    unsafe_block_signer: signer,
+                    discovery_interval: Duration::from_secs(args.p2p_discovery_interval),
+                    bootnodes: args.p2p_bootnodes,
+                    static_peers: args.p2p_static_peers,
+                    no_default_bootnodes: args.p2p_no_default_bootnodes,
+                })
+            }
+        };
+        // A named network selects the chain id along with the rollup config.
+        let l2_chain_id =
+            if args.network.is_some() { rollup_config.l2_chain_id } else { args.l2_chain_id };
+        let config = Self {
+            l2_chain_id,
             l1_rpc_url: args.l1_rpc_url,
             l1_beacon_url: args.l1_beacon_client_url,
             blob_archiver_url: args.l1_blob_archiver_url,
             l2_rpc_url: args.l2_rpc_url,
-            l2_engine_url: args.l2_engine_api_url,
+            l2_engine_url,
             rollup_config,
             jwt_secret,
             checkpoint_sync_url: args.checkpoint_sync_url,
             sync_mode: args.sync_mode,
+            el_sync_timeout: Duration::from_secs(args.el_sync_timeout),
             rpc_url: args.rpc_url,
-            devnet: false,
+            admin_rpc_url: args.admin_rpc_url,
+            health_url: args.health_url,
+            health: HealthConfig {
+                max_l1_silence: Duration::from_secs(args.health_max_l1_silence),
+                max_safe_lag: Duration::from_secs(args.health_max_safe_lag),
+            },
+            devnet: args.devnet,
+            l1_chain_spec,
+            skip_preflight: args.skip_preflight,
             cache_size: args.l1_chain_cache_size,
-        }
+            data_dir: args.data_dir,
+            network,
+            l2_output_oracle: args.l2_output_oracle,
+            dispute_game_factory: args.dispute_game_factory,
+        };
+        config.validate().wrap_err("Invalid configuration")?;
+        Ok(config)
     }
 }
@@ -0,0 +1,33 @@
+//! Derive subcommand for the Hilo Node.
+
+use clap::Args;
+use eyre::Result;
+use hilo_node::Node;
+
+/// The derive subcommand.
+///
+/// Runs derivation over a bounded range of L2 blocks, prints a summary and exits.
+#[derive(Debug, Clone, Args)]
+pub(crate) struct DeriveCommand {
+    /// The L2 block to start deriving from.
+    /// Defaults to the latest block the execution client has synced to.
+    #[clap(long, help = "The L2 block to start deriving from")]
+    pub from: Option<u64>,
+    /// The L2 block to derive up to (inclusive).
+    #[clap(long, help = "The L2 block to derive up to (inclusive)")]
+    pub to: u64,
+}
+
+impl DeriveCommand {
+    /// Run the derive subcommand.
+    pub async fn run(self, node: Node) -> Result<()> {
+        tracing::info!("Deriving L2 blocks up to {}", self.to);
+        let summary = match node.derive(self.from, self.to).await {
+            Ok(summary) => summary,
+            Err(e) => eyre::bail!("[CRIT] Derivation failed: {:?}", e),
+        };
+        tracing::info!("Derivation complete");
+        println!("{summary}");
+        Ok(())
+    }
+}
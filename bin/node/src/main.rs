@@ -3,27 +3,50 @@
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 
-use clap::Parser;
-use hilo_node::{Config, Node};
+use clap::{CommandFactory, FromArgMatches};
+use hilo_node::{Config, Node, NodeError};
 
 mod cli;
+mod config;
+mod derive;
 mod telemetry;
 
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
-    // Parse arguments.
-    let args = cli::NodeArgs::parse();
+    // Parse arguments and layer the config file and environment on top.
+    let matches = cli::NodeArgs::command().get_matches();
+    let mut args = cli::NodeArgs::from_arg_matches(&matches)?.layered(&matches)?;
+    let command = args.command.take();
+
+    // Print the configuration before telemetry is initialized to keep stdout clean.
+    if let Some(cli::NodeSubcommand::Config(config)) = &command {
+        return config.clone().run(args);
+    }
 
     // Initialize the telemetry stack.
-    telemetry::init(args.metrics_port)?;
+    let (log_filter, metrics) = telemetry::init(args.metrics_port)?;
     tracing::info!(
         "Running a standalone Hilo Node. Attributes validation: {}",
         args.validation_mode
     );
 
     // Construct the node from the config.
-    let cfg = Config::from(args);
-    let node = Node::from(cfg);
+    let checkpoint_hash = args.checkpoint_hash.clone();
+    let cfg = Config::try_from(args)?;
+    let sync_mode = cfg.sync_mode;
+    let node = Node::from(cfg)
+        .with_sync_mode(sync_mode)
+        .with_checkpoint_hash(checkpoint_hash)
+        .with_log_filter(log_filter);
+
+    // Dispatch on subcommand.
+    if let Some(cli::NodeSubcommand::Derive(derive)) = command {
+        tokio::spawn(metrics);
+        return derive.run(node).await;
+    }
+    let node = node.with_metrics(async move {
+        metrics.await.map_err(|e| NodeError::Metrics(format!("{e:?}")))
+    });
 
     // Run the node.
     if let Err(e) = node.run().await {
@@ -1,16 +1,19 @@
 use std::{io::IsTerminal, net::SocketAddr};
 
 use eyre::{bail, Result};
-use metrics_exporter_prometheus::PrometheusBuilder;
+use hilo_node::LogFilterHandle;
+use metrics_exporter_prometheus::{ExporterFuture, PrometheusBuilder};
 use tracing::{info, Level};
 use tracing_subscriber::{
-    fmt::Layer as FmtLayer, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
+    fmt::Layer as FmtLayer, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer,
 };
 
 /// Initialize the tracing stack and Prometheus metrics recorder.
 ///
-/// This function should be called at the beginning of the program.
-pub fn init(metrics_port: u16) -> Result<()> {
+/// This function should be called at the beginning of the program, within the Tokio runtime.
+/// Returns a [LogFilterHandle] that replaces the log filter at runtime, and the
+/// [ExporterFuture] serving the metrics, which must be polled for them to be served.
+pub fn init(metrics_port: u16) -> Result<(LogFilterHandle, ExporterFuture)> {
     let filter = EnvFilter::builder().with_default_directive("hilo=info".parse()?).from_env_lossy();
 
     // Whether to use ANSI formatting and colors in the console output.
@@ -29,6 +32,7 @@ pub fn init(metrics_port: u16) -> Result<()> {
         Err(_) => filter.max_level_hint().map_or(true, |max_level| max_level > Level::INFO),
     };
 
+    let (filter, filter_handle) = reload::Layer::new(filter);
     let std_layer = FmtLayer::new()
         .with_ansi(should_use_colors)
         .with_target(should_show_target)
@@ -40,11 +44,20 @@ pub fn init(metrics_port: u16) -> Result<()> {
     let prometheus_addr = SocketAddr::from(([0, 0, 0, 0], metrics_port));
     let builder = PrometheusBuilder::new().with_http_listener(prometheus_addr);
 
-    if let Err(e) = builder.install() {
-        bail!("failed to install Prometheus recorder: {:?}", e);
-    } else {
-        info!("Telemetry initialized. Serving Prometheus metrics at: http://{}", prometheus_addr);
-    }
+    let exporter = match builder.build() {
+        Ok((recorder, exporter)) => {
+            if let Err(e) = metrics::set_global_recorder(recorder) {
+                bail!("failed to install Prometheus recorder: {:?}", e);
+            }
+            exporter
+        }
+        Err(e) => bail!("failed to build Prometheus recorder: {:?}", e),
+    };
+    info!("Telemetry initialized. Serving Prometheus metrics at: http://{}", prometheus_addr);
 
-    Ok(())
+    let log_filter = LogFilterHandle::new(move |directives| {
+        let filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+        filter_handle.reload(filter).map_err(|e| e.to_string())
+    });
+    Ok((log_filter, exporter))
 }
@@ -0,0 +1,243 @@
+//! Contains on-disk [PipelineCursor] checkpoints.
+
+use alloy_primitives::B256;
+use kona_driver::{PipelineCursor, TipCursor};
+use op_alloy_protocol::{BlockInfo, L2BlockInfo};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// The interval at which the driver persists checkpoints.
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The default number of checkpoints kept on disk.
+pub const DEFAULT_CHECKPOINT_RETENTION: usize = 8;
+
+/// The file extension used for checkpoint files.
+const CHECKPOINT_EXTENSION: &str = "json";
+
+/// An error thrown by a [CheckpointStore] operation.
+#[derive(Debug, thiserror::Error)]
+pub enum CheckpointError {
+    /// An I/O error.
+    #[error("checkpoint io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// A checkpoint could not be encoded or decoded.
+    #[error("checkpoint encoding error: {0}")]
+    Encoding(#[from] serde_json::Error),
+}
+
+/// An L2 safe head tracked by the [PipelineCursor], keyed by the pipeline origin it was
+/// derived at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointTip {
+    /// The pipeline origin when the L2 safe head was derived.
+    pub l1_origin: BlockInfo,
+    /// The L2 safe head.
+    pub l2_safe_head: L2BlockInfo,
+    /// The output root of the L2 safe head.
+    #[serde(default)]
+    pub l2_safe_head_output_root: B256,
+}
+
+impl CheckpointTip {
+    /// Returns the [TipCursor] of the L2 safe head.
+    pub fn tip_cursor(&self) -> TipCursor {
+        TipCursor::new(self.l2_safe_head, Default::default(), self.l2_safe_head_output_root)
+    }
+}
+
+/// A snapshot of the [PipelineCursor].
+///
+/// The pipeline origin is persisted along with the tips within the channel timeout window,
+/// so the cursor is rebuilt exactly as it was instead of rewinding by the channel timeout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The L1 origin of the pipeline.
+    pub l1_origin: BlockInfo,
+    /// The L2 safe head.
+    pub l2_safe_head: L2BlockInfo,
+    /// The tips of the cursor, oldest first.
+    #[serde(default)]
+    pub tips: Vec<CheckpointTip>,
+}
+
+impl Checkpoint {
+    /// Rebuilds the [PipelineCursor] from the checkpoint, without rewinding the origin.
+    pub fn cursor(&self, channel_timeout: u64) -> PipelineCursor {
+        let mut cursor = PipelineCursor::new(channel_timeout, self.l1_origin);
+        for tip in &self.tips {
+            cursor.advance(tip.l1_origin, tip.tip_cursor());
+        }
+        // The pipeline may have moved on to a new origin since the last tip was derived.
+        match self.tips.last() {
+            Some(tip) if tip.l1_origin == self.l1_origin => {}
+            Some(tip) => cursor.advance(self.l1_origin, tip.tip_cursor()),
+            None => cursor.advance(
+                self.l1_origin,
+                TipCursor::new(self.l2_safe_head, Default::default(), Default::default()),
+            ),
+        }
+        cursor
+    }
+}
+
+/// A directory of [Checkpoint]s, one JSON file per checkpoint named after
+/// the L2 safe head block number.
+#[derive(Debug, Clone)]
+pub struct CheckpointStore {
+    /// The directory checkpoints are written to.
+    dir: PathBuf,
+    /// The maximum number of checkpoints to keep.
+    retention: usize,
+}
+
+impl CheckpointStore {
+    /// Opens a [CheckpointStore] in the given directory, creating it if needed.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, CheckpointError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, retention: DEFAULT_CHECKPOINT_RETENTION })
+    }
+
+    /// Sets the maximum number of checkpoints to keep on disk.
+    pub fn with_retention(mut self, retention: usize) -> Self {
+        self.retention = retention.max(1);
+        self
+    }
+
+    /// Returns the directory checkpoints are written to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Persists a [Checkpoint] and prunes the oldest checkpoints beyond the retention limit.
+    ///
+    /// The checkpoint is written to a temporary file first and then renamed so that a
+    /// crash mid-write never leaves a truncated checkpoint behind.
+    pub fn save(&self, checkpoint: &Checkpoint) -> Result<(), CheckpointError> {
+        let number = checkpoint.l2_safe_head.block_info.number;
+        let path = self.dir.join(format!("{number}.{CHECKPOINT_EXTENSION}"));
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(checkpoint)?)?;
+        fs::rename(&tmp, &path)?;
+
+        for (_, stale) in self.entries()?.into_iter().skip(self.retention) {
+            fs::remove_file(stale)?;
+        }
+        Ok(())
+    }
+
+    /// Loads all readable checkpoints, newest first.
+    ///
+    /// Checkpoints that fail to decode are skipped.
+    pub fn load(&self) -> Result<Vec<Checkpoint>, CheckpointError> {
+        let mut checkpoints = Vec::new();
+        for (_, path) in self.entries()? {
+            match fs::read(&path).map_err(CheckpointError::from).and_then(|bytes| {
+                serde_json::from_slice::<Checkpoint>(&bytes).map_err(CheckpointError::from)
+            }) {
+                Ok(checkpoint) => checkpoints.push(checkpoint),
+                Err(e) => warn!("Skipping unreadable checkpoint {:?}: {}", path, e),
+            }
+        }
+        Ok(checkpoints)
+    }
+
+    /// Returns the checkpoint files in the directory, newest first.
+    fn entries(&self) -> Result<Vec<(u64, PathBuf)>, CheckpointError> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == CHECKPOINT_EXTENSION) {
+                if let Some(number) =
+                    path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse().ok())
+                {
+                    entries.push((number, path));
+                }
+            }
+        }
+        entries.sort_by(|a, b| b.0.cmp(&a.0));
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint(number: u64) -> Checkpoint {
+        let l1_origin =
+            BlockInfo { number: number / 6, hash: B256::with_last_byte(1), ..Default::default() };
+        let l2_safe_head = L2BlockInfo {
+            block_info: BlockInfo { number, hash: B256::with_last_byte(2), ..Default::default() },
+            l1_origin: l1_origin.id(),
+            seq_num: 0,
+        };
+        let tip = CheckpointTip { l1_origin, l2_safe_head, l2_safe_head_output_root: B256::ZERO };
+        Checkpoint { l1_origin, l2_safe_head, tips: vec![tip] }
+    }
+
+    /// Opens a store in a temporary directory, removed when the directory is dropped.
+    fn store() -> (tempfile::TempDir, CheckpointStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CheckpointStore::open(dir.path()).unwrap();
+        (dir, store)
+    }
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let (_dir, store) = store();
+        let checkpoint = checkpoint(100);
+        store.save(&checkpoint).unwrap();
+        assert_eq!(store.load().unwrap(), vec![checkpoint]);
+    }
+
+    #[test]
+    fn test_checkpoint_retention() {
+        let (_dir, store) = store();
+        let store = store.with_retention(2);
+        let checkpoints = (1..=4).map(|i| checkpoint(i * 10)).collect::<Vec<_>>();
+        for checkpoint in &checkpoints {
+            store.save(checkpoint).unwrap();
+        }
+        assert_eq!(store.load().unwrap(), vec![checkpoints[3].clone(), checkpoints[2].clone()]);
+    }
+
+    #[test]
+    fn test_checkpoint_skips_corrupt_files() {
+        let (_dir, store) = store();
+        let checkpoint = checkpoint(5);
+        store.save(&checkpoint).unwrap();
+        fs::write(store.dir().join("9.json"), b"not a checkpoint").unwrap();
+        assert_eq!(store.load().unwrap(), vec![checkpoint]);
+    }
+
+    #[test]
+    fn test_checkpoint_without_tips() {
+        let (_dir, store) = store();
+        let mut checkpoint = checkpoint(7);
+        let mut legacy = serde_json::to_value(&checkpoint).unwrap();
+        legacy.as_object_mut().unwrap().remove("tips");
+        fs::write(store.dir().join("7.json"), serde_json::to_vec(&legacy).unwrap()).unwrap();
+        checkpoint.tips.clear();
+        assert_eq!(store.load().unwrap(), vec![checkpoint.clone()]);
+
+        let cursor = checkpoint.cursor(10);
+        assert_eq!(cursor.origin(), checkpoint.l1_origin);
+        assert_eq!(*cursor.l2_safe_head(), checkpoint.l2_safe_head);
+    }
+
+    #[test]
+    fn test_checkpoint_cursor_keeps_origin() {
+        let mut checkpoint = checkpoint(60);
+        checkpoint.l1_origin =
+            BlockInfo { number: 12, hash: B256::with_last_byte(3), ..Default::default() };
+        let cursor = checkpoint.cursor(10);
+        assert_eq!(cursor.origin(), checkpoint.l1_origin);
+        assert_eq!(*cursor.l2_safe_head(), checkpoint.l2_safe_head);
+    }
+}
@@ -0,0 +1,29 @@
+//! Commands accepted by a running [crate::HiloDriver].
+
+use alloy_rpc_types_engine::{ForkchoiceState, PayloadStatus};
+use tokio::sync::oneshot;
+
+/// The number of commands buffered before senders wait for the driver.
+pub const DRIVER_COMMAND_CHANNEL_SIZE: usize = 16;
+
+/// An operational command sent to a running [crate::HiloDriver].
+///
+/// Commands are handled between derivation steps, in the same loop that handles
+/// L1 chain notifications.
+#[derive(Debug)]
+pub enum DriverCommand {
+    /// Resets the derivation pipeline to the current L2 safe head, restarting from its L1
+    /// origin rewound by the channel timeout.
+    ResetPipeline,
+    /// Stops stepping the derivation pipeline. L1 notifications are still processed.
+    PauseDerivation,
+    /// Resumes stepping the derivation pipeline.
+    ResumeDerivation,
+    /// Sends a forkchoice update to the execution client.
+    ForceForkchoice {
+        /// The forkchoice state to send. Defaults to the engine controller's heads.
+        state: Option<ForkchoiceState>,
+        /// The channel the payload status of the update is sent back on.
+        reply: oneshot::Sender<Result<PayloadStatus, String>>,
+    },
+}
@@ -6,7 +6,7 @@ use kona_driver::PipelineCursor;
 use op_alloy_genesis::RollupConfig;
 use op_alloy_protocol::{BatchValidationProvider, BlockInfo, L2BlockInfo};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 use url::Url;
 
 use hilo_providers_alloy::{
@@ -14,6 +14,11 @@ use hilo_providers_alloy::{
     OnlineBlobProviderWithFallback,
 };
 
+use crate::{Checkpoint, CheckpointStore, StandaloneContext};
+
+/// The interval at which the L1 and L2 clients are polled in devnet mode.
+pub const DEVNET_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 /// An error thrown by a [Config] operation.
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
@@ -26,6 +31,20 @@ pub enum ConfigError {
     /// An L1 chain provider error.
     #[error("L1 chain provider error: {0}")]
     ChainProvider(String),
+    /// A checkpoint store error.
+    #[error("checkpoint error: {0}")]
+    Checkpoint(String),
+}
+
+/// The L1 chain parameters, loaded from a file for devnets instead of the beacon node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct L1ChainSpec {
+    /// The L1 chain ID.
+    pub chain_id: u64,
+    /// The beacon chain genesis time.
+    pub genesis_time: u64,
+    /// The beacon chain slot duration in seconds.
+    pub seconds_per_slot: u64,
 }
 
 /// The global node configuration.
@@ -53,6 +72,15 @@ pub struct Config {
     pub jwt_secret: JwtSecret,
     /// The cache size for in-memory providers.
     pub cache_size: usize,
+    /// The data directory. Pipeline checkpoints are persisted here when set.
+    #[serde(default)]
+    pub data_dir: Option<PathBuf>,
+    /// Whether the node runs against a local devnet.
+    #[serde(default)]
+    pub devnet: bool,
+    /// The L1 chain spec. Queried from the beacon node if unset.
+    #[serde(default)]
+    pub l1_chain_spec: Option<L1ChainSpec>,
 }
 
 fn as_hex<S>(v: &JwtSecret, serializer: S) -> Result<S::Ok, S::Error>
@@ -72,21 +100,54 @@ where
 }
 
 impl Config {
+    /// Returns the interval at which the L1 and L2 clients are polled for new blocks.
+    pub const fn poll_interval(&self) -> Duration {
+        if self.devnet {
+            DEVNET_POLL_INTERVAL
+        } else {
+            StandaloneContext::DEFAULT_POLL_INTERVAL
+        }
+    }
+
     /// Construct an [OnlineBlobProviderWithFallback] from the [Config].
+    ///
+    /// The blob archiver is used as the fallback provider if configured. In devnet mode,
+    /// an unreachable archiver is skipped since devnet blobs never expire from the beacon node.
     pub async fn blob_provider(
         &self,
     ) -> Result<OnlineBlobProviderWithFallback<OnlineBeaconClient, OnlineBeaconClient>, ConfigError>
     {
         let beacon_client = OnlineBeaconClient::new_http(String::from(self.l1_beacon_url.clone()));
-        let genesis = Some(self.rollup_config.genesis.l1.number);
-        let slot_interval = beacon_client
-            .config_spec()
-            .await
-            .map_err(|e| ConfigError::Beacon(e.to_string()))?
-            .data
-            .seconds_per_slot;
-        let blob = OnlineBlobProvider::new(beacon_client, genesis, Some(slot_interval));
-        Ok(OnlineBlobProviderWithFallback::new(blob, None))
+        let blob = match self.l1_chain_spec {
+            Some(spec) => OnlineBlobProvider::new(
+                beacon_client,
+                Some(spec.genesis_time),
+                Some(spec.seconds_per_slot),
+            ),
+            None => {
+                let slot_interval = beacon_client
+                    .config_spec()
+                    .await
+                    .map_err(|e| ConfigError::Beacon(e.to_string()))?
+                    .data
+                    .seconds_per_slot;
+                OnlineBlobProvider::new(beacon_client, None, Some(slot_interval))
+            }
+        };
+
+        let Some(url) = &self.blob_archiver_url else {
+            return Ok(OnlineBlobProviderWithFallback::new(blob, None));
+        };
+        let archiver = OnlineBeaconClient::new_http(String::from(url.clone()));
+        if self.devnet {
+            if let Err(e) = archiver.beacon_blob_side_cars(0, &[]).await {
+                if e.is_connect() || e.is_timeout() {
+                    warn!("Blob archiver at {} is unreachable, continuing without it: {}", url, e);
+                    return Ok(OnlineBlobProviderWithFallback::new(blob, None));
+                }
+            }
+        }
+        Ok(OnlineBlobProviderWithFallback::new(blob, Some(archiver)))
     }
 
     /// Returns an [AlloyChainProvider] from the configured provider endpoints.
@@ -110,8 +171,15 @@ impl Config {
             .latest_block_number()
             .await
             .map_err(|e| ConfigError::L2ChainProvider(e.to_string()))?;
+        self.tip_at(latest_block_number).await
+    }
+
+    /// Returns the tip at the given L2 block number.
+    /// The tip includes the L1 origin block info and the L2 block info.
+    pub async fn tip_at(&self, number: u64) -> Result<(BlockInfo, L2BlockInfo), ConfigError> {
+        let mut l2_provider = self.l2_provider();
         let l2_block_info = l2_provider
-            .l2_block_info_by_number(latest_block_number)
+            .l2_block_info_by_number(number)
             .await
             .map_err(|e| ConfigError::L2ChainProvider(e.to_string()))?;
 
@@ -128,7 +196,87 @@ impl Config {
     pub async fn tip_cursor(&self) -> Result<PipelineCursor, ConfigError> {
         // Load the safe head info.
         let (origin, safe_head_info) = self.safe_tip().await?;
+        self.cursor_from(origin, safe_head_info).await
+    }
+
+    /// Opens the [CheckpointStore] in the data directory, if one is configured.
+    pub fn checkpoint_store(&self) -> Result<Option<CheckpointStore>, ConfigError> {
+        let Some(dir) = &self.data_dir else {
+            return Ok(None);
+        };
+        CheckpointStore::open(dir.join("checkpoints"))
+            .map(Some)
+            .map_err(|e| ConfigError::Checkpoint(e.to_string()))
+    }
+
+    /// Constructs a [PipelineCursor] from the newest persisted [Checkpoint] that is still
+    /// consistent with the L1 chain and the execution layer.
+    ///
+    /// Falls back to [Config::tip_cursor] if no data directory is configured or no
+    /// checkpoint is usable.
+    pub async fn resume_cursor(&self) -> Result<PipelineCursor, ConfigError> {
+        match self.resume_checkpoint().await? {
+            Some(checkpoint) => Ok(checkpoint.cursor(
+                self.rollup_config.channel_timeout(checkpoint.l2_safe_head.block_info.timestamp),
+            )),
+            None => self.tip_cursor().await,
+        }
+    }
+
+    /// Returns the newest persisted [Checkpoint] that is still consistent with the L1 chain
+    /// and the execution layer, if a data directory is configured.
+    pub async fn resume_checkpoint(&self) -> Result<Option<Checkpoint>, ConfigError> {
+        let Some(store) = self.checkpoint_store()? else {
+            return Ok(None);
+        };
+        let checkpoints = store.load().map_err(|e| ConfigError::Checkpoint(e.to_string()))?;
+        for checkpoint in checkpoints {
+            if self.is_canonical(&checkpoint).await {
+                info!(
+                    "Resuming from checkpoint at L2 block {} (L1 origin {})",
+                    checkpoint.l2_safe_head.block_info.number, checkpoint.l1_origin.number
+                );
+                return Ok(Some(checkpoint));
+            }
+            warn!(
+                "Discarding checkpoint at L2 block {}: no longer canonical",
+                checkpoint.l2_safe_head.block_info.number
+            );
+        }
+        Ok(None)
+    }
 
+    /// Returns whether the [Checkpoint]'s pipeline origin is still canonical according to the
+    /// L1 provider, and its L2 safe head according to the execution layer.
+    ///
+    /// The older tips are ancestors of both, so they are canonical too.
+    async fn is_canonical(&self, checkpoint: &Checkpoint) -> bool {
+        let mut l1_provider = self.l1_chain_provider();
+        match l1_provider.block_info_by_number(checkpoint.l1_origin.number).await {
+            Ok(l1_block) if l1_block.hash == checkpoint.l1_origin.hash => {}
+            _ => return false,
+        }
+
+        let mut l2_provider = self.l2_provider();
+        let number = checkpoint.l2_safe_head.block_info.number;
+        matches!(
+            l2_provider.l2_block_info_by_number(number).await,
+            Ok(l2_block) if l2_block.block_info.hash == checkpoint.l2_safe_head.block_info.hash
+        )
+    }
+
+    /// Constructs a [PipelineCursor] that starts derivation from the given L2 block number.
+    pub async fn cursor_at(&self, number: u64) -> Result<PipelineCursor, ConfigError> {
+        let (origin, safe_head_info) = self.tip_at(number).await?;
+        self.cursor_from(origin, safe_head_info).await
+    }
+
+    /// Constructs a [PipelineCursor] from the L1 origin of the given L2 safe head.
+    async fn cursor_from(
+        &self,
+        origin: BlockInfo,
+        safe_head_info: L2BlockInfo,
+    ) -> Result<PipelineCursor, ConfigError> {
         // Calculate the channel timeout
         let channel_timeout =
             self.rollup_config.channel_timeout(safe_head_info.block_info.timestamp);
@@ -151,3 +299,65 @@ impl Config {
         Ok(cursor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::{block_info, l2_genesis, StandIn, GENESIS_L1},
+        CheckpointTip,
+    };
+    use alloy_primitives::B256;
+
+    /// Returns a checkpoint of the stand-in L2 genesis block, with a tip at each of the given
+    /// L1 blocks.
+    fn checkpoint(config: &Config, l1_origins: &[u64]) -> Checkpoint {
+        let l2_safe_head =
+            L2BlockInfo::from_block_and_genesis(&l2_genesis(), &config.rollup_config.genesis)
+                .unwrap();
+        let tips = l1_origins
+            .iter()
+            .map(|&number| CheckpointTip {
+                l1_origin: block_info(number),
+                l2_safe_head,
+                l2_safe_head_output_root: Default::default(),
+            })
+            .collect::<Vec<_>>();
+        Checkpoint { l1_origin: tips.last().unwrap().l1_origin, l2_safe_head, tips }
+    }
+
+    #[tokio::test]
+    async fn test_resume_restores_origin() {
+        let stand_in = StandIn::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config { data_dir: Some(dir.path().to_path_buf()), ..stand_in.config() };
+        let checkpoint = checkpoint(&config, &[GENESIS_L1 + 18, GENESIS_L1 + 20]);
+        config.checkpoint_store().unwrap().unwrap().save(&checkpoint).unwrap();
+
+        let cursor = config.resume_cursor().await.unwrap();
+        assert_eq!(cursor.origin(), block_info(GENESIS_L1 + 20));
+        assert_eq!(*cursor.l2_safe_head(), checkpoint.l2_safe_head);
+    }
+
+    #[tokio::test]
+    async fn test_resume_discards_reorged_checkpoint() {
+        let stand_in = StandIn::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config { data_dir: Some(dir.path().to_path_buf()), ..stand_in.config() };
+        let mut checkpoint = checkpoint(&config, &[GENESIS_L1 + 20]);
+        checkpoint.l1_origin.hash = B256::repeat_byte(0xff);
+        config.checkpoint_store().unwrap().unwrap().save(&checkpoint).unwrap();
+
+        assert_eq!(config.resume_checkpoint().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_cursor_rewind_clamped_to_genesis() {
+        let stand_in = StandIn::start().await;
+        let config = stand_in.config();
+        let checkpoint = checkpoint(&config, &[GENESIS_L1]);
+        let cursor = config.cursor_at(0).await.unwrap();
+        assert_eq!(cursor.origin(), block_info(GENESIS_L1));
+        assert_eq!(*cursor.l2_safe_head(), checkpoint.l2_safe_head);
+    }
+}
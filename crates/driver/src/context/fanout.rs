@@ -0,0 +1,117 @@
+//! Fanout Context
+
+use alloy_eips::BlockNumHash;
+use async_trait::async_trait;
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc,
+};
+use tracing::error;
+
+use super::{ChainNotification, Context};
+
+/// A context that receives [ChainNotification]s fanned out from a single shared
+/// context, allowing multiple drivers to follow the same L1 chain.
+///
+/// Processed tip events are forwarded to the owner of the shared context, tagged
+/// with the L2 chain ID of the driver that processed them.
+///
+/// A driver that falls too far behind the shared context to receive every notification
+/// may have missed a reorg, so the context stops producing notifications once it lags,
+/// and the driver fails rather than derive from a stale view of the L1 chain.
+#[derive(Debug)]
+pub struct FanoutContext {
+    /// The L2 chain ID of the driver using this context.
+    chain_id: u64,
+    /// Receives notifications broadcast from the shared context.
+    notifications: broadcast::Receiver<ChainNotification>,
+    /// Sends processed tips back to the owner of the shared context.
+    processed_tips: mpsc::UnboundedSender<(u64, BlockNumHash)>,
+}
+
+impl FanoutContext {
+    /// Creates a new [FanoutContext] for the given L2 chain ID.
+    pub const fn new(
+        chain_id: u64,
+        notifications: broadcast::Receiver<ChainNotification>,
+        processed_tips: mpsc::UnboundedSender<(u64, BlockNumHash)>,
+    ) -> Self {
+        Self { chain_id, notifications, processed_tips }
+    }
+
+    /// Returns the L2 chain ID of the driver using this context.
+    pub const fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+}
+
+#[async_trait]
+impl Context for FanoutContext {
+    async fn recv_notification(&mut self) -> Option<ChainNotification> {
+        match self.notifications.recv().await {
+            Ok(notification) => Some(notification),
+            Err(RecvError::Lagged(skipped)) => {
+                error!("Chain {} lagged behind by {} L1 notifications", self.chain_id, skipped);
+                None
+            }
+            Err(RecvError::Closed) => None,
+        }
+    }
+
+    fn send_processed_tip_event(&mut self, tip: BlockNumHash) {
+        _ = self.processed_tips.send((self.chain_id, tip));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Headers;
+    use alloy_rpc_types_eth::Header;
+
+    #[tokio::test]
+    async fn test_fanout_notifications() {
+        let (notifications, _) = broadcast::channel(16);
+        let (tips_tx, mut tips_rx) = mpsc::unbounded_channel();
+        let mut op = FanoutContext::new(10, notifications.subscribe(), tips_tx.clone());
+        let mut base = FanoutContext::new(8453, notifications.subscribe(), tips_tx);
+
+        let header = Header {
+            inner: alloy_consensus::Header { number: 1, ..Default::default() },
+            ..Default::default()
+        };
+        notifications.send(ChainNotification::New { new_blocks: Headers::from(header) }).unwrap();
+
+        let tip = op.recv_notification().await.unwrap().new_chain().unwrap().tip();
+        assert_eq!(tip.number, 1);
+        assert_eq!(base.recv_notification().await.unwrap().new_chain().unwrap().tip(), tip);
+
+        op.send_processed_tip_event(tip);
+        base.send_processed_tip_event(tip);
+        assert_eq!(tips_rx.recv().await, Some((10, tip)));
+        assert_eq!(tips_rx.recv().await, Some((8453, tip)));
+
+        drop(notifications);
+        assert!(op.recv_notification().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fanout_lagged() {
+        let (notifications, _) = broadcast::channel(1);
+        let (tips_tx, _tips_rx) = mpsc::unbounded_channel();
+        let mut op = FanoutContext::new(10, notifications.subscribe(), tips_tx);
+
+        for number in [1, 2] {
+            let header = Header {
+                inner: alloy_consensus::Header { number, ..Default::default() },
+                ..Default::default()
+            };
+            notifications
+                .send(ChainNotification::New { new_blocks: Headers::from(header) })
+                .unwrap();
+        }
+
+        // The first notification was overwritten before it was received.
+        assert!(op.recv_notification().await.is_none());
+    }
+}
@@ -1,5 +1,11 @@
 //! C
 
+mod fanout;
+pub use fanout::FanoutContext;
+
+mod replay;
+pub use replay::ReplayContext;
+
 mod standalone;
 pub use standalone::StandaloneContext;
 
@@ -0,0 +1,48 @@
+//! Replay Context
+
+use alloy_eips::BlockNumHash;
+use alloy_rpc_types_eth::Header;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+
+use super::{ChainNotification, Context, Headers};
+
+/// A context that replays a fixed sequence of L1 headers, yielding one
+/// [ChainNotification::New] per header and then closing.
+///
+/// Processed tip events are recorded so that tests can assert on them.
+#[derive(Debug, Default)]
+pub struct ReplayContext {
+    /// The headers that have not been replayed yet.
+    headers: VecDeque<Header>,
+    /// The processed tips reported by the driver.
+    processed_tips: Vec<BlockNumHash>,
+}
+
+impl ReplayContext {
+    /// Creates a new [ReplayContext] from the given L1 headers.
+    pub fn new(headers: impl IntoIterator<Item = alloy_consensus::Header>) -> Self {
+        let headers = headers
+            .into_iter()
+            .map(|inner| Header { hash: inner.hash_slow(), inner, ..Default::default() })
+            .collect();
+        Self { headers, processed_tips: Vec::new() }
+    }
+
+    /// Returns the processed tips reported by the driver, in order.
+    pub fn processed_tips(&self) -> &[BlockNumHash] {
+        &self.processed_tips
+    }
+}
+
+#[async_trait]
+impl Context for ReplayContext {
+    async fn recv_notification(&mut self) -> Option<ChainNotification> {
+        let header = self.headers.pop_front()?;
+        Some(ChainNotification::New { new_blocks: Headers::from(header) })
+    }
+
+    fn send_processed_tip_event(&mut self, tip: BlockNumHash) {
+        self.processed_tips.push(tip);
+    }
+}
@@ -39,16 +39,38 @@ pub struct StandaloneContext {
     /// Cache of blocks that might be reorged out. In normal conditions,
     /// this cache will not grow beyond [`FINALIZATION_TIMEOUT`] keys.
     reorg_cache: BTreeMap<BlockNumber, HashMap<B256, Header>>,
-    /// Handle to the background task that fetches and processes new blocks.
-    _handle: JoinHandle<()>,
+    /// Handle to the background task that fetches and processes new blocks,
+    /// until it is taken by a supervisor.
+    handle: Option<JoinHandle<()>>,
 }
 
 impl StandaloneContext {
+    /// The default interval at which new blocks are polled over HTTP.
+    pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
     /// Create a new standalone context that polls for new chains.
     pub async fn new(l1_rpc_url: Url) -> TransportResult<Self> {
+        Self::with_poll_interval(l1_rpc_url, Self::DEFAULT_POLL_INTERVAL).await
+    }
+
+    /// Takes the handle to the background task that fetches new blocks, so its
+    /// owner can detect the task stopping.
+    ///
+    /// If the task stops, [Context::recv_notification] returns `None` once the
+    /// already fetched blocks are drained.
+    pub fn take_handle(&mut self) -> Option<JoinHandle<()>> {
+        self.handle.take()
+    }
+
+    /// Create a new standalone context that polls for new chains at the given interval.
+    /// The interval only applies to HTTP, subscriptions are used for other schemes.
+    pub async fn with_poll_interval(
+        l1_rpc_url: Url,
+        poll_interval: Duration,
+    ) -> TransportResult<Self> {
         if l1_rpc_url.scheme().contains("http") {
             debug!("Polling for new blocks via HTTP");
-            Self::with_http_poller(l1_rpc_url).await
+            Self::with_http_poller(l1_rpc_url, poll_interval).await
         } else if l1_rpc_url.scheme().contains("ws") {
             debug!("Subscribing to new blocks via websocket");
             Self::with_ws_subscriber(l1_rpc_url).await
@@ -61,11 +83,12 @@ impl StandaloneContext {
     }
 
     /// Create a new standalone context that polls for new blocks via HTTP.
-    async fn with_http_poller(l1_rpc_url: Url) -> TransportResult<Self> {
+    async fn with_http_poller(l1_rpc_url: Url, poll_interval: Duration) -> TransportResult<Self> {
         let client = ReqwestProvider::<Ethereum>::new_http(l1_rpc_url);
+        client.client().set_poll_interval(poll_interval);
         let (new_block_tx, new_block_rx) = mpsc::channel(128);
 
-        let _handle = match client.watch_blocks().await {
+        let handle = match client.watch_blocks().await {
             Ok(new_block_hashes) => tokio::spawn(async move {
                 let mut stream = new_block_hashes.into_stream();
                 while let Some(hashes) = stream.next().await {
@@ -106,7 +129,7 @@ impl StandaloneContext {
                             Ok(Some(block)) => {
                                 if hash == block.header.hash {
                                     // If the latest hash hasn't changed, wait before polling again
-                                    tokio::time::sleep(Duration::from_secs(2)).await;
+                                    tokio::time::sleep(poll_interval).await;
                                     continue;
                                 }
                                 hash = block.header.hash;
@@ -133,13 +156,13 @@ impl StandaloneContext {
                                 error!("Failed to get latest block: {:?}", e);
                             }
                         };
-                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        tokio::time::sleep(poll_interval).await;
                     }
                 })
             }
         };
 
-        Ok(Self::with_defaults(new_block_rx, _handle))
+        Ok(Self::with_defaults(new_block_rx, handle))
     }
 
     /// Create a new standalone context that subscribes to new blocks via websocket.
@@ -149,7 +172,7 @@ impl StandaloneContext {
         let (new_block_tx, new_block_rx) = mpsc::channel(128);
 
         let mut block_sub = client.subscribe_blocks().await?.into_stream();
-        let _handle = tokio::spawn(async move {
+        let handle = tokio::spawn(async move {
             while let Some(block) = block_sub.next().await {
                 if let Err(e) = new_block_tx.try_send(block) {
                     error!("Failed to send new block to channel: {:?}", e);
@@ -157,7 +180,7 @@ impl StandaloneContext {
             }
         });
 
-        Ok(Self::with_defaults(new_block_rx, _handle))
+        Ok(Self::with_defaults(new_block_rx, handle))
     }
 
     /// Create a new standalone context that subscribes to new blocks via IPC.
@@ -167,7 +190,7 @@ impl StandaloneContext {
         let (new_block_tx, new_block_rx) = mpsc::channel(128);
 
         let mut block_sub = client.subscribe_blocks().await?.into_stream();
-        let _handle = tokio::spawn(async move {
+        let handle = tokio::spawn(async move {
             while let Some(block) = block_sub.next().await {
                 if let Err(e) = new_block_tx.try_send(block) {
                     error!("Failed to send new block to channel: {:?}", e);
@@ -175,14 +198,14 @@ impl StandaloneContext {
             }
         });
 
-        Ok(Self::with_defaults(new_block_rx, _handle))
+        Ok(Self::with_defaults(new_block_rx, handle))
     }
 
     /// Create a new standalone context with the given new block receiver and handle.
-    fn with_defaults(new_block_rx: mpsc::Receiver<Header>, _handle: JoinHandle<()>) -> Self {
+    fn with_defaults(new_block_rx: mpsc::Receiver<Header>, handle: JoinHandle<()>) -> Self {
         Self {
             new_block_rx,
-            _handle,
+            handle: Some(handle),
             l1_tip: BlockNumHash::default(),
             processed_tip: BlockNumHash::default(),
             reorg_cache: BTreeMap::new(),
@@ -225,41 +248,124 @@ impl Context for StandaloneContext {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::replay::fixtures::batch_fixture;
+    use alloy_primitives::U256;
+    use alloy_rpc_types_eth::Block;
+    use jsonrpsee::{
+        core::{traits::IdProvider, SubscriptionResult},
+        server::{Server, ServerHandle},
+        types::{ErrorObjectOwned, SubscriptionId},
+        RpcModule, SubscriptionMessage,
+    };
+    use std::{
+        net::SocketAddr,
+        sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    };
+
+    /// Hands out subscription ids as hex quantities, as the alloy client expects.
+    #[derive(Debug, Default)]
+    struct HexIdProvider(AtomicU64);
+
+    impl IdProvider for HexIdProvider {
+        fn next_id(&self) -> SubscriptionId<'static> {
+            let id = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+            SubscriptionId::Str(format!("{id:#x}").into())
+        }
+    }
 
-    #[tokio::test]
-    async fn test_http_poller() -> eyre::Result<()> {
-        let url = Url::parse("http://localhost:8545")?;
+    /// Returns the L1 headers of the replay fixture carrying batches.
+    async fn fixture_headers() -> Vec<Header> {
+        let fixture = batch_fixture().await;
+        fixture
+            .l1_blocks
+            .into_iter()
+            .map(|block| Header {
+                hash: block.header.hash_slow(),
+                inner: block.header,
+                ..Default::default()
+            })
+            .collect()
+    }
 
-        // skip test if we can't connect to the node
-        if reqwest::get(url.clone()).await.is_err() {
-            return Ok(());
-        }
+    /// Starts a stand-in L1 node announcing the given headers once, through a block filter
+    /// over HTTP and a `newHeads` subscription over websocket.
+    async fn stand_in_l1(headers: Vec<Header>) -> (SocketAddr, ServerHandle) {
+        let mut module = RpcModule::new((headers, AtomicBool::new(false)));
+        module.register_method("eth_newBlockFilter", |_, _, _| U256::from(1)).unwrap();
+        module
+            .register_method("eth_getFilterChanges", |_, state, _| {
+                let (headers, polled) = &*state;
+                if polled.swap(true, Ordering::SeqCst) {
+                    return Vec::<B256>::new();
+                }
+                headers.iter().map(|header| header.hash).collect()
+            })
+            .unwrap();
+        module
+            .register_method("eth_getBlockByHash", |params, state, _| {
+                let (hash, _): (B256, bool) = params.parse()?;
+                let header =
+                    state.0.iter().find(|header| header.hash == hash).cloned().ok_or_else(
+                        || ErrorObjectOwned::owned(-32000, "block not found", None::<()>),
+                    )?;
+                Ok::<_, ErrorObjectOwned>(Block { header, ..Default::default() })
+            })
+            .unwrap();
+        module
+            .register_subscription(
+                "eth_subscribe",
+                "eth_subscription",
+                "eth_unsubscribe",
+                |_, pending, state, _| async move {
+                    let sink = pending.accept().await?;
+                    for header in &state.0 {
+                        sink.send(SubscriptionMessage::from_json(header)?).await?;
+                    }
+                    sink.closed().await;
+                    SubscriptionResult::Ok(())
+                },
+            )
+            .unwrap();
+
+        let server = Server::builder()
+            .set_id_provider(HexIdProvider::default())
+            .build("127.0.0.1:0")
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        (addr, server.start(module))
+    }
 
-        let mut ctx = StandaloneContext::new(url).await?;
+    /// Asserts that the context notifies each of the headers, in order.
+    async fn assert_notified(ctx: &mut StandaloneContext, headers: &[Header]) {
+        for header in headers {
+            let notif = ctx.recv_notification().await.unwrap();
+            assert!(notif.reverted_chain().is_none());
+            let tip = notif.new_chain().unwrap().tip();
+            assert_eq!(tip, BlockNumHash { number: header.number, hash: header.hash });
+        }
+    }
 
-        let notif = ctx.recv_notification().await.unwrap();
+    #[tokio::test]
+    async fn test_http_poller() -> eyre::Result<()> {
+        let headers = fixture_headers().await;
+        let (addr, _handle) = stand_in_l1(headers.clone()).await;
 
-        assert!(notif.new_chain().is_some());
-        assert!(notif.reverted_chain().is_none());
+        let url = Url::parse(&format!("http://{addr}"))?;
+        let mut ctx = StandaloneContext::with_poll_interval(url, Duration::from_millis(10)).await?;
+        assert_notified(&mut ctx, &headers).await;
 
         Ok(())
     }
 
     #[tokio::test]
     async fn test_ws_subscriber() -> eyre::Result<()> {
-        let url = Url::parse("ws://localhost:8546")?;
-
-        // skip test if we can't connect to the node
-        if reqwest::get(url.clone()).await.is_err() {
-            return Ok(());
-        }
+        let headers = fixture_headers().await;
+        let (addr, _handle) = stand_in_l1(headers.clone()).await;
 
+        let url = Url::parse(&format!("ws://{addr}"))?;
         let mut ctx = StandaloneContext::new(url).await?;
-
-        let notif = ctx.recv_notification().await.unwrap();
-
-        assert!(notif.new_chain().is_some());
-        assert!(notif.reverted_chain().is_none());
+        assert_notified(&mut ctx, &headers).await;
 
         Ok(())
     }
@@ -300,6 +406,23 @@ mod tests {
         ctx.send_processed_tip_event(BlockNumHash { number: 100, ..Default::default() });
     }
 
+    #[tokio::test]
+    async fn test_stopped_task_closes_notifications() {
+        let (tx, rx) = mpsc::channel(128);
+        let handle = tokio::spawn(async move {
+            tx.try_send(create_mock_header(1)).unwrap();
+            panic!("poller failed");
+        });
+        let mut ctx = StandaloneContext::with_defaults(rx, handle);
+
+        let handle = ctx.take_handle().unwrap();
+        assert!(ctx.take_handle().is_none());
+        assert!(handle.await.unwrap_err().is_panic());
+
+        assert!(ctx.recv_notification().await.is_some());
+        assert!(ctx.recv_notification().await.is_none());
+    }
+
     // Helper function to create a mock Header
     fn create_mock_header(number: u64) -> Header {
         Header {
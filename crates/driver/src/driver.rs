@@ -1,20 +1,35 @@
 //! Contains the core `HiloDriver`.
 
-use alloy_provider::ReqwestProvider;
+use alloy_eips::BlockNumberOrTag;
+use alloy_provider::{Provider, ReqwestProvider};
+use alloy_rpc_types_eth::BlockTransactionsKind;
 use alloy_transport::TransportResult;
+use futures::FutureExt;
 use kona_derive::{errors::PipelineErrorKind, traits::SignalReceiver, types::ResetSignal};
 use kona_driver::{Driver, PipelineCursor, TipCursor};
-use std::sync::Arc;
+use op_alloy_protocol::{BlockInfo, L2BlockInfo};
+use op_alloy_rpc_types_engine::OpNetworkPayloadEnvelope;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{broadcast, mpsc, mpsc::error::TryRecvError};
 
-use hilo_engine::EngineController;
-use hilo_providers_alloy::AlloyL2ChainProvider;
+use hilo_engine::{Engine, EngineController};
+use hilo_providers_alloy::{AlloyL2ChainProvider, DurableBlobProvider};
 use hilo_providers_local::InMemoryChainProvider;
 
 use crate::{
-    ChainNotification, Config, ConfigError, Context, HiloDerivationPipeline, HiloPipeline,
-    StandaloneContext,
+    events::DRIVER_EVENT_CHANNEL_SIZE, ChainNotification, Checkpoint, CheckpointStore,
+    CheckpointTip, Config, ConfigError, Context, DriverCommand, DriverEvent, HiloChainProvider,
+    HiloDerivationPipeline, HiloPipeline, StandaloneContext, CHECKPOINT_INTERVAL,
+    DRIVER_COMMAND_CHANNEL_SIZE,
 };
 
+/// The interval at which the finalized L1 block is polled, once per L1 epoch of 32 slots.
+pub const FINALITY_POLL_INTERVAL: Duration = Duration::from_secs(384);
+
 /// A driver from [kona_driver] that uses hilo-types.
 pub type KonaDriver = Driver<EngineController, HiloPipeline, HiloDerivationPipeline>;
 
@@ -33,6 +48,54 @@ pub enum DriverError {
     /// Shutdown signal received.
     #[error("shutdown signal received")]
     Shutdown,
+    /// The context stopped sending L1 chain notifications.
+    #[error("L1 chain notifications stopped")]
+    NotificationsClosed,
+    /// The derivation target is not ahead of the starting L2 block.
+    #[error("derivation target {target} is not ahead of the starting L2 block {start}")]
+    InvalidTarget {
+        /// The requested target L2 block number.
+        target: u64,
+        /// The L2 block number derivation would start from.
+        start: u64,
+    },
+}
+
+/// A summary of a bounded derivation run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DerivationSummary {
+    /// The L2 safe head block number derivation started from.
+    pub l2_start: u64,
+    /// The L2 safe head block number derivation stopped at.
+    pub l2_end: u64,
+    /// The L1 origin of the pipeline when derivation started.
+    pub l1_start: BlockInfo,
+    /// The L1 origin of the pipeline when derivation stopped.
+    pub l1_end: BlockInfo,
+    /// The wall-clock duration of the run.
+    pub duration: Duration,
+}
+
+impl DerivationSummary {
+    /// Returns the number of L2 blocks derived during the run.
+    pub const fn blocks(&self) -> u64 {
+        self.l2_end.saturating_sub(self.l2_start)
+    }
+}
+
+impl std::fmt::Display for DerivationSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "derived {} L2 blocks ({} -> {}) from L1 blocks {} -> {} in {:.2?}",
+            self.blocks(),
+            self.l2_start,
+            self.l2_end,
+            self.l1_start.number,
+            self.l1_end.number,
+            self.duration
+        )
+    }
 }
 
 /// HiloDriver is a wrapper around the `Driver` that
@@ -43,12 +106,36 @@ pub struct HiloDriver<C: Context> {
     pub ctx: C,
     /// The driver config.
     pub cfg: Config,
+    /// The sender that [DriverEvent]s are published to.
+    events: broadcast::Sender<DriverEvent>,
+    /// The last finalized L2 head published to subscribers.
+    finalized_head: BlockInfo,
+    /// The latest L2 safe head derived from each L1 block, keyed by L1 block number,
+    /// until the L1 block is finalized.
+    safe_heads: BTreeMap<u64, L2BlockInfo>,
+    /// The tips of the cursor within the channel timeout, oldest first, persisted with
+    /// each [Checkpoint].
+    tips: VecDeque<CheckpointTip>,
+    /// The sender handed out to issue [DriverCommand]s.
+    command_sender: mpsc::Sender<DriverCommand>,
+    /// The receiver [DriverCommand]s are handled from.
+    commands: mpsc::Receiver<DriverCommand>,
+    /// Whether derivation was paused by a [DriverCommand::PauseDerivation].
+    paused: bool,
+    /// An optional receiver of unsafe blocks gossiped over p2p.
+    unsafe_payloads: Option<mpsc::Receiver<OpNetworkPayloadEnvelope>>,
+    /// An optional L1 chain provider shared with other drivers.
+    chain_provider: Option<InMemoryChainProvider>,
+    /// An optional blob provider shared with other drivers.
+    blob_provider: Option<DurableBlobProvider>,
 }
 
 impl HiloDriver<StandaloneContext> {
     /// Creates a new [HiloDriver] with a standalone context.
     pub async fn standalone(cfg: Config) -> TransportResult<Self> {
-        let ctx = StandaloneContext::new(cfg.l1_rpc_url.clone()).await?;
+        let ctx =
+            StandaloneContext::with_poll_interval(cfg.l1_rpc_url.clone(), cfg.poll_interval())
+                .await?;
         Ok(Self::new(cfg, ctx))
     }
 }
@@ -59,12 +146,76 @@ where
 {
     /// Constructs a new [HiloDriver].
     pub fn new(cfg: Config, ctx: C) -> Self {
-        Self { cfg, ctx }
+        let (events, _) = broadcast::channel(DRIVER_EVENT_CHANNEL_SIZE);
+        let (command_sender, commands) = mpsc::channel(DRIVER_COMMAND_CHANNEL_SIZE);
+        Self {
+            cfg,
+            ctx,
+            events,
+            finalized_head: BlockInfo::default(),
+            safe_heads: BTreeMap::new(),
+            tips: VecDeque::new(),
+            command_sender,
+            commands,
+            paused: false,
+            unsafe_payloads: None,
+            chain_provider: None,
+            blob_provider: None,
+        }
+    }
+
+    /// Sets the in-memory L1 chain data read by the pipeline, before falling back to the L1 RPC.
+    ///
+    /// If not set, a new [InMemoryChainProvider] is created when the pipeline is initialized.
+    pub fn with_chain_provider(mut self, chain_provider: InMemoryChainProvider) -> Self {
+        self.chain_provider = Some(chain_provider);
+        self
+    }
+
+    /// Sets the blob provider used by the pipeline.
+    ///
+    /// If not set, the blob provider is constructed from the [Config] when the pipeline
+    /// is initialized.
+    pub fn with_blob_provider(mut self, blob_provider: DurableBlobProvider) -> Self {
+        self.blob_provider = Some(blob_provider);
+        self
+    }
+
+    /// Sets the receiver of unsafe blocks gossiped over p2p.
+    ///
+    /// Received blocks are inserted into the execution client through the engine
+    /// controller, advancing the unsafe head ahead of derivation.
+    pub fn with_unsafe_payloads(
+        mut self,
+        unsafe_payloads: mpsc::Receiver<OpNetworkPayloadEnvelope>,
+    ) -> Self {
+        self.unsafe_payloads = Some(unsafe_payloads);
+        self
+    }
+
+    /// Subscribes to the [DriverEvent]s published by the driver.
+    ///
+    /// Only events published after subscribing are received.
+    pub fn subscribe(&self) -> broadcast::Receiver<DriverEvent> {
+        self.events.subscribe()
+    }
+
+    /// Returns a sender to issue [DriverCommand]s to the running driver.
+    pub fn command_sender(&self) -> mpsc::Sender<DriverCommand> {
+        self.command_sender.clone()
     }
 
     /// Initializes the [HiloPipeline].
     pub async fn init_pipeline(&self, cursor: PipelineCursor) -> Result<HiloPipeline, ConfigError> {
-        let chain_provider = InMemoryChainProvider::with_capacity(self.cfg.cache_size);
+        let cache = match &self.chain_provider {
+            Some(chain_provider) => chain_provider.clone(),
+            None => InMemoryChainProvider::with_capacity(self.cfg.cache_size),
+        };
+        let chain_provider = HiloChainProvider::new(cache, self.cfg.l1_rpc_url.clone());
+        let blob_provider = match &self.blob_provider {
+            Some(blob_provider) => blob_provider.clone(),
+            None => self.cfg.blob_provider().await?,
+        };
         // let l2_chain_provider = InMemoryL2ChainProvider::with_capacity(self.cfg.cache_size);
         let provider = ReqwestProvider::new_http(self.cfg.l2_rpc_url.clone());
         let l2_chain_provider =
@@ -72,24 +223,45 @@ where
         Ok(HiloPipeline::new(
             Arc::new(self.cfg.rollup_config.clone()),
             cursor,
-            self.cfg.blob_provider().await?,
-            chain_provider.clone(),
+            blob_provider,
+            chain_provider,
             l2_chain_provider,
-        ))
+        )
+        .with_events(self.events.clone()))
     }
 
     /// Initializes a [Driver] using the [HiloPipeline].
+    ///
+    /// Resumes from the newest valid checkpoint if a data directory is configured.
     pub async fn init_driver(&mut self) -> Result<KonaDriver, ConfigError> {
-        let cursor = self.cfg.tip_cursor().await?;
+        let Some(checkpoint) = self.cfg.resume_checkpoint().await? else {
+            let cursor = self.cfg.tip_cursor().await?;
+            return self.init_driver_at(cursor).await;
+        };
+        let channel_timeout =
+            self.cfg.rollup_config.channel_timeout(checkpoint.l2_safe_head.block_info.timestamp);
+        let driver = self.init_driver_at(checkpoint.cursor(channel_timeout)).await?;
+        if !checkpoint.tips.is_empty() {
+            self.tips = checkpoint.tips.into();
+        }
+        Ok(driver)
+    }
+
+    /// Initializes a [Driver] using the [HiloPipeline], starting from the given [PipelineCursor].
+    pub async fn init_driver_at(
+        &mut self,
+        cursor: PipelineCursor,
+    ) -> Result<KonaDriver, ConfigError> {
         let pipeline = self.init_pipeline(cursor.clone()).await?;
         let exec = EngineController::new(
             self.cfg.l2_engine_url.clone(),
             self.cfg.l2_rpc_url.clone(),
             self.cfg.jwt_secret,
-            cursor.origin(),
-            cursor.l2_safe_head().block_info.into(),
+            cursor.l2_safe_head().block_info,
+            cursor.origin().into(),
             &self.cfg.rollup_config,
         );
+        self.reset_tips(&cursor);
         Ok(Driver::new(cursor, exec, pipeline))
     }
 
@@ -114,49 +286,361 @@ where
             if let Err(e) = driver.pipeline.signal(reset_signal.signal()).await {
                 return Err(DriverError::PipelineReset(e));
             }
+            // Safe heads derived from the reverted L1 blocks can no longer be finalized.
+            self.safe_heads.split_off(&(fork_block + 1));
+            self.tips.retain(|tip| tip.l1_origin.number <= fork_block);
         }
 
         if let Some(new_chain) = notification.new_chain() {
             let tip = new_chain.tip();
             self.ctx.send_processed_tip_event(tip);
+            _ = self.events.send(DriverEvent::L1HeadReceived(tip));
+        }
+
+        Ok(())
+    }
+
+    /// Handle an unsafe block gossiped over p2p.
+    ///
+    /// Failures are logged but not fatal, since derivation catches up on the block anyway.
+    async fn handle_unsafe_payload(
+        &mut self,
+        envelope: OpNetworkPayloadEnvelope,
+        driver: &mut KonaDriver,
+    ) {
+        let number = envelope.payload.block_number();
+        if number <= driver.executor.safe_head.number {
+            debug!("Ignoring unsafe block {} behind the safe head", number);
+            return;
+        }
+        if let Err(e) = driver
+            .executor
+            .handle_unsafe_payload(&envelope.payload, envelope.parent_beacon_block_root)
+            .await
+        {
+            warn!("Failed to insert unsafe block {}: {}", number, e);
         }
+    }
 
+    /// Handle a [DriverCommand].
+    async fn handle_command(
+        &mut self,
+        command: DriverCommand,
+        driver: &mut KonaDriver,
+    ) -> Result<(), DriverError> {
+        match command {
+            DriverCommand::ResetPipeline => {
+                // The pipeline origin may be ahead of the safe head's L1 origin, with channels
+                // still pending in the pipeline, so derivation restarts from a fresh cursor.
+                let number = driver.cursor.l2_safe_head().block_info.number;
+                let cursor = self.cfg.cursor_at(number).await?;
+                let l1_origin = cursor.origin();
+                let l2_safe_head = *cursor.l2_safe_head();
+                warn!(
+                    "Resetting derivation pipeline to L2 block {} at L1 block {} on request",
+                    number, l1_origin.number
+                );
+                self.reset_tips(&cursor);
+                driver.cursor = cursor;
+                let reset_signal = ResetSignal { l1_origin, l2_safe_head, ..Default::default() };
+                driver.pipeline.signal(reset_signal.signal()).await?;
+            }
+            DriverCommand::PauseDerivation => {
+                info!("Derivation paused");
+                self.paused = true;
+            }
+            DriverCommand::ResumeDerivation => {
+                info!("Derivation resumed");
+                self.paused = false;
+            }
+            DriverCommand::ForceForkchoice { state, reply } => {
+                let state = state.unwrap_or_else(|| driver.executor.create_forkchoice_state());
+                info!("Forcing forkchoice update to head {}", state.head_block_hash);
+                let result = driver
+                    .executor
+                    .client
+                    .forkchoice_update(state, None)
+                    .await
+                    .map(|update| update.payload_status)
+                    .map_err(|e| format!("{e:?}"));
+                _ = reply.send(result);
+            }
+        }
         Ok(())
     }
 
     /// Continuously run the [HiloDriver].
     pub async fn start(&mut self) -> Result<(), DriverError> {
         // Step 1: Wait for the L2 origin block to be available
-        self.wait_for_l2_genesis_l1_block().await;
+        self.wait_for_l2_genesis_l1_block().await?;
         info!("L1 chain synced to the rollup genesis block");
 
         // Step 2: Initialize the kona driver
-        let mut driver = self.init_driver().await?;
+        let driver = self.init_driver().await?;
         info!("Driver initialized");
 
+        // Step 3: Start the processing loop
+        self.run(driver).await
+    }
+
+    /// Continuously run the [HiloDriver], starting derivation from the given L2 safe head.
+    ///
+    /// Unlike [HiloDriver::start], this ignores persisted checkpoints and the execution
+    /// client's latest block, which is useful when the execution client has synced ahead
+    /// of the safe chain.
+    pub async fn start_from(&mut self, safe_head: u64) -> Result<(), DriverError> {
+        self.wait_for_l2_genesis_l1_block().await?;
+        info!("L1 chain synced to the rollup genesis block");
+
+        let cursor = self.cfg.cursor_at(safe_head).await?;
+        let driver = self.init_driver_at(cursor).await?;
+        info!("Driver initialized at L2 safe head {}", safe_head);
+
+        self.run(driver).await
+    }
+
+    /// Runs the processing loop of an initialized [KonaDriver].
+    ///
+    /// Derivation advances one L2 block per step, and a step is never raced against the
+    /// other inputs of the driver: dropping it midway would lose attributes already taken
+    /// from the pipeline, or abort an engine call. Notifications, commands and unsafe
+    /// blocks received during a step are handled before the next one.
+    async fn run(&mut self, mut driver: KonaDriver) -> Result<(), DriverError> {
         // Wait until the engine is ready
         driver.wait_for_executor().await;
 
-        // Step 3: Start the processing loop
+        let checkpoints = self.cfg.checkpoint_store()?;
+        let mut last_checkpoint = Instant::now();
+        let mut last_finality_poll = None::<Instant>;
+
         loop {
-            tokio::select! {
-                result = driver.advance_to_target(&self.cfg.rollup_config, None) => match result {
-                    Ok((bn, hash)) => {
-                        error!("Driver unexpectedly stopped at target block: {} {}", bn, hash);
-                    }
-                    Err(e) => {
-                        error!("Driver error: {}", e);
-                        // TODO: optionally allow recovery
-                        return Err(DriverError::DriverErrored);
-                    }
-                },
-                Some(notification) = self.ctx.recv_notification() => {
-                    self.handle_notification(notification, &mut driver).await?;
+            self.handle_pending(&mut driver).await?;
+            if self.paused || !self.step(&mut driver).await? {
+                // Derivation is paused or waiting for L1 data.
+                self.wait_for_input(&mut driver).await?;
+            }
+            if last_finality_poll.map_or(true, |last| last.elapsed() >= FINALITY_POLL_INTERVAL) {
+                self.poll_finalized(&mut driver).await;
+                last_finality_poll = Some(Instant::now());
+            }
+            if let Some(store) = &checkpoints {
+                if last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL {
+                    self.checkpoint(store, &driver.cursor);
+                    last_checkpoint = Instant::now();
                 }
             }
         }
     }
 
+    /// Derives and executes the next L2 block.
+    ///
+    /// Returns whether the safe head advanced, which it does not if the pipeline ran out
+    /// of L1 data.
+    async fn step(&mut self, driver: &mut KonaDriver) -> Result<bool, DriverError> {
+        let safe_head = driver.cursor.l2_safe_head().block_info.number;
+        match driver.advance_to_target(&self.cfg.rollup_config, Some(safe_head + 1)).await {
+            Ok((number, _)) => {
+                self.record_safe_head(driver);
+                Ok(number > safe_head)
+            }
+            Err(e) => {
+                error!("Driver error: {}", e);
+                // TODO: optionally allow recovery
+                Err(DriverError::DriverErrored)
+            }
+        }
+    }
+
+    /// Records the current L2 safe head as derived from the L1 origin of the pipeline.
+    fn record_safe_head(&mut self, driver: &KonaDriver) {
+        self.safe_heads.insert(driver.cursor.origin().number, *driver.cursor.l2_safe_head());
+        self.record_tip(&driver.cursor);
+    }
+
+    /// Records the tip of the [PipelineCursor], keeping the tips within the channel timeout
+    /// of the pipeline origin like the cursor does.
+    fn record_tip(&mut self, cursor: &PipelineCursor) {
+        let tip = CheckpointTip {
+            l1_origin: cursor.origin(),
+            l2_safe_head: *cursor.l2_safe_head(),
+            l2_safe_head_output_root: *cursor.l2_safe_head_output_root(),
+        };
+        if self.tips.back().is_some_and(|last| last.l1_origin.number >= tip.l1_origin.number) {
+            self.tips.pop_back();
+        }
+        self.tips.push_back(tip);
+        let channel_timeout =
+            self.cfg.rollup_config.channel_timeout(tip.l2_safe_head.block_info.timestamp);
+        while self.tips.len() as u64 > channel_timeout + 1 {
+            self.tips.pop_front();
+        }
+    }
+
+    /// Replaces the recorded tips with the tip of a fresh [PipelineCursor].
+    fn reset_tips(&mut self, cursor: &PipelineCursor) {
+        self.tips.clear();
+        self.record_tip(cursor);
+    }
+
+    /// Fetches the finalized L1 block and finalizes the L2 blocks derived from it.
+    ///
+    /// Failures are logged but not fatal, finalization is retried at the next poll.
+    async fn poll_finalized(&mut self, driver: &mut KonaDriver) {
+        let provider = ReqwestProvider::new_http(self.cfg.l1_rpc_url.clone());
+        match provider
+            .get_block_by_number(BlockNumberOrTag::Finalized, BlockTransactionsKind::Hashes)
+            .await
+        {
+            Ok(Some(block)) => self.finalize(block.header.number, driver),
+            Ok(None) => debug!("No finalized L1 block yet"),
+            Err(e) => warn!("Failed to fetch the finalized L1 block: {}", e),
+        }
+    }
+
+    /// Finalizes the latest L2 safe head derived from L1 blocks up to the finalized L1 block,
+    /// publishing a [DriverEvent::FinalizedUpdated] if it changed.
+    ///
+    /// The execution client learns of the finalized head with the next forkchoice update.
+    fn finalize(&mut self, l1_finalized: u64, driver: &mut KonaDriver) {
+        let Some((&l1_block, &head)) = self.safe_heads.range(..=l1_finalized).next_back() else {
+            return;
+        };
+        // Older safe heads are behind the finalized head for good.
+        self.safe_heads = self.safe_heads.split_off(&l1_block);
+        if head.block_info == self.finalized_head {
+            return;
+        }
+
+        driver.executor.update_finalized(head.block_info, head.l1_origin.into());
+        self.finalized_head = head.block_info;
+        info!("Finalized L2 block {} at L1 block {}", head.block_info.number, l1_finalized);
+        _ = self.events.send(DriverEvent::FinalizedUpdated(head.block_info));
+    }
+
+    /// Handles the notifications, commands and unsafe blocks received so far, without
+    /// waiting for new ones.
+    async fn handle_pending(&mut self, driver: &mut KonaDriver) -> Result<(), DriverError> {
+        while let Some(notification) = self.ctx.recv_notification().now_or_never() {
+            let notification = notification.ok_or(DriverError::NotificationsClosed)?;
+            self.handle_notification(notification, driver).await?;
+        }
+        while let Ok(command) = self.commands.try_recv() {
+            self.handle_command(command, driver).await?;
+        }
+        loop {
+            match self.unsafe_payloads.as_mut().map(mpsc::Receiver::try_recv) {
+                Some(Ok(envelope)) => self.handle_unsafe_payload(envelope, driver).await,
+                Some(Err(TryRecvError::Disconnected)) => self.close_unsafe_payloads(),
+                Some(Err(TryRecvError::Empty)) | None => return Ok(()),
+            }
+        }
+    }
+
+    /// Waits for the next notification, command or unsafe block and handles it, or for the
+    /// poll interval to elapse.
+    ///
+    /// Only called in between steps, so waking up never interrupts derivation.
+    async fn wait_for_input(&mut self, driver: &mut KonaDriver) -> Result<(), DriverError> {
+        tokio::select! {
+            notification = self.ctx.recv_notification() => {
+                let notification = notification.ok_or(DriverError::NotificationsClosed)?;
+                self.handle_notification(notification, driver).await?;
+            }
+            Some(command) = self.commands.recv() => {
+                self.handle_command(command, driver).await?;
+            }
+            envelope = next_unsafe_payload(&mut self.unsafe_payloads) => match envelope {
+                Some(envelope) => self.handle_unsafe_payload(envelope, driver).await,
+                None => self.close_unsafe_payloads(),
+            },
+            _ = tokio::time::sleep(self.cfg.poll_interval()) => {}
+        }
+        Ok(())
+    }
+
+    /// Drops the closed receiver of unsafe blocks.
+    fn close_unsafe_payloads(&mut self) {
+        warn!("Unsafe block channel closed, following the safe chain only");
+        self.unsafe_payloads = None;
+    }
+
+    /// Persists a [Checkpoint] of the given [PipelineCursor] and the recorded tips.
+    ///
+    /// Failures are logged but not fatal, since checkpoints only speed up restarts.
+    fn checkpoint(&self, store: &CheckpointStore, cursor: &PipelineCursor) {
+        let checkpoint = Checkpoint {
+            l1_origin: cursor.origin(),
+            l2_safe_head: *cursor.l2_safe_head(),
+            tips: self.tips.iter().copied().collect(),
+        };
+        match store.save(&checkpoint) {
+            Ok(()) => debug!(
+                "Checkpointed L2 safe head {} at L1 origin {}",
+                checkpoint.l2_safe_head.block_info.number, checkpoint.l1_origin.number
+            ),
+            Err(e) => warn!("Failed to write checkpoint: {}", e),
+        }
+    }
+
+    /// Runs derivation from the current L2 tip until the safe head reaches the `target` L2 block.
+    ///
+    /// Returns a [DerivationSummary] once the target is reached.
+    pub async fn derive_until(&mut self, target: u64) -> Result<DerivationSummary, DriverError> {
+        self.wait_for_l2_genesis_l1_block().await?;
+        let cursor = self.cfg.tip_cursor().await?;
+        self.derive_to_target(cursor, target).await
+    }
+
+    /// Runs derivation from the `from` L2 block until the safe head reaches the `target` L2 block.
+    ///
+    /// Returns a [DerivationSummary] once the target is reached.
+    pub async fn derive_range(
+        &mut self,
+        from: u64,
+        target: u64,
+    ) -> Result<DerivationSummary, DriverError> {
+        self.wait_for_l2_genesis_l1_block().await?;
+        let cursor = self.cfg.cursor_at(from).await?;
+        self.derive_to_target(cursor, target).await
+    }
+
+    /// Drives derivation from the given [PipelineCursor] until the `target` L2 block is reached.
+    async fn derive_to_target(
+        &mut self,
+        cursor: PipelineCursor,
+        target: u64,
+    ) -> Result<DerivationSummary, DriverError> {
+        let l2_start = cursor.l2_safe_head().block_info.number;
+        if target <= l2_start {
+            return Err(DriverError::InvalidTarget { target, start: l2_start });
+        }
+        let l1_start = cursor.origin();
+        let started = Instant::now();
+
+        let mut driver = self.init_driver_at(cursor).await?;
+        info!("Driver initialized, deriving L2 blocks {} -> {}", l2_start, target);
+        driver.wait_for_executor().await;
+
+        while driver.cursor.l2_safe_head().block_info.number < target {
+            self.handle_pending(&mut driver).await?;
+            if self.paused || !self.step(&mut driver).await? {
+                debug!(
+                    "Derivation paused at L2 block {}, waiting for L1 data",
+                    driver.cursor.l2_safe_head().block_info.number
+                );
+                self.wait_for_input(&mut driver).await?;
+            }
+        }
+
+        Ok(DerivationSummary {
+            l2_start,
+            l2_end: driver.cursor.l2_safe_head().block_info.number,
+            l1_start,
+            l1_end: driver.cursor.origin(),
+            duration: started.elapsed(),
+        })
+    }
+
     // Exits if a SIGINT signal is received
     // fn check_shutdown(&self) -> Result<(), DriverError> {
     //     if *self.shutdown_recv.borrow() {
@@ -168,22 +652,373 @@ where
     // }
 
     /// Wait for the L2 genesis' corresponding L1 block to be available in the L1 chain.
-    async fn wait_for_l2_genesis_l1_block(&mut self) {
+    async fn wait_for_l2_genesis_l1_block(&mut self) -> Result<(), DriverError> {
         loop {
-            if let Some(notification) = self.ctx.recv_notification().await {
-                if let Some(new_chain) = notification.new_chain() {
-                    let tip = new_chain.tip();
-                    self.ctx.send_processed_tip_event(tip);
+            let notification =
+                self.ctx.recv_notification().await.ok_or(DriverError::NotificationsClosed)?;
+            if let Some(new_chain) = notification.new_chain() {
+                let tip = new_chain.tip();
+                self.ctx.send_processed_tip_event(tip);
 
-                    if tip.number >= self.cfg.rollup_config.genesis.l1.number {
-                        break;
+                if tip.number >= self.cfg.rollup_config.genesis.l1.number {
+                    return Ok(());
+                }
+                debug!("Chain not yet synced to rollup genesis. L1 block number: {}", tip.number);
+            }
+        }
+    }
+}
+
+/// Receives the next unsafe block if a receiver is set, otherwise never resolves.
+async fn next_unsafe_payload(
+    unsafe_payloads: &mut Option<mpsc::Receiver<OpNetworkPayloadEnvelope>>,
+) -> Option<OpNetworkPayloadEnvelope> {
+    match unsafe_payloads {
+        Some(unsafe_payloads) => unsafe_payloads.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::Headers,
+        test_utils::{
+            block_info, genesis_cursor, hilo_driver, l1_header, rollup_config, StandIn,
+            FINALIZED_L1, GENESIS_L1,
+        },
+        ReplayContext,
+    };
+    use alloy_eips::BlockNumHash;
+    use alloy_primitives::{Address, Bloom, Bytes, PrimitiveSignature, B256, U256};
+    use alloy_rpc_types_engine::{
+        ExecutionPayload, ExecutionPayloadV1, ExecutionPayloadV2, PayloadStatusEnum,
+    };
+    use op_alloy_genesis::RollupConfig;
+    use op_alloy_rpc_types_engine::PayloadHash;
+
+    /// Returns a gossiped unsafe block with the given number.
+    fn envelope(number: u64) -> OpNetworkPayloadEnvelope {
+        let payload_inner = ExecutionPayloadV1 {
+            parent_hash: B256::with_last_byte(number as u8 - 1),
+            fee_recipient: Address::ZERO,
+            state_root: B256::ZERO,
+            receipts_root: B256::ZERO,
+            logs_bloom: Bloom::default(),
+            prev_randao: B256::ZERO,
+            block_number: number,
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            timestamp: number * 2,
+            extra_data: Bytes::new(),
+            base_fee_per_gas: U256::from(7),
+            block_hash: B256::with_last_byte(number as u8),
+            transactions: Vec::new(),
+        };
+        OpNetworkPayloadEnvelope {
+            payload: ExecutionPayload::V2(ExecutionPayloadV2 {
+                payload_inner,
+                withdrawals: Vec::new(),
+            }),
+            signature: PrimitiveSignature::new(U256::ZERO, U256::ZERO, false),
+            payload_hash: PayloadHash::from(&Bytes::new()[..]),
+            parent_beacon_block_root: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unsafe_payloads_handled_between_steps() {
+        let stand_in = StandIn::start().await;
+        let config = stand_in.config();
+        let (sender, receiver) = mpsc::channel(4);
+        let (hilo, _notifications) = hilo_driver(config.clone());
+        let mut hilo = hilo.with_unsafe_payloads(receiver);
+        let mut driver = hilo.init_driver_at(genesis_cursor(&config, GENESIS_L1)).await.unwrap();
+        assert_eq!(driver.executor.safe_head.number, 0);
+
+        sender.send(envelope(1)).await.unwrap();
+        sender.send(envelope(2)).await.unwrap();
+        hilo.handle_pending(&mut driver).await.unwrap();
+        assert_eq!(driver.executor.unsafe_head.number, 2);
+        assert_eq!(driver.executor.unsafe_head.hash, B256::with_last_byte(2));
+        assert_eq!(driver.executor.safe_head.number, 0);
+        assert_eq!(
+            stand_in.engine_calls(),
+            [
+                "engine_newPayloadV2",
+                "engine_forkchoiceUpdatedV2",
+                "engine_newPayloadV2",
+                "engine_forkchoiceUpdatedV2"
+            ]
+        );
+
+        // Gossip wakes the driver up while it waits for L1 data.
+        sender.send(envelope(3)).await.unwrap();
+        hilo.wait_for_input(&mut driver).await.unwrap();
+        assert_eq!(driver.executor.unsafe_head.number, 3);
+
+        // Once the network stops, the driver keeps following the safe chain.
+        drop(sender);
+        hilo.handle_pending(&mut driver).await.unwrap();
+        assert!(hilo.unsafe_payloads.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unsafe_payload_behind_safe_head_is_ignored() {
+        let stand_in = StandIn::start().await;
+        let config = stand_in.config();
+        let (mut hilo, _notifications) = hilo_driver(config.clone());
+        let mut driver = hilo.init_driver_at(genesis_cursor(&config, GENESIS_L1)).await.unwrap();
+        driver.executor.safe_head.number = 5;
+
+        hilo.handle_unsafe_payload(envelope(5), &mut driver).await;
+        assert!(stand_in.engine_calls().is_empty());
+        assert_eq!(driver.executor.unsafe_head.number, 0);
+    }
+
+    /// Advances the cursor to the L2 block with the given number, derived from the L1 block
+    /// that many blocks past the rollup genesis.
+    fn advance(driver: &mut KonaDriver, number: u64) {
+        let origin = block_info(GENESIS_L1 + number);
+        let safe_head = L2BlockInfo {
+            block_info: BlockInfo {
+                hash: B256::with_last_byte(number as u8),
+                number,
+                ..Default::default()
+            },
+            l1_origin: origin.id(),
+            seq_num: 0,
+        };
+        driver.cursor.advance(origin, TipCursor::new(safe_head, Default::default(), B256::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_finalized_head_follows_l1_finality() {
+        let stand_in = StandIn::start().await;
+        let config = stand_in.config();
+        let (mut hilo, _notifications) = hilo_driver(config.clone());
+        let mut driver = hilo.init_driver_at(genesis_cursor(&config, GENESIS_L1)).await.unwrap();
+        let mut events = hilo.subscribe();
+        for number in 1..=3 {
+            advance(&mut driver, number);
+            hilo.record_safe_head(&driver);
+        }
+
+        // The safe head derived from the finalized L1 block is finalized.
+        hilo.poll_finalized(&mut driver).await;
+        let finalized = match events.try_recv().unwrap() {
+            DriverEvent::FinalizedUpdated(head) => head,
+            event => panic!("unexpected event {event:?}"),
+        };
+        assert_eq!(finalized.number, FINALIZED_L1 - GENESIS_L1);
+        assert_eq!(driver.executor.finalized_head, finalized);
+        assert_eq!(
+            driver.executor.finalized_epoch,
+            hilo_engine::Epoch::from(block_info(FINALIZED_L1).id())
+        );
+        assert_eq!(hilo.safe_heads.keys().copied().collect::<Vec<_>>(), [102, 103]);
+
+        // Nothing changes until L1 finality advances.
+        hilo.finalize(FINALIZED_L1, &mut driver);
+        assert!(events.try_recv().is_err());
+
+        hilo.finalize(FINALIZED_L1 + 1, &mut driver);
+        match events.try_recv().unwrap() {
+            DriverEvent::FinalizedUpdated(head) => assert_eq!(head.number, 3),
+            event => panic!("unexpected event {event:?}"),
+        }
+        assert_eq!(driver.executor.finalized_head.number, 3);
+    }
+
+    #[tokio::test]
+    async fn test_finalize_before_first_derived_block() {
+        let stand_in = StandIn::start().await;
+        let config = stand_in.config();
+        let (mut hilo, _notifications) = hilo_driver(config.clone());
+        let mut driver = hilo.init_driver_at(genesis_cursor(&config, GENESIS_L1)).await.unwrap();
+        let mut events = hilo.subscribe();
+        advance(&mut driver, 3);
+        hilo.record_safe_head(&driver);
+
+        hilo.finalize(FINALIZED_L1, &mut driver);
+        assert!(events.try_recv().is_err());
+        assert_eq!(driver.executor.finalized_head.number, 0);
+    }
+
+    #[tokio::test]
+    async fn test_notifications_publish_l1_heads() {
+        let stand_in = StandIn::start().await;
+        let config = stand_in.config();
+        let (mut hilo, notifications) = hilo_driver(config.clone());
+        let mut driver = hilo.init_driver_at(genesis_cursor(&config, GENESIS_L1)).await.unwrap();
+        let mut events = hilo.subscribe();
+
+        for number in [GENESIS_L1 + 1, GENESIS_L1 + 2] {
+            let inner = l1_header(number);
+            let header = alloy_rpc_types_eth::Header {
+                hash: inner.hash_slow(),
+                inner,
+                ..Default::default()
+            };
+            notifications
+                .send(ChainNotification::New { new_blocks: Headers::from(header) })
+                .unwrap();
+        }
+        hilo.handle_pending(&mut driver).await.unwrap();
+
+        for number in [GENESIS_L1 + 1, GENESIS_L1 + 2] {
+            match events.try_recv().unwrap() {
+                DriverEvent::L1HeadReceived(head) => {
+                    assert_eq!(head, BlockNumHash { number, hash: l1_header(number).hash_slow() })
+                }
+                event => panic!("unexpected event {event:?}"),
+            }
+        }
+        // Finalization only follows L1 finality, not new L1 heads.
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_context_drives_driver() {
+        let stand_in = StandIn::start().await;
+        let config = stand_in.config();
+        let numbers = GENESIS_L1 - 2..=GENESIS_L1 + 2;
+        let mut hilo =
+            HiloDriver::new(config.clone(), ReplayContext::new(numbers.clone().map(l1_header)));
+        let mut events = hilo.subscribe();
+
+        hilo.wait_for_l2_genesis_l1_block().await.unwrap();
+        let mut driver = hilo.init_driver_at(genesis_cursor(&config, GENESIS_L1)).await.unwrap();
+        // The driver stops once the replayed headers run out.
+        assert!(matches!(
+            hilo.handle_pending(&mut driver).await,
+            Err(DriverError::NotificationsClosed)
+        ));
+
+        let tips = hilo.ctx.processed_tips().iter().map(|tip| tip.number).collect::<Vec<_>>();
+        assert_eq!(tips, numbers.collect::<Vec<_>>());
+        for number in [GENESIS_L1 + 1, GENESIS_L1 + 2] {
+            match events.try_recv().unwrap() {
+                DriverEvent::L1HeadReceived(head) => assert_eq!(head.number, number),
+                event => panic!("unexpected event {event:?}"),
+            }
+        }
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_devnet_safe_head_advances() {
+        let stand_in = StandIn::start().await;
+        // Devnets outside the superchain registry load their rollup config from a file.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rollup.json");
+        let rollup_config =
+            RollupConfig { regolith_time: Some(0), canyon_time: Some(0), ..rollup_config() };
+        std::fs::write(&path, serde_json::to_vec(&rollup_config).unwrap()).unwrap();
+        let rollup_config = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        let config = Config { rollup_config, ..stand_in.config() };
+        assert!(config.devnet);
+        assert!(config.l1_chain_spec.is_some());
+        assert!(config.blob_archiver_url.is_none());
+
+        let mut hilo = HiloDriver::standalone(config).await.unwrap();
+        let mut events = hilo.subscribe();
+        let advanced = async {
+            loop {
+                match events.recv().await {
+                    Ok(DriverEvent::SafeHeadUpdated(head)) if head.block_info.number >= 2 => {
+                        return head
                     }
-                    debug!(
-                        "Chain not yet synced to rollup genesis. L1 block number: {}",
-                        tip.number
-                    );
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => panic!("driver events closed"),
                 }
             }
+        };
+        let safe_head = tokio::time::timeout(Duration::from_secs(30), async {
+            tokio::select! {
+                result = hilo.start() => panic!("driver stopped: {result:?}"),
+                head = advanced => head,
+            }
+        })
+        .await
+        .unwrap();
+
+        // The chain has no batches, so the blocks are derived once the sequencing window of
+        // the genesis epoch expires, and inserted through the engine API.
+        assert_eq!(safe_head.l1_origin.number, GENESIS_L1);
+        assert!(stand_in.l2_head() >= safe_head.block_info.number);
+        assert!(stand_in.engine_calls().iter().any(|call| call == "engine_getPayloadV2"));
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume_commands() {
+        let stand_in = StandIn::start().await;
+        let config = stand_in.config();
+        let (mut hilo, _notifications) = hilo_driver(config.clone());
+        let mut driver = hilo.init_driver_at(genesis_cursor(&config, GENESIS_L1)).await.unwrap();
+        let commands = hilo.command_sender();
+
+        commands.send(DriverCommand::PauseDerivation).await.unwrap();
+        hilo.handle_pending(&mut driver).await.unwrap();
+        assert!(hilo.paused);
+
+        // Commands wake up a paused driver.
+        commands.send(DriverCommand::ResumeDerivation).await.unwrap();
+        hilo.wait_for_input(&mut driver).await.unwrap();
+        assert!(!hilo.paused);
+    }
+
+    #[tokio::test]
+    async fn test_reset_command_rewinds_to_safe_head_origin() {
+        let stand_in = StandIn::start().await;
+        let config = stand_in.config();
+        let (mut hilo, _notifications) = hilo_driver(config.clone());
+        // The pipeline read ahead of the L1 origin of the safe head.
+        let mut driver =
+            hilo.init_driver_at(genesis_cursor(&config, GENESIS_L1 + 20)).await.unwrap();
+        let safe_head = *driver.cursor.l2_safe_head();
+        let mut events = hilo.subscribe();
+
+        hilo.handle_command(DriverCommand::ResetPipeline, &mut driver).await.unwrap();
+        assert_eq!(driver.cursor.origin(), block_info(GENESIS_L1));
+        assert_eq!(*driver.cursor.l2_safe_head(), safe_head);
+        match events.try_recv().unwrap() {
+            DriverEvent::PipelineReset { l1_origin, l2_safe_head } => {
+                assert_eq!(l1_origin, block_info(GENESIS_L1));
+                assert_eq!(l2_safe_head, safe_head);
+            }
+            event => panic!("unexpected event {event:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn test_force_forkchoice_command() {
+        let stand_in = StandIn::start().await;
+        let config = stand_in.config();
+        let (mut hilo, _notifications) = hilo_driver(config.clone());
+        let mut driver = hilo.init_driver_at(genesis_cursor(&config, GENESIS_L1)).await.unwrap();
+
+        let (reply, status) = tokio::sync::oneshot::channel();
+        let command = DriverCommand::ForceForkchoice { state: None, reply };
+        hilo.handle_command(command, &mut driver).await.unwrap();
+        assert_eq!(status.await.unwrap().unwrap().status, PayloadStatusEnum::Valid);
+        assert_eq!(stand_in.engine_calls(), ["engine_forkchoiceUpdatedV2"]);
+    }
+
+    #[test]
+    fn test_derivation_summary() {
+        let summary = DerivationSummary {
+            l2_start: 100,
+            l2_end: 110,
+            l1_start: BlockInfo { number: 20, ..Default::default() },
+            l1_end: BlockInfo { number: 22, ..Default::default() },
+            duration: Duration::from_secs(3),
+        };
+        assert_eq!(summary.blocks(), 10);
+        assert_eq!(
+            summary.to_string(),
+            "derived 10 L2 blocks (100 -> 110) from L1 blocks 20 -> 22 in 3.00s"
+        );
+    }
 }
@@ -0,0 +1,36 @@
+//! Events emitted by the [crate::HiloDriver].
+
+use alloy_eips::BlockNumHash;
+use op_alloy_protocol::{BlockInfo, L2BlockInfo};
+use op_alloy_rpc_types_engine::OpAttributesWithParent;
+use tokio::sync::broadcast;
+
+/// The number of events to buffer for each subscriber before lagging subscribers
+/// start missing events.
+pub const DRIVER_EVENT_CHANNEL_SIZE: usize = 256;
+
+/// An event describing the progress of the [crate::HiloDriver].
+///
+/// Events are published over a [broadcast] channel, so subscribers that fall
+/// more than [DRIVER_EVENT_CHANNEL_SIZE] events behind will observe a
+/// [broadcast::error::RecvError::Lagged] error and skip the missed events.
+#[derive(Debug, Clone)]
+pub enum DriverEvent {
+    /// Payload attributes were derived by the pipeline.
+    AttributesDerived(Box<OpAttributesWithParent>),
+    /// The L2 safe head was updated.
+    SafeHeadUpdated(L2BlockInfo),
+    /// The L2 finalized head was updated.
+    FinalizedUpdated(BlockInfo),
+    /// The derivation pipeline was reset.
+    PipelineReset {
+        /// The L1 origin the pipeline was reset to.
+        l1_origin: BlockInfo,
+        /// The L2 safe head the pipeline was reset to.
+        l2_safe_head: L2BlockInfo,
+    },
+    /// The L1 origin of the derivation pipeline advanced.
+    L1OriginAdvanced(BlockInfo),
+    /// A new L1 head was received from the L1 chain notifications.
+    L1HeadReceived(BlockNumHash),
+}
@@ -6,17 +6,44 @@
 #[macro_use]
 extern crate tracing;
 
+mod checkpoint;
+pub use checkpoint::{
+    Checkpoint, CheckpointError, CheckpointStore, CheckpointTip, CHECKPOINT_INTERVAL,
+    DEFAULT_CHECKPOINT_RETENTION,
+};
+
 mod config;
-pub use config::{Config, ConfigError};
+pub use config::{Config, ConfigError, L1ChainSpec, DEVNET_POLL_INTERVAL};
 
 mod driver;
-pub use driver::{DriverError, HiloDriver};
+pub use driver::{DerivationSummary, DriverError, HiloDriver, FINALITY_POLL_INTERVAL};
+
+mod commands;
+pub use commands::{DriverCommand, DRIVER_COMMAND_CHANNEL_SIZE};
+
+mod events;
+pub use events::{DriverEvent, DRIVER_EVENT_CHANNEL_SIZE};
 
 mod context;
-pub use context::{ChainNotification, Context, StandaloneContext};
+pub use context::{ChainNotification, Context, FanoutContext, ReplayContext, StandaloneContext};
+
+mod superchain;
+pub use superchain::{SuperchainDriver, SuperchainError};
+
+mod replay;
+pub use replay::{
+    ExpectedAttributes, L1BlockFixture, L2BlockFixture, ReplayBlobProvider, ReplayChainProvider,
+    ReplayError, ReplayFixture, ReplayL2ChainProvider, ReplayPipeline, ReplayProviderError,
+};
+
+mod provider;
+pub use provider::HiloChainProvider;
 
 mod pipeline;
 pub use pipeline::{
     HiloAttributesBuilder, HiloAttributesQueue, HiloDataProvider, HiloDerivationPipeline,
     HiloPipeline,
 };
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
@@ -3,72 +3,99 @@
 use async_trait::async_trait;
 use kona_derive::{
     attributes::StatefulAttributesBuilder,
-    errors::PipelineErrorKind,
+    errors::{PipelineError, PipelineErrorKind},
     pipeline::{DerivationPipeline, PipelineBuilder},
     sources::EthereumDataSource,
     stages::{
         AttributesQueue, BatchProvider, BatchStream, ChannelProvider, ChannelReader, FrameQueue,
         L1Retrieval, L1Traversal,
     },
-    traits::{OriginProvider, Pipeline, SignalReceiver},
-    types::{PipelineResult, Signal, StepResult},
+    traits::{
+        BlobProvider, ChainProvider, L2ChainProvider, OriginProvider, Pipeline, SignalReceiver,
+    },
+    types::{PipelineResult, ResetSignal, Signal, StepResult},
 };
 use kona_driver::{DriverPipeline, PipelineCursor};
 use op_alloy_genesis::{RollupConfig, SystemConfig};
 use op_alloy_protocol::{BlockInfo, L2BlockInfo};
 use op_alloy_rpc_types_engine::OpAttributesWithParent;
-use std::{boxed::Box, sync::Arc};
+use std::{boxed::Box, fmt::Debug, sync::Arc};
+use tokio::sync::broadcast;
 
+use crate::{DriverEvent, HiloChainProvider};
 use hilo_providers_alloy::{AlloyL2ChainProvider, DurableBlobProvider};
-use hilo_providers_local::InMemoryChainProvider;
 
 /// Hilo Derivation Pipeline.
-pub type HiloDerivationPipeline =
-    DerivationPipeline<HiloAttributesQueue<HiloDataProvider>, AlloyL2ChainProvider>;
+pub type HiloDerivationPipeline<
+    CP = HiloChainProvider,
+    BP = DurableBlobProvider,
+    L2P = AlloyL2ChainProvider,
+> = DerivationPipeline<HiloAttributesQueue<HiloDataProvider<CP, BP>, CP, L2P>, L2P>;
 
 /// Hilo Ethereum data source.
-pub type HiloDataProvider = EthereumDataSource<InMemoryChainProvider, DurableBlobProvider>;
+pub type HiloDataProvider<CP = HiloChainProvider, BP = DurableBlobProvider> =
+    EthereumDataSource<CP, BP>;
 
 /// Hilo payload attributes builder for the `AttributesQueue` stage of the derivation
 /// pipeline.
-pub type HiloAttributesBuilder =
-    StatefulAttributesBuilder<InMemoryChainProvider, AlloyL2ChainProvider>;
+pub type HiloAttributesBuilder<CP = HiloChainProvider, L2P = AlloyL2ChainProvider> =
+    StatefulAttributesBuilder<CP, L2P>;
 
 /// Hilo attributes queue for the derivation pipeline.
-pub type HiloAttributesQueue<DAP> = AttributesQueue<
-    BatchProvider<
-        BatchStream<
-            ChannelReader<
-                ChannelProvider<FrameQueue<L1Retrieval<DAP, L1Traversal<InMemoryChainProvider>>>>,
+pub type HiloAttributesQueue<DAP, CP = HiloChainProvider, L2P = AlloyL2ChainProvider> =
+    AttributesQueue<
+        BatchProvider<
+            BatchStream<
+                ChannelReader<ChannelProvider<FrameQueue<L1Retrieval<DAP, L1Traversal<CP>>>>>,
+                L2P,
             >,
-            AlloyL2ChainProvider,
+            L2P,
         >,
-        AlloyL2ChainProvider,
-    >,
-    HiloAttributesBuilder,
->;
+        HiloAttributesBuilder<CP, L2P>,
+    >;
 
 /// Hilo derivation pipeline.
+///
+/// The pipeline is generic over its L1 chain, blob and L2 chain providers. By default it
+/// uses the L1 chain data committed in memory with a fallback to the L1 RPC, and the online
+/// blob and L2 providers.
 #[derive(Debug)]
-pub struct HiloPipeline {
+pub struct HiloPipeline<
+    CP = HiloChainProvider,
+    BP = DurableBlobProvider,
+    L2P = AlloyL2ChainProvider,
+> where
+    CP: ChainProvider + Send + Sync + Clone + Debug,
+    BP: BlobProvider + Send + Sync + Clone + Debug,
+    L2P: L2ChainProvider + Send + Sync + Clone + Debug,
+{
     /// The internal derivation pipeline.
-    pub pipeline: HiloDerivationPipeline,
+    pub pipeline: HiloDerivationPipeline<CP, BP, L2P>,
     /// The chain provider.
     #[allow(unused)]
-    pub chain_provider: InMemoryChainProvider,
+    pub chain_provider: CP,
     /// The L2 chain provider.
     #[allow(unused)]
-    pub l2_chain_provider: AlloyL2ChainProvider,
+    pub l2_chain_provider: L2P,
+    /// An optional sender to publish [DriverEvent]s to.
+    pub events: Option<broadcast::Sender<DriverEvent>>,
+    /// The last L2 safe head the pipeline was stepped with.
+    safe_head: Option<L2BlockInfo>,
 }
 
-impl HiloPipeline {
+impl<CP, BP, L2P> HiloPipeline<CP, BP, L2P>
+where
+    CP: ChainProvider + Send + Sync + Clone + Debug,
+    BP: BlobProvider + Send + Sync + Clone + Debug,
+    L2P: L2ChainProvider + Send + Sync + Clone + Debug,
+{
     /// Constructs a new Hilo derivation pipeline.
     pub fn new(
         cfg: Arc<RollupConfig>,
         sync_start: PipelineCursor,
-        blob_provider: DurableBlobProvider,
-        chain_provider: InMemoryChainProvider,
-        l2_chain_provider: AlloyL2ChainProvider,
+        blob_provider: BP,
+        chain_provider: CP,
+        l2_chain_provider: L2P,
     ) -> Self {
         let attributes = StatefulAttributesBuilder::new(
             cfg.clone(),
@@ -85,7 +112,21 @@ impl HiloPipeline {
             .builder(attributes)
             .origin(sync_start.origin())
             .build();
-        Self { pipeline, chain_provider, l2_chain_provider }
+        Self { pipeline, chain_provider, l2_chain_provider, events: None, safe_head: None }
+    }
+
+    /// Sets the sender that [DriverEvent]s are published to.
+    pub fn with_events(mut self, events: broadcast::Sender<DriverEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Publishes a [DriverEvent] if an event sender is set.
+    fn emit(&self, event: DriverEvent) {
+        if let Some(events) = &self.events {
+            // Sending only fails if there are no subscribers.
+            _ = events.send(event);
+        }
     }
 }
 
@@ -98,38 +139,96 @@ impl DriverPipeline<HiloDerivationPipeline> for HiloPipeline {
 }
 
 #[async_trait]
-impl SignalReceiver for HiloPipeline {
+impl<CP, BP, L2P> SignalReceiver for HiloPipeline<CP, BP, L2P>
+where
+    CP: ChainProvider + Send + Sync + Clone + Debug,
+    BP: BlobProvider + Send + Sync + Clone + Debug,
+    L2P: L2ChainProvider + Send + Sync + Clone + Debug,
+{
     /// Receives a signal from the driver.
     async fn signal(&mut self, signal: Signal) -> PipelineResult<()> {
-        self.pipeline.signal(signal).await
+        let reset = match &signal {
+            Signal::Reset(ResetSignal { l1_origin, l2_safe_head, .. }) => {
+                Some(DriverEvent::PipelineReset {
+                    l1_origin: *l1_origin,
+                    l2_safe_head: *l2_safe_head,
+                })
+            }
+            _ => None,
+        };
+        self.pipeline.signal(signal).await?;
+        if let Some(event) = reset {
+            self.emit(event);
+        }
+        Ok(())
     }
 }
 
-impl OriginProvider for HiloPipeline {
+impl<CP, BP, L2P> OriginProvider for HiloPipeline<CP, BP, L2P>
+where
+    CP: ChainProvider + Send + Sync + Clone + Debug,
+    BP: BlobProvider + Send + Sync + Clone + Debug,
+    L2P: L2ChainProvider + Send + Sync + Clone + Debug,
+{
     /// Returns the optional L1 [BlockInfo] origin.
     fn origin(&self) -> Option<BlockInfo> {
         self.pipeline.origin()
     }
 }
 
-impl Iterator for HiloPipeline {
+impl<CP, BP, L2P> Iterator for HiloPipeline<CP, BP, L2P>
+where
+    CP: ChainProvider + Send + Sync + Clone + Debug,
+    BP: BlobProvider + Send + Sync + Clone + Debug,
+    L2P: L2ChainProvider + Send + Sync + Clone + Debug,
+{
     type Item = OpAttributesWithParent;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.pipeline.next()
+        let attributes = self.pipeline.next()?;
+        self.emit(DriverEvent::AttributesDerived(Box::new(attributes.clone())));
+        Some(attributes)
     }
 }
 
 #[async_trait]
-impl Pipeline for HiloPipeline {
+impl<CP, BP, L2P> Pipeline for HiloPipeline<CP, BP, L2P>
+where
+    CP: ChainProvider + Send + Sync + Clone + Debug,
+    BP: BlobProvider + Send + Sync + Clone + Debug,
+    L2P: L2ChainProvider + Send + Sync + Clone + Debug,
+{
     /// Peeks at the next [OpAttributesWithParent] from the pipeline.
     fn peek(&self) -> Option<&OpAttributesWithParent> {
         self.pipeline.peek()
     }
 
     /// Attempts to progress the pipeline.
+    ///
+    /// Temporary failures to fetch L1 data or to advance to the next L1 block mean the data
+    /// is not available yet. They are reported as the end of the source, so that the driver
+    /// returns with the blocks derived so far and waits for new L1 data, instead of retrying
+    /// in a loop it cannot be interrupted in.
     async fn step(&mut self, cursor: L2BlockInfo) -> StepResult {
-        self.pipeline.step(cursor).await
+        if self.safe_head.map_or(true, |head| head != cursor) {
+            self.safe_head = Some(cursor);
+            self.emit(DriverEvent::SafeHeadUpdated(cursor));
+        }
+
+        let result = match self.pipeline.step(cursor).await {
+            StepResult::OriginAdvanceErr(PipelineErrorKind::Temporary(e))
+            | StepResult::StepFailed(PipelineErrorKind::Temporary(
+                e @ PipelineError::Provider(_),
+            )) => {
+                debug!("Waiting for L1 data: {}", e);
+                StepResult::StepFailed(PipelineError::EndOfSource.crit())
+            }
+            result => result,
+        };
+        if let (StepResult::AdvancedOrigin, Some(origin)) = (&result, self.pipeline.origin()) {
+            self.emit(DriverEvent::L1OriginAdvanced(origin));
+        }
+        result
     }
 
     /// Returns the rollup config.
@@ -0,0 +1,77 @@
+//! The L1 chain provider of the derivation pipeline.
+
+use alloy_consensus::{Header, Receipt, TxEnvelope};
+use alloy_primitives::B256;
+use async_trait::async_trait;
+use kona_derive::traits::ChainProvider;
+use op_alloy_protocol::BlockInfo;
+use url::Url;
+
+use hilo_providers_alloy::{AlloyChainProvider, AlloyChainProviderError};
+use hilo_providers_local::InMemoryChainProvider;
+
+/// A [ChainProvider] that serves the L1 chain data committed to an [InMemoryChainProvider],
+/// and fetches anything missing from the L1 RPC.
+///
+/// Execution extension notifications carry full L1 blocks, but a standalone context only
+/// receives new headers, and derivation starts behind the L1 tip, so most reads fall
+/// through to the RPC outside of an execution extension.
+#[derive(Debug, Clone)]
+pub struct HiloChainProvider {
+    /// The L1 chain data committed in memory.
+    cache: InMemoryChainProvider,
+    /// The L1 RPC provider.
+    rpc: AlloyChainProvider,
+    /// The URL of the L1 RPC.
+    rpc_url: Url,
+}
+
+impl HiloChainProvider {
+    /// Creates a new [HiloChainProvider] over the given in-memory chain data and L1 RPC.
+    pub fn new(cache: InMemoryChainProvider, rpc_url: Url) -> Self {
+        let rpc = AlloyChainProvider::new_http(rpc_url.clone());
+        Self { cache, rpc, rpc_url }
+    }
+
+    /// Flushes the in-memory chain data and the RPC caches of this provider, after a reorg.
+    pub fn flush(&mut self) {
+        self.cache.flush();
+        self.rpc = AlloyChainProvider::new_http(self.rpc_url.clone());
+    }
+}
+
+#[async_trait]
+impl ChainProvider for HiloChainProvider {
+    type Error = AlloyChainProviderError;
+
+    async fn header_by_hash(&mut self, hash: B256) -> Result<Header, Self::Error> {
+        match self.cache.header_by_hash(hash).await {
+            Ok(header) => Ok(header),
+            Err(_) => self.rpc.header_by_hash(hash).await,
+        }
+    }
+
+    async fn block_info_by_number(&mut self, number: u64) -> Result<BlockInfo, Self::Error> {
+        match self.cache.block_info_by_number(number).await {
+            Ok(block_info) => Ok(block_info),
+            Err(_) => self.rpc.block_info_by_number(number).await,
+        }
+    }
+
+    async fn receipts_by_hash(&mut self, hash: B256) -> Result<Vec<Receipt>, Self::Error> {
+        match self.cache.receipts_by_hash(hash).await {
+            Ok(receipts) => Ok(receipts),
+            Err(_) => self.rpc.receipts_by_hash(hash).await,
+        }
+    }
+
+    async fn block_info_and_transactions_by_hash(
+        &mut self,
+        hash: B256,
+    ) -> Result<(BlockInfo, Vec<TxEnvelope>), Self::Error> {
+        match self.cache.block_info_and_transactions_by_hash(hash).await {
+            Ok(block) => Ok(block),
+            Err(_) => self.rpc.block_info_and_transactions_by_hash(hash).await,
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! Replay fixtures of a chain carrying real batches, for tests.
+//!
+//! The batches go through the same encoding a batcher applies: singular batches are
+//! RLP encoded into a zlib channel, framed, and posted by the batcher to the batch inbox
+//! in signed L1 transactions, either as calldata or in a blob.
+
+use alloy_consensus::{
+    Header, Receipt, SignableTransaction, TxEip1559, TxEip4844, TxEip4844Variant, TxEnvelope,
+};
+use alloy_eips::{
+    eip2718::Encodable2718,
+    eip4844::{Blob, BlobTransactionSidecarItem, VERSIONED_HASH_VERSION_KZG},
+    BlockNumHash,
+};
+use alloy_primitives::{address, Address, Bytes, PrimitiveSignature, TxKind, B256, U256};
+use alloy_rlp::Encodable;
+use k256::ecdsa::SigningKey;
+use op_alloy_genesis::{ChainGenesis, RollupConfig, SystemConfig};
+use op_alloy_protocol::BlockInfo;
+
+use crate::replay::{L1BlockFixture, L2BlockFixture, ReplayFixture};
+
+/// The number of the first L1 block, past the reorg cache window of the
+/// [crate::StandaloneContext].
+const FIRST_L1_BLOCK: u64 = 100;
+
+/// The number of L1 blocks in the fixture.
+const L1_BLOCKS: u64 = 16;
+
+/// The chain id of the L1 chain.
+const L1_CHAIN_ID: u64 = 900;
+
+/// The chain id of the L2 chain.
+const L2_CHAIN_ID: u64 = 901;
+
+/// The address of the batch inbox.
+const BATCH_INBOX: Address = address!("ff00000000000000000000000000000000000901");
+
+/// The private key of the batcher.
+const BATCHER_KEY: B256 = B256::repeat_byte(0x11);
+
+/// The version byte of batcher transaction data.
+const DERIVATION_VERSION_0: u8 = 0;
+
+/// The type byte of a singular batch.
+const SINGULAR_BATCH_TYPE: u8 = 0;
+
+/// The version byte of the blob encoding.
+const BLOB_ENCODING_VERSION: u8 = 0;
+
+/// The number of input bytes encoded per round of four field elements of a blob.
+const BLOB_ROUND_SIZE: usize = 127;
+
+/// Returns the address of the batcher.
+pub(crate) fn batcher_address() -> Address {
+    Address::from_private_key(&batcher_key())
+}
+
+/// Returns the user transaction with the given nonce, sent on the L2 chain.
+pub(crate) fn user_transaction(nonce: u64) -> Bytes {
+    let tx = TxEip1559 {
+        chain_id: L2_CHAIN_ID,
+        nonce,
+        gas_limit: 21_000,
+        max_fee_per_gas: 10,
+        max_priority_fee_per_gas: 1,
+        to: TxKind::Call(Address::with_last_byte(0xaa)),
+        value: U256::from(1),
+        ..Default::default()
+    };
+    let signature = sign(tx.signature_hash());
+    TxEnvelope::Eip1559(tx.into_signed(signature)).encoded_2718().into()
+}
+
+/// Returns a fixture of an L1 chain carrying two batches on top of the L2 genesis.
+///
+/// The first L2 block is batched with [user_transaction] `0` in calldata of the second
+/// L1 block, and the second L2 block with [user_transaction] `1` in a blob of the third.
+/// Later L2 blocks are deposit-only, forced by the sequencing window. The fixture has no
+/// expected attributes.
+pub(crate) async fn batch_fixture() -> ReplayFixture {
+    let mut l1_blocks = Vec::new();
+    let mut parent_hash = B256::ZERO;
+    for number in FIRST_L1_BLOCK..FIRST_L1_BLOCK + L1_BLOCKS {
+        let header = Header {
+            parent_hash,
+            number,
+            timestamp: number * 12,
+            gas_limit: 30_000_000,
+            base_fee_per_gas: Some(7),
+            ..Default::default()
+        };
+        parent_hash = header.hash_slow();
+        l1_blocks.push(L1BlockFixture {
+            header,
+            transactions: Vec::new(),
+            receipts: Vec::new(),
+            blob_sidecars: Vec::new(),
+        });
+    }
+    let epoch = l1_blocks[0].block_info();
+
+    let l2_genesis = Header {
+        number: 0,
+        timestamp: epoch.timestamp,
+        gas_limit: 30_000_000,
+        ..Default::default()
+    };
+    let rollup_config = RollupConfig {
+        genesis: ChainGenesis {
+            l1: epoch.id(),
+            l2: BlockNumHash { number: 0, hash: l2_genesis.hash_slow() },
+            l2_time: l2_genesis.timestamp,
+            system_config: Some(SystemConfig {
+                batcher_address: batcher_address(),
+                gas_limit: 30_000_000,
+                ..Default::default()
+            }),
+        },
+        block_time: 2,
+        max_sequencer_drift: 600,
+        seq_window_size: 4,
+        channel_timeout: 8,
+        l1_chain_id: L1_CHAIN_ID,
+        l2_chain_id: L2_CHAIN_ID,
+        regolith_time: Some(0),
+        canyon_time: Some(0),
+        delta_time: Some(0),
+        // Blobs are only read from L1 blocks after Ecotone.
+        ecotone_time: Some(0),
+        batch_inbox_address: BATCH_INBOX,
+        deposit_contract_address: Address::with_last_byte(1),
+        l1_system_config_address: Address::with_last_byte(2),
+        ..Default::default()
+    };
+    let block_time = rollup_config.block_time;
+
+    let mut fixture = ReplayFixture {
+        rollup_config,
+        l1_blocks,
+        l2_blocks: vec![L2BlockFixture { header: l2_genesis.clone(), transactions: Vec::new() }],
+        l2_safe_head: 0,
+        expected_attributes: Vec::new(),
+    };
+
+    let batch = singular_batch(
+        l2_genesis.hash_slow(),
+        &epoch,
+        l2_genesis.timestamp + block_time,
+        user_transaction(0),
+    );
+    let calldata = batcher_transaction(0, batcher_data(1, &batch));
+    fixture.l1_blocks[1].transactions.push(calldata);
+    fixture.l1_blocks[1].receipts.push(Receipt { status: true.into(), ..Default::default() });
+
+    // The second batch builds on the block the execution stand-in built from the first.
+    let derived = fixture.replay().await.unwrap();
+    let block = fixture.l2_chain_provider().execute(&derived[0]).unwrap();
+
+    let batch = singular_batch(
+        block.block_info.hash,
+        &epoch,
+        block.block_info.timestamp + block_time,
+        user_transaction(1),
+    );
+    let (blob_tx, sidecar) = blob_transaction(1, &batcher_data(2, &batch));
+    fixture.l1_blocks[2].transactions.push(blob_tx);
+    fixture.l1_blocks[2].receipts.push(Receipt { status: true.into(), ..Default::default() });
+    fixture.l1_blocks[2].blob_sidecars.push(sidecar);
+
+    fixture
+}
+
+/// Returns the signing key of the batcher.
+fn batcher_key() -> SigningKey {
+    SigningKey::from_slice(BATCHER_KEY.as_slice()).unwrap()
+}
+
+/// Signs the given signature hash with the batcher key.
+fn sign(hash: B256) -> PrimitiveSignature {
+    let (signature, recovery_id) = batcher_key().sign_prehash_recoverable(hash.as_slice()).unwrap();
+    PrimitiveSignature::from_signature_and_parity(signature, recovery_id.is_y_odd())
+}
+
+/// Returns a batcher transaction posting the given data to the batch inbox in calldata.
+fn batcher_transaction(nonce: u64, input: Bytes) -> TxEnvelope {
+    let tx = TxEip1559 {
+        chain_id: L1_CHAIN_ID,
+        nonce,
+        gas_limit: 1_000_000,
+        max_fee_per_gas: 10,
+        max_priority_fee_per_gas: 1,
+        to: TxKind::Call(BATCH_INBOX),
+        input,
+        ..Default::default()
+    };
+    let signature = sign(tx.signature_hash());
+    TxEnvelope::Eip1559(tx.into_signed(signature))
+}
+
+/// Returns a batcher transaction posting the given data to the batch inbox in a blob,
+/// along with the sidecar of the blob.
+fn blob_transaction(nonce: u64, data: &[u8]) -> (TxEnvelope, BlobTransactionSidecarItem) {
+    // Blobs are served without verifying commitments, so any versioned hash will do.
+    let mut versioned_hash = B256::repeat_byte(0x22);
+    versioned_hash[0] = VERSIONED_HASH_VERSION_KZG;
+    let tx = TxEip4844Variant::TxEip4844(TxEip4844 {
+        chain_id: L1_CHAIN_ID,
+        nonce,
+        gas_limit: 21_000,
+        max_fee_per_gas: 10,
+        max_priority_fee_per_gas: 1,
+        to: BATCH_INBOX,
+        blob_versioned_hashes: vec![versioned_hash],
+        max_fee_per_blob_gas: 1,
+        ..Default::default()
+    });
+    let signature = sign(tx.signature_hash());
+    let sidecar = BlobTransactionSidecarItem {
+        index: 0,
+        blob: Box::new(encode_blob(data)),
+        kzg_commitment: Default::default(),
+        kzg_proof: Default::default(),
+    };
+    (TxEnvelope::Eip4844(tx.into_signed(signature)), sidecar)
+}
+
+/// Returns a singular batch of a single transaction, prefixed with its type byte.
+fn singular_batch(parent_hash: B256, epoch: &BlockInfo, timestamp: u64, tx: Bytes) -> Vec<u8> {
+    let transactions = vec![tx];
+    let payload_length = parent_hash.length()
+        + epoch.number.length()
+        + epoch.hash.length()
+        + timestamp.length()
+        + transactions.length();
+
+    let mut batch = vec![SINGULAR_BATCH_TYPE];
+    alloy_rlp::Header { list: true, payload_length }.encode(&mut batch);
+    parent_hash.encode(&mut batch);
+    epoch.number.encode(&mut batch);
+    epoch.hash.encode(&mut batch);
+    timestamp.encode(&mut batch);
+    transactions.encode(&mut batch);
+    batch
+}
+
+/// Returns the batcher transaction data of a channel holding the given batch, sent in a
+/// single frame.
+fn batcher_data(channel_id: u8, batch: &[u8]) -> Bytes {
+    let mut channel = Vec::new();
+    batch.encode(&mut channel);
+    let frame_data = zlib_stored(&channel);
+
+    let mut data = vec![DERIVATION_VERSION_0];
+    data.extend_from_slice(&[channel_id; 16]);
+    data.extend_from_slice(&0u16.to_be_bytes());
+    data.extend_from_slice(&(frame_data.len() as u32).to_be_bytes());
+    data.extend_from_slice(&frame_data);
+    // The frame is the last of the channel.
+    data.push(1);
+    data.into()
+}
+
+/// Returns a zlib stream holding the data in a single stored, uncompressed, deflate block.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let len = u16::try_from(data.len()).expect("data fits in a stored block");
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    let mut stream = vec![0x78, 0x01];
+    // A final block, stored without compression.
+    stream.push(0x01);
+    stream.extend_from_slice(&len.to_le_bytes());
+    stream.extend_from_slice(&(!len).to_le_bytes());
+    stream.extend_from_slice(data);
+    stream.extend_from_slice(&((b << 16) | a).to_be_bytes());
+    stream
+}
+
+/// Encodes the data into a blob, following version 0 of the blob encoding.
+///
+/// The version byte and the 3-byte data length are followed by the data, and each round of
+/// 127 input bytes fills four field elements: 31 bytes of each element are copied as is,
+/// and the remaining 3 bytes are spread over the 6 low bits of each element's first byte.
+fn encode_blob(data: &[u8]) -> Blob {
+    let len = data.len() as u32;
+    let mut input = vec![BLOB_ENCODING_VERSION, (len >> 16) as u8, (len >> 8) as u8, len as u8];
+    input.extend_from_slice(data);
+    input.resize(input.len().div_ceil(BLOB_ROUND_SIZE) * BLOB_ROUND_SIZE, 0);
+
+    let mut blob = Blob::ZERO;
+    for (round, input) in input.chunks(BLOB_ROUND_SIZE).enumerate() {
+        let (x, y, z) = (input[31], input[63], input[95]);
+        let high_bytes = [
+            x & 0b0011_1111,
+            (y & 0b0000_1111) | ((x & 0b1100_0000) >> 2),
+            z & 0b0011_1111,
+            ((z & 0b1100_0000) >> 2) | ((y & 0b1111_0000) >> 4),
+        ];
+        for (i, high_byte) in high_bytes.into_iter().enumerate() {
+            let element = &mut blob[(round * 4 + i) * 32..][..32];
+            element[0] = high_byte;
+            element[1..].copy_from_slice(&input[i * 32..][..31]);
+        }
+    }
+    blob
+}
@@ -0,0 +1,565 @@
+//! Offline replay of recorded chain data through the [HiloPipeline].
+//!
+//! A [ReplayFixture] is a JSON file holding the L1 blocks (headers, transactions, receipts
+//! and blob sidecars) and L2 blocks needed to run derivation, along with the payload
+//! attributes the pipeline is expected to produce. Replaying a fixture runs the full
+//! [HiloPipeline] against fixture-backed providers, so derivation can be tested
+//! deterministically without any network access.
+
+use alloy_consensus::{Block, BlockBody, Header, Receipt, TxEnvelope};
+use alloy_eips::eip4844::BlobTransactionSidecarItem;
+use kona_derive::{
+    errors::{PipelineError, PipelineErrorKind},
+    traits::{L2ChainProvider, OriginProvider, Pipeline, SignalReceiver},
+    types::{ResetSignal, StepResult},
+};
+use kona_driver::PipelineCursor;
+use op_alloy_consensus::{OpBlock, OpTxEnvelope};
+use op_alloy_genesis::RollupConfig;
+use op_alloy_protocol::{BatchValidationProvider, BlockInfo, L2BlockInfo};
+use op_alloy_rpc_types_engine::{OpAttributesWithParent, OpPayloadAttributes};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, sync::Arc};
+
+use crate::{HiloPipeline, ReplayContext};
+
+#[cfg(test)]
+pub(crate) mod fixtures;
+
+mod providers;
+pub use providers::{
+    ReplayBlobProvider, ReplayChainProvider, ReplayL2ChainProvider, ReplayProviderError,
+};
+
+/// A [HiloPipeline] backed by replay providers.
+pub type ReplayPipeline =
+    HiloPipeline<ReplayChainProvider, ReplayBlobProvider, ReplayL2ChainProvider>;
+
+/// The number of consecutive pipeline steps without progress after which a replay
+/// considers the fixture exhausted.
+const MAX_STALLED_STEPS: usize = 1024;
+
+/// An error thrown while loading or replaying a [ReplayFixture].
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    /// An I/O error.
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The fixture could not be encoded or decoded.
+    #[error("fixture encoding error: {0}")]
+    Encoding(#[from] serde_json::Error),
+    /// The fixture contains no L1 blocks.
+    #[error("fixture contains no L1 blocks")]
+    NoL1Blocks,
+    /// A replay provider error.
+    #[error("provider error: {0}")]
+    Provider(#[from] ReplayProviderError),
+    /// The derivation pipeline failed.
+    #[error("pipeline error: {0}")]
+    Pipeline(PipelineErrorKind),
+    /// The pipeline derived a different number of attributes than expected.
+    #[error("expected {expected} attributes, derived {derived}")]
+    Count {
+        /// The number of expected attributes.
+        expected: usize,
+        /// The number of derived attributes.
+        derived: usize,
+    },
+    /// The derived attributes do not match the expected attributes.
+    #[error("attributes {index} do not match: expected {expected:?}, derived {derived:?}")]
+    Mismatch {
+        /// The index of the mismatched attributes.
+        index: usize,
+        /// The expected attributes.
+        expected: Box<ExpectedAttributes>,
+        /// The derived attributes.
+        derived: Box<ExpectedAttributes>,
+    },
+}
+
+/// An L1 block recorded in a [ReplayFixture].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct L1BlockFixture {
+    /// The block header.
+    pub header: Header,
+    /// The transactions in the block.
+    #[serde(default)]
+    pub transactions: Vec<TxEnvelope>,
+    /// The receipts of the transactions in the block.
+    #[serde(default)]
+    pub receipts: Vec<Receipt>,
+    /// The blob sidecars of the blob transactions in the block.
+    #[serde(default)]
+    pub blob_sidecars: Vec<BlobTransactionSidecarItem>,
+}
+
+impl L1BlockFixture {
+    /// Returns the [BlockInfo] of the block.
+    pub fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            hash: self.header.hash_slow(),
+            number: self.header.number,
+            parent_hash: self.header.parent_hash,
+            timestamp: self.header.timestamp,
+        }
+    }
+}
+
+/// An L2 block recorded in a [ReplayFixture].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct L2BlockFixture {
+    /// The block header.
+    pub header: Header,
+    /// The transactions in the block.
+    #[serde(default)]
+    pub transactions: Vec<OpTxEnvelope>,
+}
+
+impl L2BlockFixture {
+    /// Returns the block as an [OpBlock].
+    pub fn block(&self) -> OpBlock {
+        Block {
+            header: self.header.clone(),
+            body: BlockBody {
+                transactions: self.transactions.clone(),
+                ommers: Vec::new(),
+                withdrawals: None,
+            },
+        }
+    }
+}
+
+/// Payload attributes the pipeline is expected to derive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpectedAttributes {
+    /// The parent block of the attributes.
+    pub parent: L2BlockInfo,
+    /// The payload attributes.
+    pub attributes: OpPayloadAttributes,
+    /// Whether the attributes are the last in a span batch.
+    #[serde(default)]
+    pub is_last_in_span: bool,
+}
+
+impl From<&OpAttributesWithParent> for ExpectedAttributes {
+    fn from(attributes: &OpAttributesWithParent) -> Self {
+        Self {
+            parent: attributes.parent,
+            attributes: attributes.attributes.clone(),
+            is_last_in_span: attributes.is_last_in_span,
+        }
+    }
+}
+
+/// Recorded chain data and expected derivation output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayFixture {
+    /// The rollup config of the L2 chain.
+    pub rollup_config: RollupConfig,
+    /// The L1 blocks, in order. Derivation starts at the first block.
+    pub l1_blocks: Vec<L1BlockFixture>,
+    /// The L2 blocks. Must include the starting safe head.
+    pub l2_blocks: Vec<L2BlockFixture>,
+    /// The number of the L2 safe head derivation starts from.
+    pub l2_safe_head: u64,
+    /// The attributes the pipeline is expected to derive, in order.
+    #[serde(default)]
+    pub expected_attributes: Vec<ExpectedAttributes>,
+}
+
+impl ReplayFixture {
+    /// Loads a [ReplayFixture] from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let bytes = fs::read(path)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Writes the [ReplayFixture] to a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Returns a [ReplayContext] that yields a notification for each L1 block.
+    pub fn context(&self) -> ReplayContext {
+        ReplayContext::new(self.l1_blocks.iter().map(|block| block.header.clone()))
+    }
+
+    /// Returns a [ReplayChainProvider] serving the fixture's L1 blocks.
+    pub fn chain_provider(&self) -> ReplayChainProvider {
+        ReplayChainProvider::new(&self.l1_blocks)
+    }
+
+    /// Returns a [ReplayBlobProvider] serving the fixture's blob sidecars.
+    pub fn blob_provider(&self) -> ReplayBlobProvider {
+        ReplayBlobProvider::new(&self.l1_blocks)
+    }
+
+    /// Returns a [ReplayL2ChainProvider] serving the fixture's L2 blocks.
+    pub fn l2_chain_provider(&self) -> ReplayL2ChainProvider {
+        ReplayL2ChainProvider::new(Arc::new(self.rollup_config.clone()), &self.l2_blocks)
+    }
+
+    /// Runs the fixture through a [ReplayPipeline] and returns the derived attributes.
+    ///
+    /// Derivation stops once as many attributes as expected have been derived, or when the
+    /// pipeline stops making progress because the fixture's L1 blocks are exhausted.
+    pub async fn replay(&self) -> Result<Vec<OpAttributesWithParent>, ReplayError> {
+        let cfg = Arc::new(self.rollup_config.clone());
+        let origin = self.l1_blocks.first().ok_or(ReplayError::NoL1Blocks)?.block_info();
+        let mut l2_provider = self.l2_chain_provider();
+        let mut safe_head = l2_provider.l2_block_info_by_number(self.l2_safe_head).await?;
+        let system_config =
+            l2_provider.system_config_by_number(self.l2_safe_head, cfg.clone()).await?;
+
+        let channel_timeout = cfg.channel_timeout(safe_head.block_info.timestamp);
+        let cursor = PipelineCursor::new(channel_timeout, origin);
+        let mut pipeline: ReplayPipeline = HiloPipeline::new(
+            cfg,
+            cursor,
+            self.blob_provider(),
+            self.chain_provider(),
+            l2_provider.clone(),
+        );
+        let reset = ResetSignal {
+            l1_origin: origin,
+            l2_safe_head: safe_head,
+            system_config: Some(system_config),
+        };
+        pipeline.signal(reset.signal()).await.map_err(ReplayError::Pipeline)?;
+
+        let limit = match self.expected_attributes.len() {
+            0 => usize::MAX,
+            n => n,
+        };
+        let mut derived = Vec::new();
+        let mut stalled = 0;
+        while derived.len() < limit && stalled < MAX_STALLED_STEPS {
+            match pipeline.step(safe_head).await {
+                StepResult::PreparedAttributes => {
+                    let Some(attributes) = pipeline.next() else { continue };
+                    safe_head = l2_provider.execute(&attributes)?;
+                    derived.push(attributes);
+                    stalled = 0;
+                }
+                StepResult::AdvancedOrigin => stalled = 0,
+                StepResult::OriginAdvanceErr(e) | StepResult::StepFailed(e) => match e {
+                    PipelineErrorKind::Temporary(_) => stalled += 1,
+                    // The fixture's L1 blocks are exhausted.
+                    PipelineErrorKind::Critical(PipelineError::EndOfSource) => break,
+                    e => return Err(ReplayError::Pipeline(e)),
+                },
+            }
+        }
+        debug!(
+            "Replay derived {} attributes up to L1 origin {:?}",
+            derived.len(),
+            pipeline.origin().map(|origin| origin.number)
+        );
+        Ok(derived)
+    }
+
+    /// Replays the fixture and compares the derived attributes against the expected ones.
+    pub async fn verify(&self) -> Result<(), ReplayError> {
+        let derived = self.replay().await?;
+        if derived.len() != self.expected_attributes.len() {
+            return Err(ReplayError::Count {
+                expected: self.expected_attributes.len(),
+                derived: derived.len(),
+            });
+        }
+        for (index, (expected, derived)) in
+            self.expected_attributes.iter().zip(&derived).enumerate()
+        {
+            let derived = ExpectedAttributes::from(derived);
+            if *expected != derived {
+                return Err(ReplayError::Mismatch {
+                    index,
+                    expected: Box::new(expected.clone()),
+                    derived: Box::new(derived),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Context;
+    use alloy_eips::{eip2718::Decodable2718, BlockNumHash};
+    use alloy_primitives::{address, keccak256, Address, TxKind, B256, U256};
+    use op_alloy_genesis::{ChainGenesis, SystemConfig};
+
+    /// The selector of `setL1BlockValues`, called by the pre-Ecotone L1 info deposit.
+    const SET_L1_BLOCK_VALUES_SELECTOR: [u8; 4] = [0x01, 0x5d, 0x8e, 0xb9];
+
+    /// Payload attributes expected of the [empty_chain_fixture], as laid out in
+    /// `testdata/replay/deposit_only.json`.
+    ///
+    /// The expectations were worked out by hand from the derivation spec rather than
+    /// recorded from the pipeline: each deposit-only block is 2 seconds after its parent,
+    /// and moves to the next epoch once its timestamp reaches that of the next L1 block.
+    #[derive(Debug, Deserialize)]
+    struct SpecAttributes {
+        parent_number: u64,
+        timestamp: u64,
+        prev_randao: B256,
+        suggested_fee_recipient: Address,
+        gas_limit: u64,
+        no_tx_pool: bool,
+        withdrawals: serde_json::Value,
+        parent_beacon_block_root: Option<B256>,
+        l1_info: SpecL1Info,
+    }
+
+    /// The L1 info deposit expected at the start of each block.
+    #[derive(Debug, Deserialize)]
+    struct SpecL1Info {
+        from: Address,
+        to: Address,
+        mint: u128,
+        value: U256,
+        gas_limit: u64,
+        is_system_transaction: bool,
+        number: u64,
+        timestamp: u64,
+        base_fee: U256,
+        sequence_number: u64,
+        batcher_hash: B256,
+        l1_fee_overhead: U256,
+        l1_fee_scalar: U256,
+    }
+
+    /// Builds a fixture with an empty L1 chain, so that derivation only produces the
+    /// deposit-only blocks forced by the sequencing window.
+    fn empty_chain_fixture() -> ReplayFixture {
+        let mut l1_blocks = Vec::new();
+        let mut parent_hash = B256::ZERO;
+        for number in 0..16 {
+            let header = Header {
+                parent_hash,
+                number,
+                timestamp: number * 12,
+                gas_limit: 30_000_000,
+                base_fee_per_gas: Some(7),
+                ..Default::default()
+            };
+            parent_hash = header.hash_slow();
+            l1_blocks.push(L1BlockFixture {
+                header,
+                transactions: Vec::new(),
+                receipts: Vec::new(),
+                blob_sidecars: Vec::new(),
+            });
+        }
+
+        let l2_genesis =
+            Header { number: 0, timestamp: 0, gas_limit: 30_000_000, ..Default::default() };
+        let rollup_config = RollupConfig {
+            genesis: ChainGenesis {
+                l1: l1_blocks[0].block_info().id(),
+                l2: BlockNumHash { number: 0, hash: l2_genesis.hash_slow() },
+                l2_time: 0,
+                system_config: Some(SystemConfig {
+                    batcher_address: address!("6887246668a3b87f54deb3b94ba47a6f63f32985"),
+                    gas_limit: 30_000_000,
+                    ..Default::default()
+                }),
+            },
+            block_time: 2,
+            max_sequencer_drift: 600,
+            seq_window_size: 4,
+            channel_timeout: 8,
+            l1_chain_id: 900,
+            l2_chain_id: 901,
+            batch_inbox_address: address!("ff00000000000000000000000000000000000901"),
+            deposit_contract_address: Address::with_last_byte(1),
+            l1_system_config_address: Address::with_last_byte(2),
+            ..Default::default()
+        };
+
+        ReplayFixture {
+            rollup_config,
+            l1_blocks,
+            l2_blocks: vec![L2BlockFixture { header: l2_genesis, transactions: Vec::new() }],
+            l2_safe_head: 0,
+            expected_attributes: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_empty_chain() {
+        let fixture = empty_chain_fixture();
+        let derived = fixture.replay().await.unwrap();
+        assert!(!derived.is_empty());
+
+        let block_time = fixture.rollup_config.block_time;
+        for (i, attributes) in derived.iter().enumerate() {
+            assert_eq!(attributes.parent.block_info.number, i as u64);
+            assert_eq!(
+                attributes.attributes.payload_attributes.timestamp,
+                attributes.parent.block_info.timestamp + block_time
+            );
+            // Only the L1 info deposit is included in deposit-only blocks.
+            assert_eq!(attributes.attributes.transactions.as_ref().map(Vec::len), Some(1));
+            assert_eq!(attributes.attributes.no_tx_pool, Some(true));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_matches_spec_fixture() {
+        let expected: Vec<SpecAttributes> =
+            serde_json::from_str(include_str!("../../testdata/replay/deposit_only.json")).unwrap();
+        let fixture = empty_chain_fixture();
+        let derived = fixture.replay().await.unwrap();
+        assert!(derived.len() >= expected.len());
+
+        for (expected, derived) in expected.iter().zip(&derived) {
+            assert_eq!(derived.parent.block_info.number, expected.parent_number);
+            let attributes = &derived.attributes;
+            let payload = &attributes.payload_attributes;
+            assert_eq!(payload.timestamp, expected.timestamp);
+            assert_eq!(payload.prev_randao, expected.prev_randao);
+            assert_eq!(payload.suggested_fee_recipient, expected.suggested_fee_recipient);
+            assert_eq!(serde_json::to_value(&payload.withdrawals).unwrap(), expected.withdrawals);
+            assert_eq!(payload.parent_beacon_block_root, expected.parent_beacon_block_root);
+            assert_eq!(attributes.gas_limit, Some(expected.gas_limit));
+            assert_eq!(attributes.no_tx_pool, Some(expected.no_tx_pool));
+
+            let transactions = attributes.transactions.as_ref().unwrap();
+            assert_eq!(transactions.len(), 1);
+            let OpTxEnvelope::Deposit(deposit) =
+                OpTxEnvelope::decode_2718(&mut transactions[0].as_ref()).unwrap()
+            else {
+                panic!("block {} does not start with a deposit", expected.parent_number + 1);
+            };
+
+            let info = &expected.l1_info;
+            let l1_hash = fixture.l1_blocks[info.number as usize].header.hash_slow();
+            let sequence_number = U256::from(info.sequence_number).to_be_bytes::<32>();
+            let deposit_id = keccak256([l1_hash.as_slice(), &sequence_number].concat());
+            let domain = U256::from(1).to_be_bytes::<32>();
+            let source_hash = keccak256([domain.as_slice(), deposit_id.as_slice()].concat());
+            assert_eq!(deposit.source_hash, source_hash);
+            assert_eq!(deposit.from, info.from);
+            assert_eq!(deposit.to, TxKind::Call(info.to));
+            assert_eq!(deposit.mint.unwrap_or_default(), info.mint);
+            assert_eq!(deposit.value, info.value);
+            assert_eq!(deposit.gas_limit, info.gas_limit);
+            assert_eq!(deposit.is_system_transaction, info.is_system_transaction);
+
+            let (selector, words) = deposit.input.split_at(4);
+            assert_eq!(selector, SET_L1_BLOCK_VALUES_SELECTOR);
+            let words = words.chunks(32).map(B256::from_slice).collect::<Vec<_>>();
+            assert_eq!(
+                words,
+                vec![
+                    B256::from(U256::from(info.number)),
+                    B256::from(U256::from(info.timestamp)),
+                    B256::from(info.base_fee),
+                    l1_hash,
+                    B256::from(U256::from(info.sequence_number)),
+                    info.batcher_hash,
+                    B256::from(info.l1_fee_overhead),
+                    B256::from(info.l1_fee_scalar),
+                ]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_expectations() {
+        let mut fixture = empty_chain_fixture();
+        let derived = fixture.replay().await.unwrap();
+        fixture.expected_attributes = derived.iter().map(ExpectedAttributes::from).collect();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.json");
+        fixture.save(&path).unwrap();
+        let loaded = ReplayFixture::load(&path).unwrap();
+        assert_eq!(loaded, fixture);
+        loaded.verify().await.unwrap();
+
+        let mut tampered = loaded;
+        tampered.expected_attributes[0].attributes.payload_attributes.timestamp += 1;
+        assert!(matches!(tampered.verify().await, Err(ReplayError::Mismatch { index: 0, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_replay_batches() {
+        let fixture = fixtures::batch_fixture().await;
+        let derived = fixture.replay().await.unwrap();
+        assert!(derived.len() > 2);
+
+        let genesis = &fixture.rollup_config.genesis;
+        for (i, attributes) in derived.iter().enumerate() {
+            assert_eq!(attributes.parent.block_info.number, i as u64);
+            assert_eq!(
+                attributes.attributes.payload_attributes.timestamp,
+                genesis.l2_time + (i as u64 + 1) * fixture.rollup_config.block_time
+            );
+            let transactions = attributes.attributes.transactions.as_ref().unwrap();
+            match i {
+                // The calldata batch, then the blob batch, both in the genesis epoch.
+                0 | 1 => {
+                    assert_eq!(attributes.parent.l1_origin, genesis.l1);
+                    assert_eq!(transactions.len(), 2);
+                    assert_eq!(transactions[1], fixtures::user_transaction(i as u64));
+                }
+                _ => assert_eq!(transactions.len(), 1),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_ignores_unauthorized_batches() {
+        let mut fixture = fixtures::batch_fixture().await;
+        let system_config = fixture.rollup_config.genesis.system_config.as_mut().unwrap();
+        system_config.batcher_address = Address::with_last_byte(0xbb);
+
+        let derived = fixture.replay().await.unwrap();
+        assert!(!derived.is_empty());
+        for attributes in &derived {
+            assert_eq!(attributes.attributes.transactions.as_ref().map(Vec::len), Some(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_batches() {
+        let mut fixture = fixtures::batch_fixture().await;
+        let derived = fixture.replay().await.unwrap();
+        fixture.expected_attributes = derived.iter().map(ExpectedAttributes::from).collect();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixture.json");
+        fixture.save(&path).unwrap();
+        let loaded = ReplayFixture::load(&path).unwrap();
+        assert_eq!(loaded, fixture);
+        loaded.verify().await.unwrap();
+
+        // Dropping the blob drops the second batch.
+        let mut tampered = loaded;
+        tampered.l1_blocks[2].transactions.clear();
+        tampered.l1_blocks[2].blob_sidecars.clear();
+        let derived = tampered.replay().await.unwrap();
+        assert_eq!(derived[1].attributes.transactions.as_ref().map(Vec::len), Some(1));
+        assert!(tampered.verify().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_context() {
+        let fixture = empty_chain_fixture();
+        let mut ctx = fixture.context();
+        for block in &fixture.l1_blocks {
+            let notification = ctx.recv_notification().await.unwrap();
+            let tip = notification.new_chain().unwrap().tip();
+            assert_eq!(tip.number, block.header.number);
+            assert_eq!(tip.hash, block.header.hash_slow());
+            ctx.send_processed_tip_event(tip);
+        }
+        assert!(ctx.recv_notification().await.is_none());
+        assert_eq!(ctx.processed_tips().len(), fixture.l1_blocks.len());
+    }
+}
@@ -0,0 +1,291 @@
+//! Fixture-backed providers for offline derivation.
+
+use alloy_consensus::{Block, BlockBody, Header, Receipt, TxEnvelope};
+use alloy_eips::{
+    eip2718::Decodable2718,
+    eip4844::{Blob, BlobTransactionSidecarItem, IndexedBlobHash},
+    eip4895::Withdrawals,
+};
+use alloy_primitives::B256;
+use async_trait::async_trait;
+use kona_derive::{
+    errors::{BlobProviderError, PipelineError, PipelineErrorKind},
+    traits::{BlobProvider, ChainProvider, L2ChainProvider},
+};
+use op_alloy_consensus::{OpBlock, OpTxEnvelope};
+use op_alloy_genesis::{RollupConfig, SystemConfig};
+use op_alloy_protocol::{to_system_config, BatchValidationProvider, BlockInfo, L2BlockInfo};
+use op_alloy_rpc_types_engine::OpAttributesWithParent;
+use parking_lot::RwLock;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use crate::replay::{L1BlockFixture, L2BlockFixture};
+
+/// An error thrown by a replay provider.
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayProviderError {
+    /// The L1 block with the given hash is not in the fixture.
+    #[error("L1 block {0} not found")]
+    L1BlockNotFound(B256),
+    /// The L1 block with the given number is not in the fixture.
+    #[error("L1 block #{0} not found")]
+    L1BlockNumberNotFound(u64),
+    /// The L2 block with the given number is not in the fixture.
+    #[error("L2 block #{0} not found")]
+    L2BlockNotFound(u64),
+    /// The L2 block info could not be constructed from the L2 block.
+    #[error("failed to construct L2 block info for block #{0}")]
+    L2BlockInfo(u64),
+    /// The system config could not be constructed from the L2 block.
+    #[error("failed to construct system config for block #{0}")]
+    SystemConfig(u64),
+    /// A derived transaction could not be decoded.
+    #[error("failed to decode derived transaction: {0}")]
+    TransactionDecode(String),
+}
+
+impl From<ReplayProviderError> for PipelineErrorKind {
+    fn from(e: ReplayProviderError) -> Self {
+        Self::Temporary(PipelineError::Provider(e.to_string()))
+    }
+}
+
+/// The L1 chain data of a fixture, indexed by block hash and number.
+#[derive(Debug, Default)]
+struct ReplayL1Chain {
+    /// Maps block hashes to blocks.
+    blocks: HashMap<B256, L1BlockFixture>,
+    /// Maps block numbers to block hashes.
+    numbers: BTreeMap<u64, B256>,
+}
+
+/// A [ChainProvider] serving L1 headers, receipts and transactions from a fixture.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayChainProvider(Arc<ReplayL1Chain>);
+
+impl ReplayChainProvider {
+    /// Creates a new [ReplayChainProvider] from the given L1 blocks.
+    pub fn new(blocks: &[L1BlockFixture]) -> Self {
+        let mut chain = ReplayL1Chain::default();
+        for block in blocks {
+            let hash = block.header.hash_slow();
+            chain.numbers.insert(block.header.number, hash);
+            chain.blocks.insert(hash, block.clone());
+        }
+        Self(Arc::new(chain))
+    }
+
+    /// Returns the fixture block with the given hash.
+    fn block(&self, hash: B256) -> Result<&L1BlockFixture, ReplayProviderError> {
+        self.0.blocks.get(&hash).ok_or(ReplayProviderError::L1BlockNotFound(hash))
+    }
+}
+
+#[async_trait]
+impl ChainProvider for ReplayChainProvider {
+    type Error = ReplayProviderError;
+
+    async fn header_by_hash(&mut self, hash: B256) -> Result<Header, Self::Error> {
+        Ok(self.block(hash)?.header.clone())
+    }
+
+    async fn block_info_by_number(&mut self, number: u64) -> Result<BlockInfo, Self::Error> {
+        let hash = self
+            .0
+            .numbers
+            .get(&number)
+            .ok_or(ReplayProviderError::L1BlockNumberNotFound(number))?;
+        Ok(self.block(*hash)?.block_info())
+    }
+
+    async fn receipts_by_hash(&mut self, hash: B256) -> Result<Vec<Receipt>, Self::Error> {
+        Ok(self.block(hash)?.receipts.clone())
+    }
+
+    async fn block_info_and_transactions_by_hash(
+        &mut self,
+        hash: B256,
+    ) -> Result<(BlockInfo, Vec<TxEnvelope>), Self::Error> {
+        let block = self.block(hash)?;
+        Ok((block.block_info(), block.transactions.clone()))
+    }
+}
+
+/// A [BlobProvider] serving blob sidecars from a fixture.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayBlobProvider(Arc<HashMap<B256, Vec<BlobTransactionSidecarItem>>>);
+
+impl ReplayBlobProvider {
+    /// Creates a new [ReplayBlobProvider] from the blob sidecars of the given L1 blocks.
+    pub fn new(blocks: &[L1BlockFixture]) -> Self {
+        let sidecars = blocks
+            .iter()
+            .filter(|block| !block.blob_sidecars.is_empty())
+            .map(|block| (block.header.hash_slow(), block.blob_sidecars.clone()))
+            .collect();
+        Self(Arc::new(sidecars))
+    }
+}
+
+#[async_trait]
+impl BlobProvider for ReplayBlobProvider {
+    type Error = BlobProviderError;
+
+    /// Returns the blobs with the given indices from the sidecars recorded for the block.
+    async fn get_blobs(
+        &mut self,
+        block_ref: &BlockInfo,
+        blob_hashes: &[IndexedBlobHash],
+    ) -> Result<Vec<Box<Blob>>, Self::Error> {
+        if blob_hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let sidecars = self.0.get(&block_ref.hash).ok_or_else(|| {
+            BlobProviderError::Backend(format!("no blob sidecars for block {}", block_ref.hash))
+        })?;
+        blob_hashes
+            .iter()
+            .map(|hash| {
+                sidecars
+                    .iter()
+                    .find(|sidecar| sidecar.index == hash.index)
+                    .map(|sidecar| sidecar.blob.clone())
+                    .ok_or_else(|| {
+                        BlobProviderError::Backend(format!(
+                            "no blob sidecar with index {} for block {}",
+                            hash.index, block_ref.hash
+                        ))
+                    })
+            })
+            .collect()
+    }
+}
+
+/// The L2 chain of a replay, keyed by block number.
+#[derive(Debug)]
+struct ReplayL2Chain {
+    /// The rollup config used to construct block infos and system configs.
+    rollup_config: Arc<RollupConfig>,
+    /// The L2 blocks.
+    blocks: BTreeMap<u64, OpBlock>,
+}
+
+/// An [L2ChainProvider] serving L2 blocks from a fixture.
+///
+/// Blocks built from derived attributes during a replay are inserted with
+/// [ReplayL2ChainProvider::execute], standing in for the execution layer.
+#[derive(Debug, Clone)]
+pub struct ReplayL2ChainProvider(Arc<RwLock<ReplayL2Chain>>);
+
+impl ReplayL2ChainProvider {
+    /// Creates a new [ReplayL2ChainProvider] from the given L2 blocks.
+    pub fn new(rollup_config: Arc<RollupConfig>, blocks: &[L2BlockFixture]) -> Self {
+        let blocks = blocks.iter().map(|block| (block.header.number, block.block())).collect();
+        Self(Arc::new(RwLock::new(ReplayL2Chain { rollup_config, blocks })))
+    }
+
+    /// Inserts an L2 block.
+    pub fn insert(&self, block: OpBlock) {
+        self.0.write().blocks.insert(block.header.number, block);
+    }
+
+    /// Returns the L2 block built from the derived attributes, standing in for the
+    /// execution layer.
+    ///
+    /// If the fixture recorded the block, the recorded block is used. Otherwise a block is
+    /// built on top of the parent and inserted. Only the fields relevant to derivation are
+    /// populated on built blocks, so their hashes do not match the canonical chain.
+    pub fn execute(
+        &self,
+        attributes: &OpAttributesWithParent,
+    ) -> Result<L2BlockInfo, ReplayProviderError> {
+        let parent = attributes.parent.block_info;
+        let rollup_config = self.0.read().rollup_config.clone();
+        if let Ok(block) = self.block(parent.number + 1) {
+            return L2BlockInfo::from_block_and_genesis(&block, &rollup_config.genesis)
+                .map_err(|_| ReplayProviderError::L2BlockInfo(block.header.number));
+        }
+
+        let payload = &attributes.attributes.payload_attributes;
+        let transactions = attributes
+            .attributes
+            .transactions
+            .iter()
+            .flatten()
+            .map(|tx| OpTxEnvelope::decode_2718(&mut tx.as_ref()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ReplayProviderError::TransactionDecode(e.to_string()))?;
+        let gas_limit = match attributes.attributes.gas_limit {
+            Some(gas_limit) => gas_limit,
+            None => self.block(parent.number)?.header.gas_limit,
+        };
+
+        let header = Header {
+            parent_hash: parent.hash,
+            number: parent.number + 1,
+            timestamp: payload.timestamp,
+            beneficiary: payload.suggested_fee_recipient,
+            mix_hash: payload.prev_randao,
+            parent_beacon_block_root: payload.parent_beacon_block_root,
+            gas_limit,
+            ..Default::default()
+        };
+        let block = Block {
+            header,
+            body: BlockBody {
+                transactions,
+                ommers: Vec::new(),
+                withdrawals: payload.withdrawals.clone().map(Withdrawals::new),
+            },
+        };
+        let number = block.header.number;
+        let info = L2BlockInfo::from_block_and_genesis(&block, &rollup_config.genesis)
+            .map_err(|_| ReplayProviderError::L2BlockInfo(number))?;
+        self.insert(block);
+        Ok(info)
+    }
+
+    /// Returns the L2 block with the given number.
+    fn block(&self, number: u64) -> Result<OpBlock, ReplayProviderError> {
+        self.0
+            .read()
+            .blocks
+            .get(&number)
+            .cloned()
+            .ok_or(ReplayProviderError::L2BlockNotFound(number))
+    }
+}
+
+#[async_trait]
+impl BatchValidationProvider for ReplayL2ChainProvider {
+    type Error = ReplayProviderError;
+
+    async fn l2_block_info_by_number(&mut self, number: u64) -> Result<L2BlockInfo, Self::Error> {
+        let block = self.block(number)?;
+        let rollup_config = self.0.read().rollup_config.clone();
+        L2BlockInfo::from_block_and_genesis(&block, &rollup_config.genesis)
+            .map_err(|_| ReplayProviderError::L2BlockInfo(number))
+    }
+
+    async fn block_by_number(&mut self, number: u64) -> Result<OpBlock, Self::Error> {
+        self.block(number)
+    }
+}
+
+#[async_trait]
+impl L2ChainProvider for ReplayL2ChainProvider {
+    type Error = ReplayProviderError;
+
+    async fn system_config_by_number(
+        &mut self,
+        number: u64,
+        rollup_config: Arc<RollupConfig>,
+    ) -> Result<SystemConfig, <Self as BatchValidationProvider>::Error> {
+        let block = self.block(number)?;
+        to_system_config(&block, &rollup_config)
+            .map_err(|_| ReplayProviderError::SystemConfig(number))
+    }
+}
@@ -0,0 +1,415 @@
+//! Contains the `SuperchainDriver`, which drives multiple L2 chains from a single L1 context.
+
+use alloy_eips::BlockNumHash;
+use alloy_transport::TransportError;
+use std::collections::{BTreeMap, HashMap};
+use tokio::{
+    sync::{broadcast, mpsc},
+    task::JoinSet,
+};
+
+use hilo_providers_alloy::DurableBlobProvider;
+use hilo_providers_local::InMemoryChainProvider;
+
+use crate::{
+    ChainNotification, Config, ConfigError, Context, DriverError, DriverEvent, FanoutContext,
+    HiloDriver, StandaloneContext,
+};
+
+/// The number of L1 notifications to buffer for each chain driver.
+const NOTIFICATION_CHANNEL_SIZE: usize = 128;
+
+/// An error thrown by the [SuperchainDriver].
+#[derive(Debug, thiserror::Error)]
+pub enum SuperchainError {
+    /// No chains were configured.
+    #[error("no chains configured")]
+    NoChains,
+    /// The same L2 chain was configured more than once.
+    #[error("chain {0} is configured more than once")]
+    DuplicateChain(u64),
+    /// A chain does not settle on the same L1 chain as the others.
+    #[error("chain {chain_id} settles on L1 chain {l1_chain_id}, expected {expected}")]
+    MismatchedL1 {
+        /// The L2 chain ID.
+        chain_id: u64,
+        /// The L1 chain ID the chain settles on.
+        l1_chain_id: u64,
+        /// The L1 chain ID shared by the other chains.
+        expected: u64,
+    },
+    /// Failed to construct the shared L1 context.
+    #[error("L1 context error: {0}")]
+    Context(#[from] TransportError),
+    /// An error thrown from a method on the [Config].
+    #[error("config error: {0}")]
+    Config(#[from] ConfigError),
+    /// A chain driver failed.
+    #[error("driver for chain {0} failed: {1}")]
+    Driver(u64, DriverError),
+    /// A chain driver task panicked or was cancelled.
+    #[error("driver task failed: {0}")]
+    Task(String),
+    /// The shared L1 context stopped producing notifications.
+    #[error("L1 context closed")]
+    ContextClosed,
+}
+
+/// A driver for multiple L2 chains that share the same L1 chain.
+///
+/// The [SuperchainDriver] owns a single L1 [Context] and runs one [HiloDriver], and thus one
+/// derivation pipeline and engine controller, per L2 chain ID. Notifications from the shared
+/// context are fanned out to every chain through a [FanoutContext], and the L1 chain and blob
+/// providers are shared across all pipelines.
+///
+/// The [SuperchainDriver] is only available as a library. The `hilo` binary drives a single
+/// L2 chain with a [HiloDriver].
+#[derive(Debug)]
+pub struct SuperchainDriver<C: Context> {
+    /// The shared L1 context.
+    pub ctx: C,
+    /// The chain drivers, keyed by L2 chain ID.
+    pub drivers: BTreeMap<u64, HiloDriver<FanoutContext>>,
+    /// Fans out notifications from the shared context to the chain drivers.
+    notifications: broadcast::Sender<ChainNotification>,
+    /// Receives tips processed by the chain drivers.
+    processed_tips: mpsc::UnboundedReceiver<(u64, BlockNumHash)>,
+}
+
+impl SuperchainDriver<StandaloneContext> {
+    /// Creates a new [SuperchainDriver] with a standalone context.
+    ///
+    /// The L1 RPC and beacon endpoints, and the poll interval, of the first [Config] are
+    /// used for the shared context and providers.
+    pub async fn standalone(configs: Vec<Config>) -> Result<Self, SuperchainError> {
+        let first = configs.first().ok_or(SuperchainError::NoChains)?;
+        let ctx =
+            StandaloneContext::with_poll_interval(first.l1_rpc_url.clone(), first.poll_interval())
+                .await?;
+        let cache_size = configs.iter().map(|cfg| cfg.cache_size).max().unwrap_or_default();
+        let chain_provider = InMemoryChainProvider::with_capacity(cache_size);
+        let blob_provider = first.blob_provider().await?;
+        Self::new(ctx, configs, chain_provider, blob_provider)
+    }
+}
+
+impl<C> SuperchainDriver<C>
+where
+    C: Context,
+{
+    /// Constructs a new [SuperchainDriver].
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if no configs are given, if an L2 chain ID is configured more than
+    /// once, or if the chains do not all settle on the same L1 chain.
+    pub fn new(
+        ctx: C,
+        configs: Vec<Config>,
+        chain_provider: InMemoryChainProvider,
+        blob_provider: DurableBlobProvider,
+    ) -> Result<Self, SuperchainError> {
+        let expected = configs.first().ok_or(SuperchainError::NoChains)?.rollup_config.l1_chain_id;
+        let (notifications, _) = broadcast::channel(NOTIFICATION_CHANNEL_SIZE);
+        let (tips_tx, processed_tips) = mpsc::unbounded_channel();
+
+        let mut drivers = BTreeMap::new();
+        for cfg in configs {
+            let chain_id = cfg.l2_chain_id;
+            let l1_chain_id = cfg.rollup_config.l1_chain_id;
+            if l1_chain_id != expected {
+                return Err(SuperchainError::MismatchedL1 { chain_id, l1_chain_id, expected });
+            }
+            if drivers.contains_key(&chain_id) {
+                return Err(SuperchainError::DuplicateChain(chain_id));
+            }
+
+            let fanout = FanoutContext::new(chain_id, notifications.subscribe(), tips_tx.clone());
+            let driver = HiloDriver::new(cfg, fanout)
+                .with_chain_provider(chain_provider.clone())
+                .with_blob_provider(blob_provider.clone());
+            drivers.insert(chain_id, driver);
+        }
+
+        Ok(Self { ctx, drivers, notifications, processed_tips })
+    }
+
+    /// Returns the L2 chain IDs driven by the [SuperchainDriver].
+    pub fn chain_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.drivers.keys().copied()
+    }
+
+    /// Subscribes to the [DriverEvent]s published by the driver for the given L2 chain ID.
+    pub fn subscribe(&self, chain_id: u64) -> Option<broadcast::Receiver<DriverEvent>> {
+        self.drivers.get(&chain_id).map(HiloDriver::subscribe)
+    }
+
+    /// Continuously run the [SuperchainDriver].
+    ///
+    /// Each chain driver is spawned in its own task. Notifications from the shared context
+    /// are fanned out to all chains, and the shared context is only told a tip has been
+    /// processed once every chain has processed it.
+    ///
+    /// Returns an error as soon as any chain driver fails.
+    pub async fn start(self) -> Result<(), SuperchainError> {
+        let Self { mut ctx, drivers, notifications, mut processed_tips } = self;
+        let chains = drivers.len();
+
+        let mut tasks = JoinSet::new();
+        for (chain_id, mut driver) in drivers {
+            tasks.spawn(async move { (chain_id, driver.start().await) });
+        }
+        info!("Started drivers for {} chains", chains);
+
+        let mut tips = ProcessedTips::new(chains);
+        loop {
+            tokio::select! {
+                notification = ctx.recv_notification() => {
+                    let Some(notification) = notification else {
+                        tasks.abort_all();
+                        return Err(SuperchainError::ContextClosed);
+                    };
+                    // Sending only fails if every chain driver has stopped, which
+                    // is reported through the task set.
+                    _ = notifications.send(notification);
+                }
+                Some((chain_id, tip)) = processed_tips.recv() => {
+                    if let Some(lowest) = tips.record(chain_id, tip) {
+                        ctx.send_processed_tip_event(lowest);
+                    }
+                }
+                Some(result) = tasks.join_next() => {
+                    tasks.abort_all();
+                    return match result {
+                        Ok((chain_id, Err(e))) => {
+                            error!("Driver for chain {} failed: {}", chain_id, e);
+                            Err(SuperchainError::Driver(chain_id, e))
+                        }
+                        Ok((chain_id, Ok(()))) => {
+                            error!("Driver for chain {} unexpectedly stopped", chain_id);
+                            Err(SuperchainError::Driver(chain_id, DriverError::DriverErrored))
+                        }
+                        Err(e) => Err(SuperchainError::Task(e.to_string())),
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Tracks the L1 tips processed by each chain driver.
+#[derive(Debug)]
+struct ProcessedTips {
+    /// The number of chain drivers.
+    chains: usize,
+    /// The latest tip processed by each chain, keyed by L2 chain ID.
+    tips: HashMap<u64, BlockNumHash>,
+    /// The last tip reported to the shared context.
+    reported: Option<BlockNumHash>,
+}
+
+impl ProcessedTips {
+    /// Creates a new [ProcessedTips] for the given number of chain drivers.
+    fn new(chains: usize) -> Self {
+        Self { chains, tips: HashMap::with_capacity(chains), reported: None }
+    }
+
+    /// Records a tip processed by the given chain.
+    ///
+    /// Returns the lowest tip processed by every chain, once all chains have reported a tip
+    /// and the lowest tip has changed since it was last returned.
+    fn record(&mut self, chain_id: u64, tip: BlockNumHash) -> Option<BlockNumHash> {
+        self.tips.insert(chain_id, tip);
+        if self.tips.len() < self.chains {
+            return None;
+        }
+        let lowest = self.tips.values().min_by_key(|tip| tip.number).copied();
+        if lowest == self.reported {
+            return None;
+        }
+        self.reported = lowest;
+        lowest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::Headers,
+        test_utils::{l1_header, StandIn, GENESIS_L1, UNREACHABLE},
+    };
+    use alloy_primitives::B256;
+    use alloy_rpc_types_engine::JwtSecret;
+    use hilo_providers_alloy::{OnlineBeaconClient, OnlineBlobProvider};
+    use op_alloy_genesis::RollupConfig;
+    use std::time::Duration;
+    use url::Url;
+
+    fn config(l2_chain_id: u64, l1_chain_id: u64) -> Config {
+        let url = Url::parse("http://127.0.0.1:8545").unwrap();
+        Config {
+            l2_chain_id,
+            l1_rpc_url: url.clone(),
+            l1_beacon_url: url.clone(),
+            blob_archiver_url: None,
+            l2_rpc_url: url.clone(),
+            l2_engine_url: url,
+            rollup_config: RollupConfig { l1_chain_id, l2_chain_id, ..Default::default() },
+            rpc_url: None,
+            jwt_secret: JwtSecret::random(),
+            cache_size: 16,
+            data_dir: None,
+            devnet: false,
+            l1_chain_spec: None,
+        }
+    }
+
+    fn driver(configs: Vec<Config>) -> Result<SuperchainDriver<FanoutContext>, SuperchainError> {
+        let (notifications, _) = broadcast::channel(1);
+        let (tips, _) = mpsc::unbounded_channel();
+        let ctx = FanoutContext::new(0, notifications.subscribe(), tips);
+        superchain_driver(ctx, configs)
+    }
+
+    fn superchain_driver(
+        ctx: FanoutContext,
+        configs: Vec<Config>,
+    ) -> Result<SuperchainDriver<FanoutContext>, SuperchainError> {
+        let beacon = OnlineBeaconClient::new_http("http://127.0.0.1:5052".to_string());
+        let blob_provider =
+            DurableBlobProvider::new(OnlineBlobProvider::new(beacon, None, None), None);
+        SuperchainDriver::new(ctx, configs, InMemoryChainProvider::with_capacity(16), blob_provider)
+    }
+
+    /// Returns a config for the given L2 chain ID, served by the stand-in.
+    fn stand_in_config(stand_in: &StandIn, l2_chain_id: u64) -> Config {
+        let config = stand_in.config();
+        let rollup_config = RollupConfig {
+            l2_chain_id,
+            regolith_time: Some(0),
+            canyon_time: Some(0),
+            ..config.rollup_config
+        };
+        Config { l2_chain_id, rollup_config, ..config }
+    }
+
+    fn notification(number: u64) -> (ChainNotification, BlockNumHash) {
+        let inner = l1_header(number);
+        let hash = inner.hash_slow();
+        let header = alloy_rpc_types_eth::Header { hash, inner, ..Default::default() };
+        (
+            ChainNotification::New { new_blocks: Headers::from(header) },
+            BlockNumHash { number, hash },
+        )
+    }
+
+    fn tip(number: u64) -> BlockNumHash {
+        BlockNumHash { number, hash: B256::with_last_byte(number as u8) }
+    }
+
+    #[test]
+    fn test_processed_tips_lowest_once_all_reported() {
+        let mut tips = ProcessedTips::new(2);
+        assert_eq!(tips.record(10, tip(5)), None);
+        assert_eq!(tips.record(10, tip(6)), None);
+        assert_eq!(tips.record(8453, tip(4)), Some(tip(4)));
+        assert_eq!(tips.record(8453, tip(5)), Some(tip(5)));
+        assert_eq!(tips.record(8453, tip(7)), Some(tip(6)));
+        assert_eq!(tips.record(8453, tip(8)), None);
+        assert_eq!(tips.record(10, tip(8)), Some(tip(8)));
+    }
+
+    #[tokio::test]
+    async fn test_superchain_driver_reports_processed_tips() {
+        let (op, base) = (StandIn::start().await, StandIn::start().await);
+        let (notifications, _) = broadcast::channel(16);
+        let (tips_tx, mut tips) = mpsc::unbounded_channel();
+        let ctx = FanoutContext::new(0, notifications.subscribe(), tips_tx);
+        let configs = vec![stand_in_config(&op, 901), stand_in_config(&base, 902)];
+        let driver = superchain_driver(ctx, configs).unwrap();
+
+        let running = driver.start();
+        tokio::pin!(running);
+        for number in [GENESIS_L1, GENESIS_L1 + 1] {
+            let (notification, expected) = notification(number);
+            notifications.send(notification).unwrap();
+            let tip = tokio::time::timeout(Duration::from_secs(10), async {
+                tokio::select! {
+                    result = &mut running => panic!("superchain driver stopped: {result:?}"),
+                    tip = tips.recv() => tip,
+                }
+            })
+            .await
+            .unwrap();
+            // The shared context is told about the tip once both chains processed it.
+            assert_eq!(tip, Some((0, expected)));
+        }
+        assert!(tips.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_superchain_driver_failure_aborts_others() {
+        let stand_in = StandIn::start().await;
+        let unreachable = Url::parse(UNREACHABLE).unwrap();
+        // The execution client of the second chain is down, so its driver fails to start.
+        let failing = Config {
+            l2_rpc_url: unreachable.clone(),
+            l2_engine_url: unreachable,
+            ..stand_in_config(&stand_in, 902)
+        };
+        let (notifications, _) = broadcast::channel(16);
+        let (tips_tx, _tips) = mpsc::unbounded_channel();
+        let ctx = FanoutContext::new(0, notifications.subscribe(), tips_tx);
+        let driver =
+            superchain_driver(ctx, vec![stand_in_config(&stand_in, 901), failing]).unwrap();
+        let mut events = driver.subscribe(901).unwrap();
+
+        notifications.send(notification(GENESIS_L1).0).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(10), driver.start()).await.unwrap();
+        assert!(matches!(result, Err(SuperchainError::Driver(902, DriverError::Config(_)))));
+
+        // The healthy driver is aborted, closing its event channel.
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Err(broadcast::error::RecvError::Closed) = events.recv().await {
+                    return;
+                }
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn test_superchain_driver_new() {
+        let driver = driver(vec![config(10, 1), config(8453, 1)]).unwrap();
+        assert_eq!(driver.chain_ids().collect::<Vec<_>>(), vec![10, 8453]);
+        assert!(driver.subscribe(10).is_some());
+        assert!(driver.subscribe(420).is_none());
+    }
+
+    #[test]
+    fn test_superchain_driver_no_chains() {
+        assert!(matches!(driver(vec![]), Err(SuperchainError::NoChains)));
+    }
+
+    #[test]
+    fn test_superchain_driver_duplicate_chain() {
+        let err = driver(vec![config(10, 1), config(10, 1)]).unwrap_err();
+        assert!(matches!(err, SuperchainError::DuplicateChain(10)));
+    }
+
+    #[test]
+    fn test_superchain_driver_mismatched_l1() {
+        let err = driver(vec![config(10, 1), config(11155420, 11155111)]).unwrap_err();
+        assert!(matches!(
+            err,
+            SuperchainError::MismatchedL1 {
+                chain_id: 11155420,
+                l1_chain_id: 11155111,
+                expected: 1
+            }
+        ));
+    }
+}
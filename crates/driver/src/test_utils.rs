@@ -0,0 +1,548 @@
+//! Stand-in services for tests of the driver and the crates built on it.
+
+use alloy_consensus::{Block, BlockBody, Header, TxEnvelope};
+use alloy_eips::{
+    eip2718::{Decodable2718, Encodable2718},
+    BlockNumHash, BlockNumberOrTag,
+};
+use alloy_primitives::{Address, Bytes, B256, U256, U64};
+use alloy_rlp::Encodable;
+use alloy_rpc_types_engine::{
+    ExecutionPayloadEnvelopeV2, ExecutionPayloadFieldV2, ExecutionPayloadInputV2,
+    ExecutionPayloadV1, ExecutionPayloadV2, ForkchoiceState, ForkchoiceUpdated, JwtSecret,
+    PayloadId, PayloadStatus, PayloadStatusEnum,
+};
+use jsonrpsee::{
+    server::{Server, ServerHandle},
+    types::ErrorObjectOwned,
+    RpcModule,
+};
+use kona_driver::{PipelineCursor, TipCursor};
+use op_alloy_consensus::{OpBlock, OpTxEnvelope};
+use op_alloy_genesis::{ChainGenesis, RollupConfig, SystemConfig};
+use op_alloy_protocol::{BlockInfo, L2BlockInfo};
+use op_alloy_rpc_types_engine::OpPayloadAttributes;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::{broadcast, mpsc};
+use url::Url;
+
+use crate::{ChainNotification, Config, FanoutContext, HiloDriver, L1ChainSpec};
+
+/// The L1 block the stand-in rollup starts at.
+pub const GENESIS_L1: u64 = 100;
+
+/// The finalized L1 block of the stand-in L1 node.
+pub const FINALIZED_L1: u64 = GENESIS_L1 + 2;
+
+/// The latest L1 block of the stand-in L1 node.
+pub const HEAD_L1: u64 = GENESIS_L1 + 40;
+
+/// The channel timeout of the stand-in rollup, in L1 blocks.
+pub const CHANNEL_TIMEOUT: u64 = 10;
+
+/// The storage root of the `L2ToL1MessagePasser` served by the stand-in L2 node.
+const MESSAGE_PASSER_STORAGE_ROOT: B256 = B256::repeat_byte(0x16);
+
+/// An address nothing listens on.
+pub const UNREACHABLE: &str = "http://127.0.0.1:1";
+
+/// Returns the stand-in L1 header with the given number.
+pub fn l1_header(number: u64) -> Header {
+    l1_headers(number).pop().unwrap()
+}
+
+/// Returns the stand-in L1 headers up to the given number, each linked to its parent.
+fn l1_headers(to: u64) -> Vec<Header> {
+    let mut headers: Vec<Header> = Vec::new();
+    for number in 0..=to {
+        headers.push(Header {
+            parent_hash: headers.last().map(Header::hash_slow).unwrap_or_default(),
+            number,
+            timestamp: number * 12,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        });
+    }
+    headers
+}
+
+/// Returns the stand-in L2 genesis block.
+pub fn l2_genesis() -> OpBlock {
+    let header = Header {
+        timestamp: l1_header(GENESIS_L1).timestamp,
+        gas_limit: 30_000_000,
+        ..Default::default()
+    };
+    Block {
+        header,
+        body: BlockBody { transactions: Vec::new(), ommers: Vec::new(), withdrawals: None },
+    }
+}
+
+/// Returns the rollup config of the stand-in chain.
+pub fn rollup_config() -> RollupConfig {
+    RollupConfig {
+        genesis: ChainGenesis {
+            l1: BlockNumHash { number: GENESIS_L1, hash: l1_header(GENESIS_L1).hash_slow() },
+            l2: BlockNumHash { number: 0, hash: l2_genesis().header.hash_slow() },
+            l2_time: l1_header(GENESIS_L1).timestamp,
+            system_config: Some(SystemConfig { gas_limit: 30_000_000, ..Default::default() }),
+        },
+        block_time: 2,
+        seq_window_size: 4,
+        channel_timeout: CHANNEL_TIMEOUT,
+        l1_chain_id: 900,
+        l2_chain_id: 901,
+        ..Default::default()
+    }
+}
+
+/// A block referenced by hash or by number in the `debug` namespace.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawBlockId {
+    /// A block hash.
+    Hash(B256),
+    /// A block number.
+    Number(U64),
+}
+
+/// The state of a [StandIn].
+#[derive(Debug)]
+struct State {
+    /// The L1 headers up to [HEAD_L1].
+    l1_headers: Vec<Header>,
+    /// Maps L1 block hashes to block numbers.
+    l1_numbers: HashMap<B256, u64>,
+    /// The L2 chain, starting at its genesis block.
+    l2_blocks: Vec<OpBlock>,
+    /// The number of the L2 safe head.
+    l2_safe: u64,
+    /// The number of the L2 finalized head.
+    l2_finalized: u64,
+    /// The L2 blocks built by the engine, not yet inserted into the chain.
+    payloads: HashMap<PayloadId, OpBlock>,
+    /// The number of forkchoice updates answered with `SYNCING` before `VALID`.
+    syncing: u64,
+    /// The forkchoice states received, in order.
+    forkchoices: Vec<ForkchoiceState>,
+    /// The engine API methods called, in order.
+    engine_calls: Vec<String>,
+}
+
+impl State {
+    /// Returns the L1 header with the given hash.
+    fn l1_header_by_hash(&self, hash: B256) -> Result<&Header, ErrorObjectOwned> {
+        let number = self.l1_numbers.get(&hash).ok_or_else(|| not_found("L1 block"))?;
+        Ok(&self.l1_headers[*number as usize])
+    }
+
+    /// Returns the L1 header with the given number.
+    fn l1_header(&self, number: u64) -> Result<&Header, ErrorObjectOwned> {
+        self.l1_headers.get(number as usize).ok_or_else(|| not_found("L1 block"))
+    }
+
+    /// Returns the L2 block with the given number or tag, if known.
+    fn l2_block(&self, tag: BlockNumberOrTag) -> Result<Option<&OpBlock>, ErrorObjectOwned> {
+        let number = match tag {
+            BlockNumberOrTag::Number(number) => number,
+            BlockNumberOrTag::Latest => self.l2_blocks.len() as u64 - 1,
+            BlockNumberOrTag::Safe => self.l2_safe,
+            BlockNumberOrTag::Finalized => self.l2_finalized,
+            tag => return Err(unsupported(tag)),
+        };
+        Ok(self.l2_blocks.get(number as usize))
+    }
+
+    /// Builds an L2 block on top of the head of the forkchoice state.
+    fn build(
+        &mut self,
+        state: ForkchoiceState,
+        attributes: OpPayloadAttributes,
+    ) -> Result<PayloadId, ErrorObjectOwned> {
+        let parent = self
+            .l2_blocks
+            .iter()
+            .find(|block| block.header.hash_slow() == state.head_block_hash)
+            .ok_or_else(|| not_found("forkchoice head"))?
+            .header
+            .clone();
+        let header = Header {
+            parent_hash: parent.hash_slow(),
+            number: parent.number + 1,
+            timestamp: attributes.payload_attributes.timestamp,
+            beneficiary: attributes.payload_attributes.suggested_fee_recipient,
+            mix_hash: attributes.payload_attributes.prev_randao,
+            gas_limit: attributes.gas_limit.unwrap_or(parent.gas_limit),
+            ..Default::default()
+        };
+        let transactions = attributes
+            .transactions
+            .unwrap_or_default()
+            .iter()
+            .map(|tx| OpTxEnvelope::decode_2718(&mut tx.as_ref()))
+            .collect::<Result<_, _>>()
+            .map_err(|e| ErrorObjectOwned::owned(-32602, e.to_string(), None::<()>))?;
+        let id = PayloadId::new(header.number.to_be_bytes());
+        let body = BlockBody { transactions, ommers: Vec::new(), withdrawals: None };
+        self.payloads.insert(id, Block { header, body });
+        Ok(id)
+    }
+}
+
+/// A stand-in L1 node, and an L2 node serving the engine API.
+///
+/// L1 headers are served up to [HEAD_L1], with [FINALIZED_L1] as the finalized block, and
+/// empty bodies and receipts. The L2 chain starts at its genesis block and grows with the
+/// payloads built and inserted through the engine API, or with [StandIn::extend_l2]. Engine
+/// API calls are answered as valid, unless told to report `SYNCING` first, and recorded.
+#[derive(Debug)]
+pub struct StandIn {
+    /// The URL the L1 node is served on.
+    pub l1_url: Url,
+    /// The URL the L2 node and engine API are served on.
+    pub url: Url,
+    /// The state of the stand-in.
+    state: Arc<Mutex<State>>,
+    /// The handles of the servers, which stop them when dropped.
+    _handles: [ServerHandle; 2],
+}
+
+impl StandIn {
+    /// Starts the stand-in services.
+    pub async fn start() -> Self {
+        let l1_headers = l1_headers(HEAD_L1);
+        let l1_numbers =
+            l1_headers.iter().map(|header| (header.hash_slow(), header.number)).collect();
+        let state = Arc::new(Mutex::new(State {
+            l1_headers,
+            l1_numbers,
+            l2_blocks: vec![l2_genesis()],
+            l2_safe: 0,
+            l2_finalized: 0,
+            payloads: HashMap::new(),
+            syncing: 0,
+            forkchoices: Vec::new(),
+            engine_calls: Vec::new(),
+        }));
+
+        let mut l1 = RpcModule::new(state.clone());
+        l1.register_method("eth_newBlockFilter", |_, _, _| {
+            Err::<(), _>(ErrorObjectOwned::owned(-32000, "filters not supported", None::<()>))
+        })
+        .unwrap();
+        l1.register_method("eth_getBlockByNumber", |params, state, _| {
+            let (tag, _): (BlockNumberOrTag, bool) = params.parse()?;
+            let number = match tag {
+                BlockNumberOrTag::Number(number) => number,
+                BlockNumberOrTag::Finalized => FINALIZED_L1,
+                BlockNumberOrTag::Latest => HEAD_L1,
+                tag => return Err(unsupported(tag)),
+            };
+            Ok(rpc_block(state.lock().l1_header(number)?))
+        })
+        .unwrap();
+        l1.register_method("eth_getBlockByHash", |params, state, _| {
+            let (hash, _): (B256, bool) = params.parse()?;
+            Ok::<_, ErrorObjectOwned>(rpc_block(state.lock().l1_header_by_hash(hash)?))
+        })
+        .unwrap();
+        l1.register_method("debug_getRawHeader", |params, state, _| {
+            let state = state.lock();
+            let header = match params.one()? {
+                RawBlockId::Hash(hash) => state.l1_header_by_hash(hash)?,
+                RawBlockId::Number(number) => state.l1_header(number.to())?,
+            };
+            Ok::<_, ErrorObjectOwned>(rlp(header))
+        })
+        .unwrap();
+        l1.register_method("debug_getRawReceipts", |params, state, _| {
+            let hash: B256 = params.one()?;
+            state.lock().l1_header_by_hash(hash)?;
+            Ok::<_, ErrorObjectOwned>(Vec::<Bytes>::new())
+        })
+        .unwrap();
+        l1.register_method("debug_getRawBlock", |params, state, _| {
+            let hash: B256 = params.one()?;
+            let header = state.lock().l1_header_by_hash(hash)?.clone();
+            let body = BlockBody::<TxEnvelope> {
+                transactions: Vec::new(),
+                ommers: Vec::new(),
+                withdrawals: None,
+            };
+            Ok::<_, ErrorObjectOwned>(rlp(&Block { header, body }))
+        })
+        .unwrap();
+
+        let mut l2 = RpcModule::new(state.clone());
+        l2.register_method("eth_blockNumber", |_, state, _| {
+            U64::from(state.lock().l2_blocks.len() - 1)
+        })
+        .unwrap();
+        l2.register_method("eth_getBlockByNumber", |params, state, _| {
+            let (tag, _): (BlockNumberOrTag, bool) = params.parse()?;
+            Ok::<_, ErrorObjectOwned>(
+                state.lock().l2_block(tag)?.map(|block| rpc_block(&block.header)),
+            )
+        })
+        .unwrap();
+        l2.register_method("eth_getBlockByHash", |params, state, _| {
+            let (hash, _): (B256, bool) = params.parse()?;
+            let state = state.lock();
+            let block = state.l2_blocks.iter().find(|block| block.header.hash_slow() == hash);
+            Ok::<_, ErrorObjectOwned>(block.map(|block| rpc_block(&block.header)))
+        })
+        .unwrap();
+        l2.register_method("debug_getRawBlock", |params, state, _| {
+            let number: U64 = params.one()?;
+            state
+                .lock()
+                .l2_blocks
+                .get(number.to::<usize>())
+                .map(rlp)
+                .ok_or_else(|| not_found("L2 block"))
+        })
+        .unwrap();
+        l2.register_method("eth_getProof", |_, _, _| {
+            serde_json::json!({
+                "address": Address::ZERO,
+                "balance": U256::ZERO,
+                "codeHash": B256::ZERO,
+                "nonce": U64::ZERO,
+                "storageHash": MESSAGE_PASSER_STORAGE_ROOT,
+                "accountProof": [],
+                "storageProof": [],
+            })
+        })
+        .unwrap();
+
+        // Engine API
+        l2.register_method("engine_forkchoiceUpdatedV2", |params, state, _| {
+            let (forkchoice, attributes): (ForkchoiceState, Option<OpPayloadAttributes>) =
+                params.parse()?;
+            let mut state = state.lock();
+            state.engine_calls.push("engine_forkchoiceUpdatedV2".to_string());
+            state.forkchoices.push(forkchoice);
+            if state.syncing > 0 {
+                state.syncing -= 1;
+                return Ok(ForkchoiceUpdated::from_status(PayloadStatusEnum::Syncing));
+            }
+            let update = ForkchoiceUpdated::from_status(PayloadStatusEnum::Valid);
+            Ok::<_, ErrorObjectOwned>(match attributes {
+                Some(attributes) => update.with_payload_id(state.build(forkchoice, attributes)?),
+                None => update,
+            })
+        })
+        .unwrap();
+        l2.register_method("engine_getPayloadV2", |params, state, _| {
+            let id: PayloadId = params.one()?;
+            let mut state = state.lock();
+            state.engine_calls.push("engine_getPayloadV2".to_string());
+            let block = state.payloads.get(&id).ok_or_else(|| not_found("payload"))?;
+            Ok::<_, ErrorObjectOwned>(ExecutionPayloadEnvelopeV2 {
+                execution_payload: ExecutionPayloadFieldV2::V2(execution_payload(block)),
+                block_value: U256::ZERO,
+            })
+        })
+        .unwrap();
+        l2.register_method("engine_newPayloadV2", |params, state, _| {
+            let payload: ExecutionPayloadInputV2 = params.one()?;
+            let mut state = state.lock();
+            state.engine_calls.push("engine_newPayloadV2".to_string());
+            let hash = payload.execution_payload.block_hash;
+            let built = state.payloads.values().find(|block| block.header.hash_slow() == hash);
+            if let Some(block) = built.cloned() {
+                state.l2_blocks.truncate(block.header.number as usize);
+                state.l2_blocks.push(block);
+            }
+            Ok::<_, ErrorObjectOwned>(PayloadStatus::from_status(PayloadStatusEnum::Valid))
+        })
+        .unwrap();
+        l2.register_method("engine_newPayloadV3", |_, state, _| {
+            state.lock().engine_calls.push("engine_newPayloadV3".to_string());
+            PayloadStatus::from_status(PayloadStatusEnum::Valid)
+        })
+        .unwrap();
+
+        let (l1_url, l1) = serve(l1).await;
+        let (url, l2) = serve(l2).await;
+        Self { l1_url, url, state, _handles: [l1, l2] }
+    }
+
+    /// Returns a driver config for the stand-in chain, served entirely by the stand-in.
+    pub fn config(&self) -> Config {
+        let unreachable = Url::parse(UNREACHABLE).unwrap();
+        Config {
+            l2_chain_id: 901,
+            l1_rpc_url: self.l1_url.clone(),
+            l1_beacon_url: unreachable,
+            blob_archiver_url: None,
+            l2_rpc_url: self.url.clone(),
+            l2_engine_url: self.url.clone(),
+            rollup_config: rollup_config(),
+            rpc_url: None,
+            jwt_secret: JwtSecret::random(),
+            cache_size: 16,
+            data_dir: None,
+            devnet: true,
+            l1_chain_spec: Some(L1ChainSpec {
+                chain_id: 900,
+                genesis_time: 0,
+                seconds_per_slot: 12,
+            }),
+        }
+    }
+
+    /// Returns the engine API methods called so far.
+    pub fn engine_calls(&self) -> Vec<String> {
+        self.state.lock().engine_calls.clone()
+    }
+
+    /// Returns the number of the latest L2 block inserted through the engine API.
+    pub fn l2_head(&self) -> u64 {
+        self.state.lock().l2_blocks.len() as u64 - 1
+    }
+
+    /// Appends the given number of empty blocks to the L2 chain.
+    pub fn extend_l2(&self, count: u64) {
+        let mut state = self.state.lock();
+        for _ in 0..count {
+            let parent = &state.l2_blocks[state.l2_blocks.len() - 1].header;
+            let header = Header {
+                parent_hash: parent.hash_slow(),
+                number: parent.number + 1,
+                timestamp: parent.timestamp + 2,
+                gas_limit: parent.gas_limit,
+                ..Default::default()
+            };
+            let body =
+                BlockBody { transactions: Vec::new(), ommers: Vec::new(), withdrawals: None };
+            state.l2_blocks.push(Block { header, body });
+        }
+    }
+
+    /// Sets the numbers of the L2 safe and finalized heads.
+    pub fn set_l2_heads(&self, safe: u64, finalized: u64) {
+        let mut state = self.state.lock();
+        state.l2_safe = safe;
+        state.l2_finalized = finalized;
+    }
+
+    /// Returns the [BlockInfo] of the L2 block with the given number.
+    pub fn l2_block_info(&self, number: u64) -> BlockInfo {
+        let header = &self.state.lock().l2_blocks[number as usize].header;
+        BlockInfo {
+            hash: header.hash_slow(),
+            number,
+            parent_hash: header.parent_hash,
+            timestamp: header.timestamp,
+        }
+    }
+
+    /// Answers the given number of forkchoice updates with `SYNCING` before `VALID`.
+    pub fn set_syncing(&self, count: u64) {
+        self.state.lock().syncing = count;
+    }
+
+    /// Returns the forkchoice states received so far.
+    pub fn forkchoices(&self) -> Vec<ForkchoiceState> {
+        self.state.lock().forkchoices.clone()
+    }
+}
+
+/// Returns a [HiloDriver] following the L1 notifications sent on the returned sender.
+pub fn hilo_driver(
+    config: Config,
+) -> (HiloDriver<FanoutContext>, broadcast::Sender<ChainNotification>) {
+    let (notifications, _) = broadcast::channel(16);
+    let (tips, _) = mpsc::unbounded_channel();
+    let ctx = FanoutContext::new(config.l2_chain_id, notifications.subscribe(), tips);
+    (HiloDriver::new(config, ctx), notifications)
+}
+
+/// Returns a [PipelineCursor] at the L2 genesis, with the pipeline at the given L1 origin.
+pub fn genesis_cursor(config: &Config, l1_origin: u64) -> PipelineCursor {
+    let genesis = l2_genesis();
+    let safe_head =
+        L2BlockInfo::from_block_and_genesis(&genesis, &config.rollup_config.genesis).unwrap();
+    let origin = block_info(l1_origin);
+    let mut cursor = PipelineCursor::new(CHANNEL_TIMEOUT, origin);
+    cursor.advance(origin, TipCursor::new(safe_head, Default::default(), B256::ZERO));
+    cursor
+}
+
+/// Returns the [BlockInfo] of the stand-in L1 block.
+pub fn block_info(number: u64) -> BlockInfo {
+    let header = l1_header(number);
+    BlockInfo {
+        hash: header.hash_slow(),
+        number,
+        parent_hash: header.parent_hash,
+        timestamp: header.timestamp,
+    }
+}
+
+/// Returns the RPC representation of a block with the given header.
+fn rpc_block(header: &Header) -> alloy_rpc_types_eth::Block {
+    let header = alloy_rpc_types_eth::Header {
+        hash: header.hash_slow(),
+        inner: header.clone(),
+        ..Default::default()
+    };
+    alloy_rpc_types_eth::Block { header, ..Default::default() }
+}
+
+/// Returns the execution payload of an L2 block.
+fn execution_payload(block: &OpBlock) -> ExecutionPayloadV2 {
+    let header = &block.header;
+    let payload_inner = ExecutionPayloadV1 {
+        parent_hash: header.parent_hash,
+        fee_recipient: header.beneficiary,
+        state_root: header.state_root,
+        receipts_root: header.receipts_root,
+        logs_bloom: header.logs_bloom,
+        prev_randao: header.mix_hash,
+        block_number: header.number,
+        gas_limit: header.gas_limit,
+        gas_used: header.gas_used,
+        timestamp: header.timestamp,
+        extra_data: header.extra_data.clone(),
+        base_fee_per_gas: U256::ZERO,
+        block_hash: header.hash_slow(),
+        transactions: block.body.transactions.iter().map(raw_transaction).collect(),
+    };
+    ExecutionPayloadV2 { payload_inner, withdrawals: Vec::new() }
+}
+
+/// Serves the module on a local port, returning its URL and the server handle.
+async fn serve(module: RpcModule<Arc<Mutex<State>>>) -> (Url, ServerHandle) {
+    let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("http://{}", server.local_addr().unwrap())).unwrap();
+    (url, server.start(module))
+}
+
+/// Returns a JSON-RPC error for an unsupported block tag.
+fn unsupported(tag: BlockNumberOrTag) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(-32000, format!("unsupported block tag {tag}"), None::<()>)
+}
+
+/// Returns a JSON-RPC error for a missing item.
+fn not_found(item: &str) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(-32000, format!("{item} not found"), None::<()>)
+}
+
+/// RLP encodes the value.
+fn rlp(value: &impl Encodable) -> Bytes {
+    let mut buf = Vec::new();
+    value.encode(&mut buf);
+    buf.into()
+}
+
+/// Returns the EIP-2718 encoding of the transaction.
+fn raw_transaction(tx: &OpTxEnvelope) -> Bytes {
+    let mut buf = Vec::new();
+    tx.encode_2718(&mut buf);
+    buf.into()
+}
@@ -0,0 +1,112 @@
+//! Drives devnet mode against local stand-in L1 services.
+
+use alloy_primitives::B256;
+use alloy_rpc_types_engine::JwtSecret;
+use alloy_rpc_types_eth::{Block, Header};
+use hilo_driver::{Config, Context, L1ChainSpec, StandaloneContext, DEVNET_POLL_INTERVAL};
+use jsonrpsee::{
+    server::{Server, ServerHandle},
+    types::ErrorObjectOwned,
+    RpcModule,
+};
+use op_alloy_genesis::RollupConfig;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use url::Url;
+
+/// The first block served by the stand-in L1, past the reorg cache window.
+const FIRST_BLOCK: u64 = 100;
+
+/// An address nothing listens on.
+const UNREACHABLE: &str = "http://127.0.0.1:1";
+
+/// Returns a block whose hash encodes its number.
+fn block(number: u64) -> Block {
+    let header = Header {
+        hash: B256::left_padding_from(&number.to_be_bytes()),
+        inner: alloy_consensus::Header { number, ..Default::default() },
+        ..Default::default()
+    };
+    Block { header, ..Default::default() }
+}
+
+/// Starts a stand-in L1 execution client without filter support, producing a new
+/// block every time the latest block is requested.
+async fn stand_in_l1() -> (Url, ServerHandle) {
+    let mut module = RpcModule::new(AtomicU64::new(FIRST_BLOCK));
+    module
+        .register_method("eth_newBlockFilter", |_, _, _| {
+            Err::<(), _>(ErrorObjectOwned::owned(-32000, "filters not supported", None::<()>))
+        })
+        .unwrap();
+    module
+        .register_method("eth_getBlockByNumber", |_, head, _| {
+            block(head.fetch_add(1, Ordering::SeqCst))
+        })
+        .unwrap();
+    module
+        .register_method("eth_getBlockByHash", |params, _, _| {
+            let (hash, _): (B256, bool) = params.parse()?;
+            let number = u64::from_be_bytes(hash[24..].try_into().unwrap());
+            Ok::<_, ErrorObjectOwned>(block(number))
+        })
+        .unwrap();
+
+    let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("http://{}", server.local_addr().unwrap())).unwrap();
+    (url, server.start(module))
+}
+
+/// Returns a devnet config for chains outside the superchain registry.
+fn devnet_config(l1_rpc_url: Url) -> Config {
+    let unreachable = Url::parse(UNREACHABLE).unwrap();
+    Config {
+        l2_chain_id: 901,
+        l1_rpc_url,
+        l1_beacon_url: unreachable.clone(),
+        blob_archiver_url: Some(unreachable.clone()),
+        l2_rpc_url: unreachable.clone(),
+        l2_engine_url: unreachable,
+        rollup_config: RollupConfig { l1_chain_id: 900, l2_chain_id: 901, ..Default::default() },
+        rpc_url: None,
+        jwt_secret: JwtSecret::random(),
+        cache_size: 16,
+        data_dir: None,
+        devnet: true,
+        l1_chain_spec: Some(L1ChainSpec { chain_id: 900, genesis_time: 0, seconds_per_slot: 2 }),
+    }
+}
+
+#[tokio::test]
+async fn test_devnet_polls_l1() {
+    let (url, _server) = stand_in_l1().await;
+    let config = devnet_config(url);
+    assert_eq!(config.poll_interval(), DEVNET_POLL_INTERVAL);
+
+    let mut ctx =
+        StandaloneContext::with_poll_interval(config.l1_rpc_url.clone(), config.poll_interval())
+            .await
+            .unwrap();
+    for number in FIRST_BLOCK..FIRST_BLOCK + 3 {
+        let notification = tokio::time::timeout(Duration::from_secs(5), ctx.recv_notification())
+            .await
+            .unwrap()
+            .unwrap();
+        let new_chain = notification.new_chain().unwrap();
+        assert_eq!(new_chain.tip().number, number);
+    }
+}
+
+#[tokio::test]
+async fn test_devnet_blob_provider_without_beacon_or_archiver() {
+    let (url, _server) = stand_in_l1().await;
+    let mut config = devnet_config(url);
+
+    // The chain spec replaces the beacon node queries, and the unreachable archiver is skipped.
+    assert!(config.blob_provider().await.is_ok());
+
+    config.l1_chain_spec = None;
+    assert!(config.blob_provider().await.is_err());
+}
@@ -5,8 +5,7 @@
 use alloy_consensus::{Header, Sealed};
 use alloy_primitives::B256;
 use alloy_rpc_types_engine::{
-    ExecutionPayloadEnvelopeV2, ExecutionPayloadFieldV2, ExecutionPayloadV2, ForkchoiceState,
-    JwtSecret, PayloadStatusEnum,
+    ExecutionPayload, ExecutionPayloadV2, ForkchoiceState, JwtSecret, PayloadStatusEnum,
 };
 use async_trait::async_trait;
 use hilo_providers_alloy::AlloyL2ChainProvider;
@@ -19,7 +18,7 @@ use std::{sync::Arc, time::Duration};
 use tokio::time::sleep;
 use url::Url;
 
-use crate::{Engine, EngineClient, EngineControllerError, Epoch};
+use crate::{Engine, EngineClient, EngineControllerError, EngineError, Epoch};
 
 /// The engine controller.
 #[derive(Debug, Clone)]
@@ -44,6 +43,8 @@ pub struct EngineController {
     pub ecotone_timestamp: Option<u64>,
     /// The canyon timestamp used for fork choice
     pub canyon_timestamp: Option<u64>,
+    /// The output root of the last executed block
+    pub output_root: B256,
 }
 
 impl EngineController {
@@ -74,29 +75,36 @@ impl EngineController {
             provider,
             ecotone_timestamp: config.ecotone_time,
             canyon_timestamp: config.canyon_time,
+            output_root: B256::ZERO,
         }
     }
 
     /// Instructs the engine to create a block and updates the forkchoice, based on a payload
     /// received via p2p gossip.
+    ///
+    /// V3 payloads require the parent beacon block root carried by their gossip envelope.
     pub async fn handle_unsafe_payload(
         &mut self,
-        payload: &ExecutionPayloadEnvelopeV2,
+        payload: &ExecutionPayload,
+        parent_beacon_block_root: Option<B256>,
     ) -> Result<(), EngineControllerError> {
-        let withdrawals = match &payload.execution_payload {
-            ExecutionPayloadFieldV2::V2(ExecutionPayloadV2 { withdrawals, .. }) => {
-                withdrawals.clone()
+        let status = match payload.clone() {
+            ExecutionPayload::V1(payload_inner) => {
+                let payload = ExecutionPayloadV2 { payload_inner, withdrawals: vec![] };
+                self.client.new_payload_v2(payload).await?
+            }
+            ExecutionPayload::V2(payload) => self.client.new_payload_v2(payload).await?,
+            ExecutionPayload::V3(payload) => {
+                let parent_beacon_block_root = parent_beacon_block_root
+                    .ok_or(EngineControllerError::MissingParentBeaconBlockRoot)?;
+                self.client.new_payload_v3(payload, parent_beacon_block_root).await?
             }
-            ExecutionPayloadFieldV2::V1(_) => vec![],
         };
-        let payload_inner = payload.clone().into_v1_payload();
-        let exec_payload = ExecutionPayloadV2 { payload_inner, withdrawals };
-        let status = self.client.new_payload_v2(exec_payload).await?;
         if !status.is_valid() && status.status != PayloadStatusEnum::Accepted {
             return Err(EngineControllerError::InvalidPayloadAttributes);
         }
 
-        let payload = payload.clone().into_v1_payload();
+        let payload = payload.as_v1();
         self.unsafe_head = BlockInfo {
             number: payload.block_number,
             hash: payload.block_hash,
@@ -148,8 +156,8 @@ impl EngineController {
 
     /// Initiates validation & production of a new block:
     /// - Sends the [OpPayloadAttributes] to the engine via `engine_forkchoiceUpdatedV2` (V3 post
-    ///   Ecotone) and retrieves the [ExecutionPayloadEnvelopeV2]
-    /// - Executes the [ExecutionPayloadEnvelopeV2] to create a block via `engine_newPayloadV2` (V3
+    ///   Ecotone) and retrieves the `ExecutionPayloadEnvelopeV2`
+    /// - Executes the `ExecutionPayloadEnvelopeV2` to create a block via `engine_newPayloadV2` (V3
     ///   post Ecotone)
     /// - Updates the [EngineController] `safe_head`, `safe_epoch`, and `unsafe_head`
     /// - Updates the forkchoice and sends this to the engine via `engine_forkchoiceUpdatedV2` (v3
@@ -273,12 +281,17 @@ impl Executor for EngineController {
             .block_by_number(self.unsafe_head.number)
             .await
             .map_err(|_| EngineControllerError::BlockFetchFailed(self.unsafe_head.number))?;
+        self.output_root = self
+            .provider
+            .output_root(&block.header)
+            .await
+            .map_err(|_| EngineError::OutputRootError)?;
         Ok(block.header)
     }
 
-    /// Computes the output root.
+    /// Returns the output root of the last executed block.
     fn compute_output_root(&mut self) -> Result<B256, Self::Error> {
-        unimplemented!("Output root computation is not used by hilo")
+        Ok(self.output_root)
     }
 }
 
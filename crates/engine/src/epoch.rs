@@ -1,5 +1,6 @@
 //! Contains an epoch type.
 
+use alloy_eips::BlockNumHash;
 use alloy_primitives::B256;
 use op_alloy_protocol::BlockInfo;
 
@@ -19,3 +20,11 @@ impl From<BlockInfo> for Epoch {
         Self { number: block.number, hash: block.hash, timestamp: block.timestamp }
     }
 }
+
+/// Converts an L1 origin, such as [op_alloy_protocol::L2BlockInfo::l1_origin].
+/// The origin does not carry the epoch timestamp, so it is left at zero.
+impl From<BlockNumHash> for Epoch {
+    fn from(block: BlockNumHash) -> Self {
+        Self { number: block.number, hash: block.hash, timestamp: 0 }
+    }
+}
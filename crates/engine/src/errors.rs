@@ -47,4 +47,7 @@ pub enum EngineControllerError {
     /// Failed to fetch block.
     #[error("Failed to fetch block {0}")]
     BlockFetchFailed(u64),
+    /// A V3 execution payload was received without a parent beacon block root.
+    #[error("Missing parent beacon block root for a V3 execution payload")]
+    MissingParentBeaconBlockRoot,
 }
@@ -3,11 +3,16 @@
 use alloy_primitives::Address;
 use discv5::{Config, ListenConfig};
 use eyre::Result;
+use k256::ecdsa::SigningKey;
 use std::{
     net::{IpAddr, SocketAddr},
+    path::PathBuf,
     time::Duration,
 };
-use tokio::sync::watch::channel;
+use tokio::sync::{
+    mpsc,
+    watch::{self, channel},
+};
 
 use libp2p::{
     gossipsub::Config as GossipConfig, multiaddr::Protocol, noise::Config as NoiseConfig,
@@ -16,9 +21,20 @@ use libp2p::{
 use libp2p_identity::Keypair;
 
 use crate::{
-    discovery::builder::DiscoveryBuilder,
+    discovery::{bootnodes::Bootnode, builder::DiscoveryBuilder},
     driver::NetworkDriver,
-    gossip::{behaviour::Behaviour, config, driver::GossipDriver, handler::BlockHandler},
+    gossip::{
+        behaviour::Behaviour,
+        config,
+        driver::GossipDriver,
+        gate::GateConfig,
+        handler::{BlockHandler, Handler},
+        publisher::BlockPublisher,
+        score::{self, ScoreConfig},
+    },
+    key::NodeKey,
+    peerstore::Peerstore,
+    sync::payloads::PayloadSync,
 };
 
 /// Constructs a [NetworkDriver] for Optimism's consensus-layer.
@@ -38,8 +54,16 @@ pub struct NetworkDriverBuilder {
     pub interval: Option<Duration>,
     /// The [Config] constructs the config for `discv5`.
     pub discovery_config: Option<Config>,
+    /// The bootnodes replacing the default bootnodes of the chain.
+    pub bootnodes: Option<Vec<Bootnode>>,
+    /// Disables the default bootnodes of the chain.
+    pub disable_default_bootnodes: bool,
+    /// The peers that are always dialed.
+    pub static_peers: Vec<Multiaddr>,
     /// The [Keypair] for the node.
     pub keypair: Option<Keypair>,
+    /// The key identifying the node to both gossip and discovery.
+    pub node_key: Option<NodeKey>,
     /// The [TcpConfig] for the swarm.
     pub tcp_config: Option<TcpConfig>,
     /// The [NoiseConfig] for the swarm.
@@ -48,6 +72,20 @@ pub struct NetworkDriverBuilder {
     pub yamux_config: Option<YamuxConfig>,
     /// The idle connection timeout.
     pub timeout: Option<Duration>,
+    /// The connection limits, defaulting to [GateConfig::default].
+    pub gate_config: Option<GateConfig>,
+    /// The peer scoring config, defaulting to [ScoreConfig::default].
+    pub score_config: Option<ScoreConfig>,
+    /// Disables peer scoring and the banning of low scoring peers.
+    pub disable_peer_scoring: bool,
+    /// The sequencer key signing published blocks.
+    pub sequencer_key: Option<SigningKey>,
+    /// The Canyon activation timestamp, selecting the envelope of published blocks.
+    pub canyon_time: Option<u64>,
+    /// The Ecotone activation timestamp, selecting the envelope of published blocks.
+    pub ecotone_time: Option<u64>,
+    /// The directory the peerstore is persisted to.
+    pub data_dir: Option<PathBuf>,
 }
 
 impl NetworkDriverBuilder {
@@ -74,6 +112,42 @@ impl NetworkDriverBuilder {
         self
     }
 
+    /// Specifies the bootnodes, replacing the default bootnodes of the chain.
+    ///
+    /// ENRs bootstrap discovery, and multiaddrs are dialed once on start.
+    pub fn with_bootnodes(&mut self, bootnodes: Vec<Bootnode>) -> &mut Self {
+        self.bootnodes = Some(bootnodes);
+        self
+    }
+
+    /// Disables the default bootnodes of the chain, so discovery is only bootstrapped
+    /// from the given bootnodes and the peerstore.
+    ///
+    /// Local devnets and private chains disable them, since they serve public networks.
+    pub fn without_default_bootnodes(&mut self) -> &mut Self {
+        self.disable_default_bootnodes = true;
+        self
+    }
+
+    /// Specifies peers that are dialed on start and redialed whenever they disconnect.
+    ///
+    /// Only addresses ending with a `/p2p/` peer id are redialed.
+    pub fn with_static_peers(&mut self, peers: Vec<Multiaddr>) -> &mut Self {
+        self.static_peers = peers;
+        self
+    }
+
+    /// Specifies the directory to persist the node key, discovered nodes and peer
+    /// metadata to.
+    ///
+    /// The node key keeps the peer id and ENR of the node stable, the persisted nodes
+    /// bootstrap discovery on the next start, and peer bans are restored. Without a data
+    /// directory, nothing is remembered across restarts.
+    pub fn with_data_dir(&mut self, data_dir: PathBuf) -> &mut Self {
+        self.data_dir = Some(data_dir);
+        self
+    }
+
     /// Specifies the socket address that the gossip service is listening on.
     pub fn with_gossip_addr(&mut self, socket: SocketAddr) -> &mut Self {
         self.gossip_addr = Some(socket);
@@ -87,11 +161,22 @@ impl NetworkDriverBuilder {
     }
 
     /// Specifies the keypair for the node.
+    ///
+    /// The keypair must be secp256k1, since it also signs the ENR of the node.
     pub fn with_keypair(&mut self, keypair: Keypair) -> &mut Self {
         self.keypair = Some(keypair);
         self
     }
 
+    /// Specifies the key identifying the node to both gossip and discovery.
+    ///
+    /// If neither a node key nor a keypair is set, the key is loaded from the data
+    /// directory, or generated for this run only.
+    pub fn with_node_key(&mut self, node_key: NodeKey) -> &mut Self {
+        self.node_key = Some(node_key);
+        self
+    }
+
     /// Specifies the [TcpConfig] for the swarm.
     pub fn with_tcp_config(&mut self, tcp_config: TcpConfig) -> &mut Self {
         self.tcp_config = Some(tcp_config);
@@ -116,6 +201,44 @@ impl NetworkDriverBuilder {
         self
     }
 
+    /// Specifies the [GateConfig] limiting the connected peers, and the connections from
+    /// a single IP address or subnet.
+    pub fn with_gate_config(&mut self, gate_config: GateConfig) -> &mut Self {
+        self.gate_config = Some(gate_config);
+        self
+    }
+
+    /// Specifies the [ScoreConfig] used to score gossip peers and ban low scoring peers.
+    pub fn with_peer_scoring(&mut self, score_config: ScoreConfig) -> &mut Self {
+        self.score_config = Some(score_config);
+        self
+    }
+
+    /// Disables gossip peer scoring, so peers are never banned for their score.
+    pub fn without_peer_scoring(&mut self) -> &mut Self {
+        self.disable_peer_scoring = true;
+        self
+    }
+
+    /// Specifies the sequencer key signing the unsafe blocks published with
+    /// [NetworkDriver::block_publisher].
+    pub fn with_sequencer_key(&mut self, key: SigningKey) -> &mut Self {
+        self.sequencer_key = Some(key);
+        self
+    }
+
+    /// Specifies the Canyon activation timestamp of the chain.
+    pub fn with_canyon_time(&mut self, time: u64) -> &mut Self {
+        self.canyon_time = Some(time);
+        self
+    }
+
+    /// Specifies the Ecotone activation timestamp of the chain.
+    pub fn with_ecotone_time(&mut self, time: u64) -> &mut Self {
+        self.ecotone_time = Some(time);
+        self
+    }
+
     /// Specifies the [GossipConfig] for the `gossipsub` configuration.
     ///
     /// If not set, the [NetworkDriverBuilder] will use the default gossipsub
@@ -243,13 +366,26 @@ impl NetworkDriverBuilder {
         let (handler, unsafe_block_recv) = BlockHandler::new(chain_id, unsafe_block_signer_recv);
 
         // Construct the gossipsub behaviour.
-        let behaviour = Behaviour::new(config, &[Box::new(handler.clone())])?;
+        let mut behaviour = Behaviour::new(chain_id, config, &[Box::new(handler.clone())])?;
+        behaviour.gate.config = self.gate_config.unwrap_or_default();
+        let scoring = (!self.disable_peer_scoring).then(|| self.score_config.unwrap_or_default());
+        if let Some(scoring) = scoring {
+            let params = scoring.peer_score_params(&handler.topics());
+            behaviour.with_peer_score(params, score::peer_score_thresholds())?;
+        }
+
+        // Resolve the key shared by the swarm and discovery.
+        let node_key = match (self.node_key.take(), self.keypair.take(), &self.data_dir) {
+            (Some(key), _, _) => key,
+            (None, Some(keypair), _) => NodeKey::try_from(keypair)?,
+            (None, None, Some(dir)) => NodeKey::load_or_create(dir)?,
+            (None, None, None) => NodeKey::random(),
+        };
 
         // Build the swarm.
         let timeout = self.timeout.take().unwrap_or(Duration::from_secs(60));
         let noise_config = self.noise_config.take();
-        let keypair = self.keypair.take().unwrap_or(Keypair::generate_secp256k1());
-        let swarm = SwarmBuilder::with_existing_identity(keypair)
+        let swarm = SwarmBuilder::with_existing_identity(node_key.keypair())
             .with_tokio()
             .with_tcp(
                 self.tcp_config.take().unwrap_or_default(),
@@ -271,11 +407,30 @@ impl NetworkDriverBuilder {
             IpAddr::V6(ip) => multiaddr.push(Protocol::Ip6(ip)),
         }
         multiaddr.push(Protocol::Tcp(gossip_addr.port()));
-        let gossip = GossipDriver::new(swarm, multiaddr, handler.clone());
+        let peerstore = match self.data_dir.take() {
+            Some(dir) => Peerstore::open(dir)?,
+            None => Peerstore::default(),
+        };
+        let bans = peerstore.bans()?;
+        let peerstore = peerstore.shared();
+
+        let mut gossip = GossipDriver::new(swarm, multiaddr, handler.clone());
+        gossip.scoring = scoring;
+        gossip.peerstore = peerstore.clone();
+        for (peer, remaining) in bans {
+            gossip.ban(peer, remaining);
+        }
+        gossip.publisher = self
+            .sequencer_key
+            .take()
+            .map(|key| BlockPublisher::new(chain_id, key, self.canyon_time, self.ecotone_time));
+        gossip.static_peers = std::mem::take(&mut self.static_peers);
 
         // Build the discovery service
-        let mut discovery_builder =
-            DiscoveryBuilder::new().with_address(gossip_addr).with_chain_id(chain_id);
+        let mut discovery_builder = DiscoveryBuilder::new()
+            .with_address(gossip_addr)
+            .with_chain_id(chain_id)
+            .with_node_key(node_key);
 
         if let Some(discovery_addr) = self.discovery_addr.take() {
             discovery_builder = discovery_builder.with_listen_config(discovery_addr);
@@ -287,12 +442,32 @@ impl NetworkDriverBuilder {
 
         let mut discovery = discovery_builder.build()?;
         discovery.interval = self.interval.unwrap_or(Duration::from_secs(10));
+        if let Some(bootnodes) = self.bootnodes.take() {
+            discovery.bootnodes.clear();
+            for bootnode in bootnodes {
+                match bootnode {
+                    Bootnode::Enr(enr) => discovery.bootnodes.push(enr),
+                    Bootnode::Multiaddr(addr) => gossip.bootnodes.push(addr),
+                }
+            }
+        } else if self.disable_default_bootnodes {
+            discovery.bootnodes.clear();
+        }
+        discovery.peerstore = peerstore;
 
+        let (publish_sender, publish_recv) = mpsc::channel(16);
+        let (sync_sender, sync_recv) = mpsc::channel(16);
         Ok(NetworkDriver {
             discovery,
             gossip,
             unsafe_block_recv: Some(unsafe_block_recv),
             unsafe_block_signer_sender: Some(unsafe_block_signer_sender),
+            peer_count: watch::Sender::new(0),
+            publish_sender,
+            publish_recv,
+            sync_sender,
+            sync_recv,
+            sync: PayloadSync::default(),
         })
     }
 }
@@ -388,6 +563,7 @@ mod tests {
         assert_eq!(driver.gossip.addr, multiaddr);
         assert_eq!(driver.discovery.chain_id, id);
         assert_eq!(driver.discovery.disc.local_enr().tcp4().unwrap(), 9099);
+        assert_eq!(driver.discovery.reachable, crate::types::peer::Reachable::Ipv4);
 
         // Block Handler Assertions
         assert_eq!(driver.gossip.handler.chain_id, id);
@@ -455,4 +631,178 @@ mod tests {
 
         assert_eq!(driver.discovery.disc.local_enr().tcp4().unwrap(), 9097);
     }
+
+    #[test]
+    fn test_build_network_driver_peer_scoring() {
+        let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 9099);
+        let scoring = ScoreConfig { ban_threshold: -50.0, ..Default::default() };
+        let driver = NetworkDriverBuilder::new()
+            .with_unsafe_block_signer(Address::random())
+            .with_chain_id(10)
+            .with_gossip_addr(socket)
+            .with_peer_scoring(scoring)
+            .build()
+            .unwrap();
+        assert_eq!(driver.gossip.scoring, Some(scoring));
+        let peer = libp2p::PeerId::random();
+        assert_eq!(driver.gossip.swarm.behaviour().gossipsub.peer_score(&peer), Some(0.0));
+
+        let driver = NetworkDriverBuilder::new()
+            .with_unsafe_block_signer(Address::random())
+            .with_chain_id(10)
+            .with_gossip_addr(socket)
+            .without_peer_scoring()
+            .build()
+            .unwrap();
+        assert_eq!(driver.gossip.scoring, None);
+        assert_eq!(driver.gossip.swarm.behaviour().gossipsub.peer_score(&peer), None);
+    }
+
+    #[test]
+    fn test_build_network_driver_with_gate_config() {
+        let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 9099);
+        let gate = GateConfig { max_peers: 5, max_peers_per_ip: 1, max_peers_per_subnet: 2 };
+        let mut driver = NetworkDriverBuilder::new()
+            .with_unsafe_block_signer(Address::random())
+            .with_chain_id(10)
+            .with_gossip_addr(socket)
+            .with_gate_config(gate)
+            .build()
+            .unwrap();
+        assert_eq!(driver.gossip.swarm.behaviour().gate.config, gate);
+
+        let peer = libp2p::PeerId::random();
+        driver.gossip.ban(peer, Duration::from_secs(60));
+        assert!(driver.gossip.swarm.behaviour().gate.is_peer_banned(&peer));
+        driver.gossip.ban_subnet("10.0.0.0/8".parse().unwrap(), Duration::from_secs(60));
+        assert!(driver.gossip.swarm.behaviour().gate.is_ip_banned("10.1.2.3".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_build_network_driver_with_sequencer_key() {
+        let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 9099);
+        let driver = NetworkDriverBuilder::new()
+            .with_unsafe_block_signer(Address::random())
+            .with_chain_id(10)
+            .with_gossip_addr(socket)
+            .with_sequencer_key(SigningKey::from_slice(&[1; 32]).unwrap())
+            .with_canyon_time(10)
+            .with_ecotone_time(20)
+            .build()
+            .unwrap();
+
+        let publisher = driver.gossip.publisher.unwrap();
+        assert_eq!(publisher.chain_id, 10);
+        assert_eq!(publisher.canyon_time, Some(10));
+        assert_eq!(publisher.ecotone_time, Some(20));
+    }
+
+    #[test]
+    fn test_build_network_driver_with_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let banned = libp2p::PeerId::random();
+        let store = Peerstore::open(dir.path()).unwrap();
+        store.save([(banned, Some(Duration::from_secs(600)))]).unwrap();
+
+        let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 9099);
+        let driver = NetworkDriverBuilder::new()
+            .with_unsafe_block_signer(Address::random())
+            .with_chain_id(10)
+            .with_gossip_addr(socket)
+            .with_data_dir(dir.path().to_path_buf())
+            .build()
+            .unwrap();
+
+        assert!(driver.gossip.is_banned(&banned));
+        let store = driver.discovery.peerstore.lock().unwrap();
+        assert_eq!(store.path(), Some(dir.path().join(crate::peerstore::PEERSTORE_FILE).as_path()));
+    }
+
+    #[test]
+    fn test_build_network_driver_with_persistent_node_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 9099);
+        let build = || {
+            NetworkDriverBuilder::new()
+                .with_unsafe_block_signer(Address::random())
+                .with_chain_id(10)
+                .with_gossip_addr(socket)
+                .with_data_dir(dir.path().to_path_buf())
+                .build()
+                .unwrap()
+        };
+
+        let driver = build();
+        assert_eq!(crate::key::enr_peer_id(&driver.local_enr()).unwrap(), driver.peer_id());
+        let restarted = build();
+        assert_eq!(restarted.peer_id(), driver.peer_id());
+        assert_eq!(restarted.local_enr().node_id(), driver.local_enr().node_id());
+
+        let keypair = Keypair::generate_secp256k1();
+        let driver = NetworkDriverBuilder::new()
+            .with_unsafe_block_signer(Address::random())
+            .with_chain_id(10)
+            .with_gossip_addr(socket)
+            .with_keypair(keypair.clone())
+            .build()
+            .unwrap();
+        assert_eq!(driver.peer_id(), keypair.public().to_peer_id());
+        assert_eq!(crate::key::enr_peer_id(&driver.local_enr()).unwrap(), driver.peer_id());
+    }
+
+    #[test]
+    fn test_build_network_driver_without_bootnodes() {
+        let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 9099);
+        let driver = NetworkDriverBuilder::new()
+            .with_unsafe_block_signer(Address::random())
+            .with_chain_id(901)
+            .with_gossip_addr(socket)
+            .with_bootnodes(Vec::new())
+            .build()
+            .unwrap();
+
+        assert!(driver.discovery.bootnodes.is_empty());
+
+        let driver = NetworkDriverBuilder::new()
+            .with_unsafe_block_signer(Address::random())
+            .with_chain_id(10)
+            .with_gossip_addr(socket)
+            .without_default_bootnodes()
+            .build()
+            .unwrap();
+
+        assert!(driver.discovery.bootnodes.is_empty());
+    }
+
+    #[test]
+    fn test_build_network_driver_with_bootnodes() {
+        use crate::discovery::bootnodes::{
+            bootnodes, BASE_MAINNET_BOOTNODES, OP_MAINNET_BOOTNODES,
+        };
+
+        let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 9099);
+        let driver = NetworkDriverBuilder::new()
+            .with_unsafe_block_signer(Address::random())
+            .with_chain_id(8453)
+            .with_gossip_addr(socket)
+            .build()
+            .unwrap();
+        assert_eq!(driver.discovery.bootnodes, bootnodes(8453));
+
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/9222".parse().unwrap();
+        let static_peer: Multiaddr =
+            format!("/ip4/127.0.0.1/tcp/9223/p2p/{}", libp2p::PeerId::random()).parse().unwrap();
+        let driver = NetworkDriverBuilder::new()
+            .with_unsafe_block_signer(Address::random())
+            .with_chain_id(8453)
+            .with_gossip_addr(socket)
+            .with_bootnodes(vec![OP_MAINNET_BOOTNODES[0].clone().into(), addr.clone().into()])
+            .with_static_peers(vec![static_peer.clone()])
+            .build()
+            .unwrap();
+        assert_eq!(driver.discovery.bootnodes, [OP_MAINNET_BOOTNODES[0].clone()]);
+        assert!(!driver.discovery.bootnodes.contains(&BASE_MAINNET_BOOTNODES[0]));
+        assert_eq!(driver.gossip.bootnodes, [addr]);
+        assert_eq!(driver.gossip.static_peers, [static_peer]);
+    }
 }
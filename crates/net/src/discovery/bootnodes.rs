@@ -2,26 +2,126 @@
 
 use discv5::enr::{CombinedKey, Enr};
 use lazy_static::lazy_static;
+use libp2p::Multiaddr;
 use std::str::FromStr;
 
+/// The chain IDs of the mainnet chains, which share a discovery network.
+pub const MAINNET_CHAIN_IDS: &[u64] = &[10, 252, 480, 1135, 8453, 34443, 7777777];
+
+/// The chain ID of OP Mainnet.
+pub const OP_MAINNET_CHAIN_ID: u64 = 10;
+
+/// The chain ID of Base Mainnet.
+pub const BASE_MAINNET_CHAIN_ID: u64 = 8453;
+
+/// The chain ID of OP Sepolia.
+pub const OP_SEPOLIA_CHAIN_ID: u64 = 11155420;
+
+/// The chain ID of Base Sepolia.
+pub const BASE_SEPOLIA_CHAIN_ID: u64 = 84532;
+
 lazy_static! {
-    /// Default bootnodes to use.
-    pub static ref BOOTNODES: Vec<Enr<CombinedKey>> = [
-        // Optimism Mainnet Bootnodes
+    /// OP Mainnet bootnodes, also used by the other mainnet chains of the superchain.
+    pub static ref OP_MAINNET_BOOTNODES: Vec<Enr<CombinedKey>> = [
         Enr::from_str("enr:-J64QBbwPjPLZ6IOOToOLsSjtFUjjzN66qmBZdUexpO32Klrc458Q24kbty2PdRaLacHM5z-cZQr8mjeQu3pik6jPSOGAYYFIqBfgmlkgnY0gmlwhDaRWFWHb3BzdGFja4SzlAUAiXNlY3AyNTZrMaECmeSnJh7zjKrDSPoNMGXoopeDF4hhpj5I0OsQUUt4u8uDdGNwgiQGg3VkcIIkBg").unwrap(),
         Enr::from_str("enr:-J64QAlTCDa188Hl1OGv5_2Kj2nWCsvxMVc_rEnLtw7RPFbOfqUOV6khXT_PH6cC603I2ynY31rSQ8sI9gLeJbfFGaWGAYYFIrpdgmlkgnY0gmlwhANWgzCHb3BzdGFja4SzlAUAiXNlY3AyNTZrMaECkySjcg-2v0uWAsFsZZu43qNHppGr2D5F913Qqs5jDCGDdGNwgiQGg3VkcIIkBg").unwrap(),
         Enr::from_str("enr:-J24QGEzN4mJgLWNTUNwj7riVJ2ZjRLenOFccl2dbRFxHHOCCZx8SXWzgf-sLzrGs6QgqSFCvGXVgGPBkRkfOWlT1-iGAYe6Cu93gmlkgnY0gmlwhCJBEUSHb3BzdGFja4OkAwCJc2VjcDI1NmsxoQLuYIwaYOHg3CUQhCkS-RsSHmUd1b_x93-9yQ5ItS6udIN0Y3CCIyuDdWRwgiMr").unwrap(),
+    ].to_vec();
 
-        // Base Mainnet Bootnodes
+    /// Base Mainnet bootnodes.
+    pub static ref BASE_MAINNET_BOOTNODES: Vec<Enr<CombinedKey>> = [
         Enr::from_str("enr:-J24QNz9lbrKbN4iSmmjtnr7SjUMk4zB7f1krHZcTZx-JRKZd0kA2gjufUROD6T3sOWDVDnFJRvqBBo62zuF-hYCohOGAYiOoEyEgmlkgnY0gmlwhAPniryHb3BzdGFja4OFQgCJc2VjcDI1NmsxoQKNVFlCxh_B-716tTs-h1vMzZkSs1FTu_OYTNjgufplG4N0Y3CCJAaDdWRwgiQG").unwrap(),
         Enr::from_str("enr:-J24QH-f1wt99sfpHy4c0QJM-NfmsIfmlLAMMcgZCUEgKG_BBYFc6FwYgaMJMQN5dsRBJApIok0jFn-9CS842lGpLmqGAYiOoDRAgmlkgnY0gmlwhLhIgb2Hb3BzdGFja4OFQgCJc2VjcDI1NmsxoQJ9FTIv8B9myn1MWaC_2lJ-sMoeCDkusCsk4BYHjjCq04N0Y3CCJAaDdWRwgiQG").unwrap(),
         Enr::from_str("enr:-J24QDXyyxvQYsd0yfsN0cRr1lZ1N11zGTplMNlW4xNEc7LkPXh0NAJ9iSOVdRO95GPYAIc6xmyoCCG6_0JxdL3a0zaGAYiOoAjFgmlkgnY0gmlwhAPckbGHb3BzdGFja4OFQgCJc2VjcDI1NmsxoQJwoS7tzwxqXSyFL7g0JM-KWVbgvjfB8JA__T7yY_cYboN0Y3CCJAaDdWRwgiQG").unwrap(),
         Enr::from_str("enr:-J24QHmGyBwUZXIcsGYMaUqGGSl4CFdx9Tozu-vQCn5bHIQbR7On7dZbU61vYvfrJr30t0iahSqhc64J46MnUO2JvQaGAYiOoCKKgmlkgnY0gmlwhAPnCzSHb3BzdGFja4OFQgCJc2VjcDI1NmsxoQINc4fSijfbNIiGhcgvwjsjxVFJHUstK9L1T8OTKUjgloN0Y3CCJAaDdWRwgiQG").unwrap(),
         Enr::from_str("enr:-J24QG3ypT4xSu0gjb5PABCmVxZqBjVw9ca7pvsI8jl4KATYAnxBmfkaIuEqy9sKvDHKuNCsy57WwK9wTt2aQgcaDDyGAYiOoGAXgmlkgnY0gmlwhDbGmZaHb3BzdGFja4OFQgCJc2VjcDI1NmsxoQIeAK_--tcLEiu7HvoUlbV52MspE0uCocsx1f_rYvRenIN0Y3CCJAaDdWRwgiQG").unwrap(),
-
-        // Conduit Bootnode
-        // discv5::enr::NodeId::from("enode://9d7a3efefe442351217e73b3a593bcb8efffb55b4807699972145324eab5e6b382152f8d24f6301baebbfb5ecd4127bd3faab2842c04cd432bdf50ba092f6645@34.65.109.126:30305"),
     ].to_vec();
+
+    /// OP Sepolia bootnodes.
+    ///
+    /// No OP Sepolia node records are bundled yet, so OP Sepolia nodes are given their
+    /// bootnodes with `--p2p-bootnodes`.
+    pub static ref OP_SEPOLIA_BOOTNODES: Vec<Enr<CombinedKey>> = Vec::new();
+
+    /// Base Sepolia bootnodes.
+    ///
+    /// No Base Sepolia node records are bundled yet, so Base Sepolia nodes are given their
+    /// bootnodes with `--p2p-bootnodes`.
+    pub static ref BASE_SEPOLIA_BOOTNODES: Vec<Enr<CombinedKey>> = Vec::new();
+
+    /// All built-in bootnodes.
+    pub static ref BOOTNODES: Vec<Enr<CombinedKey>> = [
+        OP_MAINNET_BOOTNODES.as_slice(),
+        BASE_MAINNET_BOOTNODES.as_slice(),
+        OP_SEPOLIA_BOOTNODES.as_slice(),
+        BASE_SEPOLIA_BOOTNODES.as_slice(),
+    ]
+    .concat();
+}
+
+/// Returns the default bootnodes of the chain.
+///
+/// Chains share the discovery network of their superchain, and discovered nodes are
+/// filtered by the chain ID in their ENR, so mainnet chains without bootnodes of their
+/// own use the OP Mainnet bootnodes. Chains outside the known networks have no default
+/// bootnodes.
+pub fn bootnodes(chain_id: u64) -> Vec<Enr<CombinedKey>> {
+    match chain_id {
+        OP_MAINNET_CHAIN_ID => OP_MAINNET_BOOTNODES.clone(),
+        BASE_MAINNET_CHAIN_ID => BASE_MAINNET_BOOTNODES.clone(),
+        OP_SEPOLIA_CHAIN_ID => OP_SEPOLIA_BOOTNODES.clone(),
+        BASE_SEPOLIA_CHAIN_ID => BASE_SEPOLIA_BOOTNODES.clone(),
+        id if MAINNET_CHAIN_IDS.contains(&id) => OP_MAINNET_BOOTNODES.clone(),
+        _ => Vec::new(),
+    }
+}
+
+/// A user-supplied bootnode.
+///
+/// ENRs bootstrap discovery, while multiaddrs are dialed directly by gossip since
+/// discovery can only contact nodes with a known ENR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bootnode {
+    /// A node record, added to the discovery table.
+    Enr(Enr<CombinedKey>),
+    /// A libp2p address, dialed on startup.
+    Multiaddr(Multiaddr),
+}
+
+impl Bootnode {
+    /// Returns the multiaddr of the bootnode, if it is not an ENR.
+    pub fn into_multiaddr(self) -> Option<Multiaddr> {
+        match self {
+            Self::Enr(_) => None,
+            Self::Multiaddr(addr) => Some(addr),
+        }
+    }
+}
+
+impl FromStr for Bootnode {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("enr:") {
+            Enr::from_str(s).map(Self::Enr).map_err(|e| eyre::eyre!("invalid bootnode ENR: {}", e))
+        } else {
+            Multiaddr::from_str(s)
+                .map(Self::Multiaddr)
+                .map_err(|e| eyre::eyre!("invalid bootnode multiaddr: {}", e))
+        }
+    }
+}
+
+impl From<Enr<CombinedKey>> for Bootnode {
+    fn from(enr: Enr<CombinedKey>) -> Self {
+        Self::Enr(enr)
+    }
+}
+
+impl From<Multiaddr> for Bootnode {
+    fn from(addr: Multiaddr) -> Self {
+        Self::Multiaddr(addr)
+    }
 }
 
 #[cfg(test)]
@@ -29,7 +129,57 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_bootnodes() {
+    fn test_all_bootnodes() {
         assert_eq!(BOOTNODES.len(), 8);
     }
+
+    #[test]
+    fn test_op_mainnet_bootnodes() {
+        assert_eq!(bootnodes(10), *OP_MAINNET_BOOTNODES);
+        assert_eq!(bootnodes(10).len(), 3);
+    }
+
+    #[test]
+    fn test_base_mainnet_bootnodes() {
+        assert_eq!(bootnodes(8453), *BASE_MAINNET_BOOTNODES);
+        assert_eq!(bootnodes(8453).len(), 5);
+    }
+
+    #[test]
+    fn test_superchain_mainnet_bootnodes() {
+        for chain_id in [252, 480, 1135, 34443, 7777777] {
+            assert_eq!(bootnodes(chain_id), *OP_MAINNET_BOOTNODES);
+        }
+    }
+
+    #[test]
+    fn test_op_sepolia_bootnodes() {
+        assert_eq!(bootnodes(11155420), *OP_SEPOLIA_BOOTNODES);
+    }
+
+    #[test]
+    fn test_base_sepolia_bootnodes() {
+        assert_eq!(bootnodes(84532), *BASE_SEPOLIA_BOOTNODES);
+    }
+
+    #[test]
+    fn test_unknown_chain_bootnodes() {
+        assert!(bootnodes(420).is_empty());
+        assert!(bootnodes(901).is_empty());
+    }
+
+    #[test]
+    fn test_parse_bootnode() {
+        let enr = BASE_MAINNET_BOOTNODES[0].to_base64();
+        assert_eq!(
+            enr.parse::<Bootnode>().unwrap(),
+            Bootnode::Enr(BASE_MAINNET_BOOTNODES[0].clone())
+        );
+
+        let addr = "/ip4/127.0.0.1/tcp/9222";
+        assert_eq!(addr.parse::<Bootnode>().unwrap(), Bootnode::Multiaddr(addr.parse().unwrap()));
+
+        assert!("enr:invalid".parse::<Bootnode>().is_err());
+        assert!("127.0.0.1:9222".parse::<Bootnode>().is_err());
+    }
 }
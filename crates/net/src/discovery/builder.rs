@@ -1,6 +1,10 @@
 //! Contains a builder for the discovery service.
 
-use crate::{discovery::driver::DiscoveryDriver, types::enr::OpStackEnr};
+use crate::{
+    discovery::driver::DiscoveryDriver,
+    key::NodeKey,
+    types::{enr::OpStackEnr, peer::Reachable},
+};
 use discv5::{
     enr::{CombinedKey, Enr},
     Config, ConfigBuilder, Discv5, ListenConfig,
@@ -22,6 +26,8 @@ pub struct DiscoveryBuilder {
 
     /// The discovery config for the discovery service.
     discovery_config: Option<Config>,
+    /// The key signing the ENR of the node.
+    node_key: Option<NodeKey>,
 }
 
 impl DiscoveryBuilder {
@@ -54,6 +60,12 @@ impl DiscoveryBuilder {
         self
     }
 
+    /// Sets the key signing the ENR of the node, generating a random key if unset.
+    pub fn with_node_key(mut self, node_key: NodeKey) -> Self {
+        self.node_key = Some(node_key);
+        self
+    }
+
     /// Builds a [DiscoveryDriver].
     pub fn build(&mut self) -> Result<DiscoveryDriver> {
         let chain_id = self.chain_id.ok_or_else(|| eyre::eyre!("chain ID not set"))?;
@@ -76,7 +88,7 @@ impl DiscoveryBuilder {
             Ok(ConfigBuilder::new(listen_config).build())
         }?;
 
-        let key = CombinedKey::generate_secp256k1();
+        let key = self.node_key.take().unwrap_or_else(NodeKey::random).combined_key();
         let mut enr_builder = Enr::builder();
         enr_builder.add_value_rlp(OP_CL_KEY, opstack_data.into());
         match config.listen_config {
@@ -92,10 +104,13 @@ impl DiscoveryBuilder {
             }
         }
         let enr = enr_builder.build(&key)?;
+        let reachable = Reachable::from(&config.listen_config);
 
         let disc = Discv5::new(enr, key, config)
             .map_err(|_| eyre::eyre!("could not create disc service"))?;
 
-        Ok(DiscoveryDriver::new(disc, chain_id))
+        let mut driver = DiscoveryDriver::new(disc, chain_id);
+        driver.reachable = reachable;
+        Ok(driver)
     }
 }
@@ -8,11 +8,18 @@ use tokio::{
 };
 use tracing::{info, warn};
 
-use discv5::{enr::NodeId, Discv5};
+use discv5::{
+    enr::{CombinedKey, Enr, NodeId},
+    Discv5,
+};
 
 use crate::{
-    discovery::{bootnodes::BOOTNODES, builder::DiscoveryBuilder},
-    types::{enr::OpStackEnr, peer::Peer},
+    discovery::{bootnodes::bootnodes, builder::DiscoveryBuilder},
+    peerstore::{Peerstore, SharedPeerstore},
+    types::{
+        enr::OpStackEnr,
+        peer::{Peer, Reachable},
+    },
 };
 
 /// The number of peers to buffer in the channel.
@@ -26,6 +33,12 @@ pub struct DiscoveryDriver {
     pub chain_id: u64,
     /// The interval to discovery random nodes.
     pub interval: Duration,
+    /// The nodes to bootstrap discovery from. Defaults to the [bootnodes] of the chain.
+    pub bootnodes: Vec<Enr<CombinedKey>>,
+    /// Remembers discovered nodes, which also bootstrap discovery.
+    pub peerstore: SharedPeerstore,
+    /// The address families of discovered peers that are dialed.
+    pub reachable: Reachable,
 }
 
 impl DiscoveryDriver {
@@ -36,11 +49,24 @@ impl DiscoveryDriver {
 
     /// Instantiates a new [DiscoveryDriver].
     pub fn new(disc: Discv5, chain_id: u64) -> Self {
-        Self { disc, chain_id, interval: Duration::from_secs(10) }
+        Self {
+            disc,
+            chain_id,
+            interval: Duration::from_secs(10),
+            bootnodes: bootnodes(chain_id),
+            peerstore: Peerstore::default().shared(),
+            reachable: Reachable::default(),
+        }
     }
 
     /// Spawns a new [Discv5] discovery service in a new tokio task.
     ///
+    /// Discovered nodes are sent with the address of their [Reachable] family, so IPv6
+    /// only nodes are dialed by IPv6 and dual stack hosts.
+    ///
+    /// Discovery is bootstrapped from the bootnodes and the nodes remembered by the
+    /// peerstore, and every valid node discovered is recorded in the peerstore.
+    ///
     /// Returns a [Receiver] to receive [Peer] structs.
     ///
     /// ## Errors
@@ -72,8 +98,11 @@ impl DiscoveryDriver {
     /// }
     /// ```
     pub fn start(mut self) -> Result<Receiver<Peer>> {
-        // Clone the bootnodes since the spawned thread takes mutable ownership.
-        let bootnodes = BOOTNODES.clone();
+        // Take the bootnodes since the spawned thread takes mutable ownership.
+        let mut bootnodes = std::mem::take(&mut self.bootnodes);
+        if let Ok(store) = self.peerstore.lock() {
+            bootnodes.extend(store.enrs().cloned());
+        }
 
         // Create a multi-producer, single-consumer (mpsc) channel to receive
         // peers bounded by `DISCOVERY_PEER_CHANNEL_SIZE`.
@@ -92,17 +121,27 @@ impl DiscoveryDriver {
 
             info!("Started peer discovery");
 
+            let reachable = self.reachable;
             loop {
                 let target = NodeId::random();
                 match self.disc.find_node(target).await {
                     Ok(nodes) => {
-                        let peers = nodes
-                            .iter()
+                        let nodes = nodes
+                            .into_iter()
                             .filter(|node| OpStackEnr::is_valid_node(node, self.chain_id))
-                            .flat_map(Peer::try_from);
+                            .collect::<Vec<_>>();
+                        if let Ok(mut store) = self.peerstore.lock() {
+                            nodes.iter().for_each(|node| store.add_enr(node.clone()));
+                        }
 
+                        let peers = nodes.iter().flat_map(|node| Peer::from_enr(node, reachable));
                         for peer in peers {
-                            _ = sender.send(peer).await;
+                            if sender.send(peer).await.is_err() {
+                                // The receiver was dropped, release the socket for a restart.
+                                info!("Peer receiver dropped, stopping discovery");
+                                self.disc.shutdown();
+                                return;
+                            }
                         }
                     }
                     Err(err) => {
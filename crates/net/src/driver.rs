@@ -1,29 +1,80 @@
 //! Driver for network services.
 
-use std::sync::mpsc::Receiver;
+use std::{sync::mpsc::Receiver, time::Duration};
 
 use alloy_primitives::Address;
-use alloy_rpc_types_engine::ExecutionPayload;
+use discv5::enr::{CombinedKey, Enr};
 use eyre::Result;
-use tokio::{select, sync::watch};
+use libp2p::{swarm::SwarmEvent, PeerId};
+use op_alloy_rpc_types_engine::OpNetworkPayloadEnvelope;
+use tokio::{
+    select,
+    sync::{mpsc, watch},
+    task::JoinHandle,
+    time::MissedTickBehavior,
+};
 
 use crate::{
-    builder::NetworkDriverBuilder, discovery::driver::DiscoveryDriver, gossip::driver::GossipDriver,
+    builder::NetworkDriverBuilder,
+    discovery::driver::DiscoveryDriver,
+    gossip::{
+        config::PEER_SCORE_INSPECT_FREQUENCY, driver::GossipDriver, event::Event,
+        publisher::UnsafeBlock,
+    },
+    peerstore::PEERSTORE_FLUSH_INTERVAL,
+    sync::{
+        codec::SyncPayload,
+        payloads::{PayloadLookup, PayloadRequest, PayloadSync, INVALID_PAYLOAD_BAN},
+    },
 };
 
+/// The interval at which queued payload requests are sent, as the rate limit allows.
+pub const SYNC_REQUEST_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The interval at which disconnected static peers are redialed.
+pub const STATIC_PEER_REDIAL_INTERVAL: Duration = Duration::from_secs(30);
+
 /// NetworkDriver
 ///
 /// Contains the logic to run Optimism's consensus-layer networking stack.
 /// There are two core services that are run by the driver:
 /// - Block gossip through Gossipsub.
 /// - Peer discovery with `discv5`.
+///
+/// Missed blocks are fetched from peers with the payload by number protocol, which is
+/// also served to peers.
+///
+/// Discovered peers are dialed through the connection gate of the swarm, which skips
+/// peers that are banned, connected or already being dialed, and stops dialing once the
+/// maximum number of peers is connected.
+///
+/// Bootnode addresses and static peers are dialed on start, and static peers are
+/// redialed every [STATIC_PEER_REDIAL_INTERVAL] while disconnected.
+///
+/// Discovered nodes and peer metadata are persisted every [PEERSTORE_FLUSH_INTERVAL]
+/// if a data directory is configured.
+///
+/// Gossip peer scores are inspected every [struct@PEER_SCORE_INSPECT_FREQUENCY], banning
+/// peers that score below the ban threshold.
 pub struct NetworkDriver {
     /// Channel to receive unsafe blocks.
-    pub(crate) unsafe_block_recv: Option<Receiver<ExecutionPayload>>,
+    pub(crate) unsafe_block_recv: Option<Receiver<OpNetworkPayloadEnvelope>>,
     /// Channel to send unsafe signer updates.
     pub(crate) unsafe_block_signer_sender: Option<watch::Sender<Address>>,
+    /// Channel publishing the number of connected peers.
+    pub(crate) peer_count: watch::Sender<usize>,
+    /// Channel to send unsafe blocks to publish.
+    pub(crate) publish_sender: mpsc::Sender<UnsafeBlock>,
+    /// Channel to receive unsafe blocks to publish.
+    pub(crate) publish_recv: mpsc::Receiver<UnsafeBlock>,
+    /// Channel to send ranges of payloads to fetch.
+    pub(crate) sync_sender: mpsc::Sender<PayloadRequest>,
+    /// Channel to receive ranges of payloads to fetch.
+    pub(crate) sync_recv: mpsc::Receiver<PayloadRequest>,
     /// The swarm instance.
     pub gossip: GossipDriver,
+    /// The payload by number protocol state.
+    pub sync: PayloadSync,
     /// The discovery service driver.
     pub discovery: DiscoveryDriver,
 }
@@ -34,8 +85,18 @@ impl NetworkDriver {
         NetworkDriverBuilder::new()
     }
 
+    /// Returns the ENR of the node, signed with the key of its [NetworkDriver::peer_id].
+    pub fn local_enr(&self) -> Enr<CombinedKey> {
+        self.discovery.disc.local_enr()
+    }
+
+    /// Returns the libp2p peer id of the node.
+    pub fn peer_id(&self) -> PeerId {
+        *self.gossip.swarm.local_peer_id()
+    }
+
     /// Take the unsafe block receiver.
-    pub fn take_unsafe_block_recv(&mut self) -> Option<Receiver<ExecutionPayload>> {
+    pub fn take_unsafe_block_recv(&mut self) -> Option<Receiver<OpNetworkPayloadEnvelope>> {
         self.unsafe_block_recv.take()
     }
 
@@ -44,26 +105,110 @@ impl NetworkDriver {
         self.unsafe_block_signer_sender.take()
     }
 
+    /// Returns a receiver of the number of connected gossip peers.
+    pub fn peer_count(&self) -> watch::Receiver<usize> {
+        self.peer_count.subscribe()
+    }
+
+    /// Returns a sender of unsafe blocks to sign and publish to gossip.
+    ///
+    /// Publishing requires the sequencer key to be set on the [NetworkDriverBuilder].
+    pub fn block_publisher(&self) -> mpsc::Sender<UnsafeBlock> {
+        self.publish_sender.clone()
+    }
+
+    /// Returns a sender of block number ranges to fetch from peers, anchored to the known
+    /// block following each range.
+    ///
+    /// The fetched payloads are received from [NetworkDriver::take_synced_payload_recv],
+    /// from the end of each range down, once verified to link to the known block.
+    pub fn payload_requester(&self) -> mpsc::Sender<PayloadRequest> {
+        self.sync_sender.clone()
+    }
+
+    /// Take the receiver of the payloads fetched from peers.
+    pub fn take_synced_payload_recv(&mut self) -> Option<mpsc::Receiver<SyncPayload>> {
+        self.sync.take_payload_recv()
+    }
+
+    /// Take the receiver of the payloads requested by peers, which are answered through
+    /// each [PayloadLookup].
+    ///
+    /// Until taken, peers requesting payloads are told they are not found.
+    pub fn take_payload_lookup_recv(&mut self) -> Option<mpsc::Receiver<PayloadLookup>> {
+        self.sync.take_lookup_recv()
+    }
+
     /// Starts the Discv5 peer discovery & libp2p services
-    /// and continually listens for new peers and messages to handle
-    pub fn start(mut self) -> Result<()> {
+    /// and continually listens for new peers and messages to handle.
+    ///
+    /// Returns the handle of the spawned task, which only completes if the
+    /// discovery service stops.
+    pub fn start(mut self) -> Result<JoinHandle<()>> {
+        tracing::info!("Starting node {} with ENR {}", self.peer_id(), self.local_enr());
         let mut peer_recv = self.discovery.start()?;
         self.gossip.listen()?;
-        tokio::spawn(async move {
+        self.gossip.dial_bootnodes();
+        let handle = tokio::spawn(async move {
+            let mut inspect = tokio::time::interval(*PEER_SCORE_INSPECT_FREQUENCY);
+            let mut sync_tick = tokio::time::interval(SYNC_REQUEST_INTERVAL);
+            sync_tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            let mut flush = tokio::time::interval(PEERSTORE_FLUSH_INTERVAL);
+            let mut redial = tokio::time::interval(STATIC_PEER_REDIAL_INTERVAL);
+            redial.reset();
             loop {
                 select! {
                     peer = peer_recv.recv() => {
+                        if peer.is_none() {
+                            tracing::warn!("Discovery stopped, shutting down the network driver");
+                            return;
+                        }
                         self.gossip.dial_opt(peer.clone()).await;
                         tracing::info!("Received peer: {:?} | Connected peers: {:?}", peer, self.gossip.connected_peers());
                     },
                     event = self.gossip.select_next_some() => {
                         tracing::debug!("Received event: {:?}", event);
-                        self.gossip.handle_event(event);
+                        match event {
+                            SwarmEvent::Behaviour(Event::Sync(event)) => {
+                                self.sync.handle_event(&mut self.gossip.behaviour_mut().sync, event);
+                                for peer in self.sync.take_misbehaving() {
+                                    tracing::warn!("Banning peer {} for serving invalid payloads", peer);
+                                    self.gossip.ban(peer, INVALID_PAYLOAD_BAN);
+                                }
+                            }
+                            event => self.gossip.handle_event(event),
+                        }
+                    },
+                    Some(block) = self.publish_recv.recv() => {
+                        match self.gossip.publish(&block) {
+                            Ok(id) => tracing::debug!("Published block with message id: {}", id),
+                            Err(e) => tracing::warn!("Failed to publish block: {}", e),
+                        }
+                    },
+                    Some(request) = self.sync_recv.recv() => self.sync.enqueue(request),
+                    (channel, number, payload) = self.sync.next_lookup() => {
+                        self.sync.respond(&mut self.gossip.behaviour_mut().sync, channel, number, payload);
                     },
+                    _ = sync_tick.tick(), if self.sync.pending() > 0 => {},
+                    _ = inspect.tick() => self.gossip.inspect_scores(),
+                    _ = redial.tick(), if !self.gossip.static_peers.is_empty() => {
+                        self.gossip.dial_static_peers()
+                    },
+                    _ = flush.tick() => {
+                        if let Err(e) = self.gossip.save_peerstore() {
+                            tracing::warn!("Failed to persist the peerstore: {}", e);
+                        }
+                    },
+                }
+                if self.sync.pending() > 0 {
+                    let peers = self.gossip.sync_peers();
+                    self.sync.send_requests(&mut self.gossip.behaviour_mut().sync, &peers);
                 }
+                let peers = self.gossip.connected_peers();
+                self.peer_count.send_if_modified(|count| std::mem::replace(count, peers) != peers);
             }
         });
 
-        Ok(())
+        Ok(handle)
     }
 }
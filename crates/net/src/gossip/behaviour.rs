@@ -2,27 +2,43 @@
 
 use eyre::Result;
 use libp2p::{
-    gossipsub::{Config, IdentTopic, MessageAuthenticity},
+    gossipsub::{Config, IdentTopic, MessageAuthenticity, PeerScoreParams, PeerScoreThresholds},
+    request_response::{self, ProtocolSupport},
     swarm::NetworkBehaviour,
 };
+use std::time::Duration;
 
-use super::{event::Event, handler::Handler};
+use super::{event::Event, gate::ConnectionGate, handler::Handler};
+use crate::sync::codec::{self, PayloadByNumberCodec};
+
+/// The timeout of payload by number requests.
+pub const SYNC_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Specifies the [NetworkBehaviour] of the node
 #[derive(NetworkBehaviour)]
 #[behaviour(out_event = "Event")]
 pub struct Behaviour {
+    /// Gates connections by the peer limits and ban lists.
+    pub gate: ConnectionGate,
     /// Responds to inbound pings and send outbound pings.
     pub ping: libp2p::ping::Behaviour,
     /// Enables gossipsub as the routing layer.
     pub gossipsub: libp2p::gossipsub::Behaviour,
+    /// Requests and serves payloads by block number.
+    pub sync: request_response::Behaviour<PayloadByNumberCodec>,
 }
 
 impl Behaviour {
     /// Configures the swarm behaviors, subscribes to the gossip topics, and returns a new
     /// [Behaviour].
-    pub fn new(cfg: Config, handlers: &[Box<dyn Handler>]) -> Result<Self> {
+    ///
+    /// Connections are gated with the default [crate::gossip::gate::GateConfig].
+    pub fn new(chain_id: u64, cfg: Config, handlers: &[Box<dyn Handler>]) -> Result<Self> {
         let ping = libp2p::ping::Behaviour::default();
+        let sync = request_response::Behaviour::new(
+            [(codec::protocol(chain_id), ProtocolSupport::Full)],
+            request_response::Config::default().with_request_timeout(SYNC_REQUEST_TIMEOUT),
+        );
 
         let mut gossipsub = libp2p::gossipsub::Behaviour::new(MessageAuthenticity::Anonymous, cfg)
             .map_err(|_| eyre::eyre!("gossipsub behaviour creation failed"))?;
@@ -41,14 +57,29 @@ impl Behaviour {
             })
             .collect::<Result<Vec<bool>>>()?;
 
-        Ok(Self { ping, gossipsub })
+        Ok(Self { gate: ConnectionGate::default(), ping, gossipsub, sync })
+    }
+
+    /// Enables gossipsub peer scoring with the given parameters and thresholds.
+    pub fn with_peer_score(
+        &mut self,
+        params: PeerScoreParams,
+        thresholds: PeerScoreThresholds,
+    ) -> Result<()> {
+        self.gossipsub
+            .with_peer_score(params, thresholds)
+            .map_err(|e| eyre::eyre!("invalid peer score parameters: {}", e))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::gossip::{config, handler::BlockHandler};
+    use crate::gossip::{
+        config,
+        handler::BlockHandler,
+        score::{peer_score_thresholds, ScoreConfig},
+    };
     use alloy_primitives::Address;
     use libp2p::gossipsub::{IdentTopic, TopicHash};
 
@@ -64,7 +95,7 @@ mod tests {
     fn test_behaviour_no_handlers() {
         let cfg = config::default_config_builder().build().expect("Failed to build default config");
         let handlers = vec![];
-        let _ = Behaviour::new(cfg, &handlers).unwrap();
+        let _ = Behaviour::new(0, cfg, &handlers).unwrap();
     }
 
     #[test]
@@ -73,9 +104,24 @@ mod tests {
         let (_, recv) = tokio::sync::watch::channel(Address::default());
         let (block_handler, _) = BlockHandler::new(0, recv);
         let handlers: Vec<Box<dyn Handler>> = vec![Box::new(block_handler)];
-        let behaviour = Behaviour::new(cfg, &handlers).unwrap();
+        let behaviour = Behaviour::new(0, cfg, &handlers).unwrap();
         let mut topics = behaviour.gossipsub.topics().cloned().collect::<Vec<TopicHash>>();
         topics.sort();
         assert_eq!(topics, zero_topics());
     }
+
+    #[test]
+    fn test_behaviour_with_peer_score() {
+        let cfg = config::default_config_builder().build().expect("Failed to build default config");
+        let mut behaviour = Behaviour::new(0, cfg, &[]).unwrap();
+        let peer = libp2p::PeerId::random();
+        assert_eq!(behaviour.gossipsub.peer_score(&peer), None);
+
+        let params = ScoreConfig::default().peer_score_params(&zero_topics());
+        behaviour.with_peer_score(params, peer_score_thresholds()).unwrap();
+        assert_eq!(behaviour.gossipsub.peer_score(&peer), Some(0.0));
+        assert!(behaviour
+            .with_peer_score(PeerScoreParams::default(), peer_score_thresholds())
+            .is_err());
+    }
 }
@@ -1,14 +1,29 @@
 //! Consensus-layer gossipsub driver for Optimism.
 
-use crate::gossip::{
-    behaviour::Behaviour,
-    event::Event,
-    handler::{BlockHandler, Handler},
+use crate::{
+    gossip::{
+        behaviour::Behaviour,
+        event::Event,
+        gate::Subnet,
+        handler::{BlockHandler, Handler},
+        publisher::{BlockPublisher, UnsafeBlock},
+        score::ScoreConfig,
+    },
+    peerstore::{Peerstore, SharedPeerstore},
 };
 use eyre::Result;
 use futures::stream::StreamExt;
-use libp2p::{swarm::SwarmEvent, Multiaddr, Swarm};
-use tracing::{debug, error, info};
+use libp2p::{
+    gossipsub::MessageId,
+    multiaddr::Protocol,
+    swarm::{
+        dial_opts::{DialOpts, PeerCondition},
+        DialError, SwarmEvent,
+    },
+    Multiaddr, PeerId, Swarm,
+};
+use std::{net::IpAddr, time::Duration};
+use tracing::{debug, error, info, warn};
 
 /// A [libp2p::Swarm] instance with an associated address to listen on.
 pub struct GossipDriver {
@@ -18,12 +33,31 @@ pub struct GossipDriver {
     pub addr: Multiaddr,
     /// Block handler.
     pub handler: BlockHandler,
+    /// The peer scoring config, if peers are scored.
+    pub scoring: Option<ScoreConfig>,
+    /// Signs the published blocks, if the node is a sequencer.
+    pub publisher: Option<BlockPublisher>,
+    /// Remembers the peers and their scores, and persists the bans of the connection gate.
+    pub peerstore: SharedPeerstore,
+    /// Bootnode addresses, dialed once on start.
+    pub bootnodes: Vec<Multiaddr>,
+    /// Peers that are dialed on start and redialed whenever they disconnect.
+    pub static_peers: Vec<Multiaddr>,
 }
 
 impl GossipDriver {
     /// Creates a new [GossipDriver] instance.
     pub fn new(swarm: Swarm<Behaviour>, addr: Multiaddr, handler: BlockHandler) -> Self {
-        Self { swarm, addr, handler }
+        Self {
+            swarm,
+            addr,
+            handler,
+            scoring: None,
+            publisher: None,
+            peerstore: Peerstore::default().shared(),
+            bootnodes: Vec::new(),
+            static_peers: Vec::new(),
+        }
     }
 
     /// Listens on the address.
@@ -48,13 +82,108 @@ impl GossipDriver {
         self.swarm.connected_peers().count()
     }
 
+    /// Signs the block with the sequencer key and publishes it to the topic of its
+    /// envelope version.
+    pub fn publish(&mut self, block: &UnsafeBlock) -> Result<MessageId> {
+        let publisher =
+            self.publisher.as_ref().ok_or_else(|| eyre::eyre!("sequencer key not set"))?;
+        let (version, data) = publisher.encode(block)?;
+        let topic = self.handler.topic(version).clone();
+        self.swarm
+            .behaviour_mut()
+            .gossipsub
+            .publish(topic, data)
+            .map_err(|e| eyre::eyre!("publish failed: {:?}", e))
+    }
+
+    /// Returns the connected peers that are not banned, to request payloads from.
+    pub fn sync_peers(&self) -> Vec<PeerId> {
+        self.swarm.connected_peers().filter(|peer| !self.is_banned(peer)).copied().collect()
+    }
+
+    /// Returns whether the peer is banned by the connection gate.
+    pub fn is_banned(&self, peer: &PeerId) -> bool {
+        self.swarm.behaviour().gate.is_peer_banned(peer)
+    }
+
+    /// Bans the peer for the given duration, disconnecting it and ignoring its messages.
+    pub fn ban(&mut self, peer: PeerId, duration: Duration) {
+        self.swarm.behaviour_mut().gate.ban_peer(peer, duration);
+        self.swarm.behaviour_mut().gossipsub.blacklist_peer(&peer);
+        _ = self.swarm.disconnect_peer_id(peer);
+    }
+
+    /// Bans the IP address for the given duration, disconnecting the peers connected
+    /// from it.
+    pub fn ban_ip(&mut self, ip: IpAddr, duration: Duration) {
+        self.swarm.behaviour_mut().gate.ban_ip(ip, duration);
+        self.disconnect_banned_connections();
+    }
+
+    /// Bans the subnet for the given duration, disconnecting the peers connected from it.
+    pub fn ban_subnet(&mut self, subnet: Subnet, duration: Duration) {
+        self.swarm.behaviour_mut().gate.ban_subnet(subnet, duration);
+        self.disconnect_banned_connections();
+    }
+
+    /// Disconnects the peers connected from a banned IP address or subnet.
+    fn disconnect_banned_connections(&mut self) {
+        for peer in self.swarm.behaviour().gate.banned_connections() {
+            debug!("Disconnecting peer {} connected from a banned address", peer);
+            _ = self.swarm.disconnect_peer_id(peer);
+        }
+    }
+
+    /// Lifts expired bans, then bans the connected peers scoring below the ban threshold.
+    pub fn inspect_scores(&mut self) {
+        for peer in self.swarm.behaviour_mut().gate.expire_bans() {
+            debug!("Ban of peer {} expired", peer);
+            self.swarm.behaviour_mut().gossipsub.remove_blacklisted_peer(&peer);
+        }
+
+        let Some(scoring) = self.scoring else {
+            return;
+        };
+        let gossipsub = &self.swarm.behaviour().gossipsub;
+        if let Ok(mut store) = self.peerstore.lock() {
+            for peer in self.swarm.connected_peers() {
+                store.record_peer(*peer, gossipsub.peer_score(peer));
+            }
+        }
+        let low = self
+            .swarm
+            .connected_peers()
+            .filter_map(|peer| {
+                let score = gossipsub.peer_score(peer)?;
+                (score < scoring.ban_threshold).then_some((*peer, score))
+            })
+            .collect::<Vec<_>>();
+        for (peer, score) in low {
+            warn!("Banning peer {} with score {:.2} for {:?}", peer, score, scoring.ban_duration);
+            self.ban(peer, scoring.ban_duration);
+        }
+    }
+
+    /// Expires and persists the peerstore, along with the peer bans of the connection gate.
+    pub fn save_peerstore(&self) -> Result<()> {
+        let bans = self.swarm.behaviour().gate.peer_bans().collect::<Vec<_>>();
+        let mut store =
+            self.peerstore.lock().map_err(|_| eyre::eyre!("peerstore lock poisoned"))?;
+        store.expire();
+        store.save(bans)
+    }
+
     /// Dials the given [`Option<Multiaddr>`].
+    ///
+    /// Dials denied by the connection gate, e.g. to peers that are already connected,
+    /// are skipped quietly.
     pub async fn dial_opt(&mut self, peer: Option<impl Into<Multiaddr>>) {
         let Some(addr) = peer else {
             return;
         };
-        match self.dial(addr).await {
+        match self.swarm.dial(addr.into()) {
             Ok(_) => info!("Dialed peer"),
+            Err(DialError::Denied { cause }) => debug!("Skipped dialing peer: {:?}", cause),
             Err(e) => error!("Failed to dial peer: {:?}", e),
         }
     }
@@ -66,9 +195,47 @@ impl GossipDriver {
         Ok(())
     }
 
+    /// Dials the bootnode addresses and the static peers.
+    ///
+    /// Static peers with a `/p2p/` peer id are exempt from the connection limits.
+    pub fn dial_bootnodes(&mut self) {
+        for peer in self.static_peers.iter().filter_map(peer_id) {
+            self.swarm.behaviour_mut().gate.protect(peer);
+        }
+        for addr in self.bootnodes.clone().into_iter().chain(self.static_peers.clone()) {
+            if let Err(e) = self.swarm.dial(dial_opts(addr.clone())) {
+                warn!("Failed to dial bootnode {}: {}", addr, e);
+            }
+        }
+    }
+
+    /// Redials the static peers that are neither connected nor being dialed.
+    ///
+    /// Static peers are only redialed if their address ends with a `/p2p/` peer id, since
+    /// the connection to an address without one cannot be told apart from other peers.
+    pub fn dial_static_peers(&mut self) {
+        for addr in self.static_peers.clone() {
+            let Some(peer) = peer_id(&addr) else { continue };
+            if self.swarm.is_connected(&peer) {
+                continue;
+            }
+            debug!("Redialing static peer {}", addr);
+            if let Err(e) = self.swarm.dial(dial_opts(addr.clone())) {
+                debug!("Failed to redial static peer {}: {}", addr, e);
+            }
+        }
+    }
+
     /// Handles the [`SwarmEvent<Event>`].
     pub fn handle_event(&mut self, event: SwarmEvent<Event>) {
-        if let SwarmEvent::Behaviour(Event::Gossipsub(libp2p::gossipsub::Event::Message {
+        if let SwarmEvent::ConnectionEstablished { peer_id, .. } = event {
+            if self.is_banned(&peer_id) {
+                debug!("Disconnecting banned peer {}", peer_id);
+                _ = self.swarm.disconnect_peer_id(peer_id);
+            } else if let Ok(mut store) = self.peerstore.lock() {
+                store.record_peer(peer_id, None);
+            }
+        } else if let SwarmEvent::Behaviour(Event::Gossipsub(libp2p::gossipsub::Event::Message {
             propagation_source: src,
             message_id: id,
             message,
@@ -88,3 +255,70 @@ impl GossipDriver {
         }
     }
 }
+
+/// Returns the peer id of a `/p2p/` address.
+fn peer_id(addr: &Multiaddr) -> Option<PeerId> {
+    addr.iter().find_map(|protocol| match protocol {
+        Protocol::P2p(peer) => Some(peer),
+        _ => None,
+    })
+}
+
+/// Returns the [DialOpts] of the address, skipping peers that are already connected or
+/// being dialed if the address has a peer id.
+fn dial_opts(addr: Multiaddr) -> DialOpts {
+    match peer_id(&addr) {
+        Some(peer) => DialOpts::peer_id(peer)
+            .condition(PeerCondition::DisconnectedAndNotDialing)
+            .addresses(vec![addr])
+            .build(),
+        None => DialOpts::unknown_peer_id().address(addr).build(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::NetworkDriverBuilder;
+    use alloy_primitives::Address;
+    use std::{
+        net::{IpAddr, Ipv4Addr, SocketAddr},
+        time::Duration,
+    };
+
+    #[test]
+    fn test_bans_expire() {
+        let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 9099);
+        let mut driver = NetworkDriverBuilder::new()
+            .with_unsafe_block_signer(Address::random())
+            .with_chain_id(10)
+            .with_gossip_addr(socket)
+            .build()
+            .unwrap();
+        let gossip = &mut driver.gossip;
+        assert!(gossip.scoring.is_some());
+
+        let (banned, expired) = (PeerId::random(), PeerId::random());
+        gossip.ban(banned, Duration::from_secs(60));
+        gossip.ban(expired, Duration::ZERO);
+        assert!(gossip.is_banned(&banned));
+        assert!(!gossip.is_banned(&expired));
+
+        gossip.inspect_scores();
+        let bans = gossip.swarm.behaviour().gate.peer_bans().collect::<Vec<_>>();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].0, banned);
+    }
+
+    #[test]
+    fn test_static_peer_id() {
+        let peer = PeerId::random();
+        let addr: Multiaddr = format!("/ip4/127.0.0.1/tcp/9222/p2p/{}", peer).parse().unwrap();
+        assert_eq!(peer_id(&addr), Some(peer));
+        assert_eq!(dial_opts(addr).get_peer_id(), Some(peer));
+
+        let addr: Multiaddr = "/ip4/127.0.0.1/tcp/9222".parse().unwrap();
+        assert_eq!(peer_id(&addr), None);
+        assert_eq!(dial_opts(addr).get_peer_id(), None);
+    }
+}
@@ -1,6 +1,9 @@
 //! Event Handling Module.
 
-use libp2p::{gossipsub, ping};
+use libp2p::{gossipsub, ping, request_response};
+use std::convert::Infallible;
+
+use crate::sync::codec::PayloadResponse;
 
 /// The type of message received
 #[derive(Debug)]
@@ -10,6 +13,8 @@ pub enum Event {
     Ping(ping::Event),
     /// Represents a [gossipsub::Event]
     Gossipsub(gossipsub::Event),
+    /// Represents a [request_response::Event] of the payload by number protocol
+    Sync(request_response::Event<u64, PayloadResponse>),
 }
 
 impl From<ping::Event> for Event {
@@ -25,3 +30,17 @@ impl From<gossipsub::Event> for Event {
         Event::Gossipsub(value)
     }
 }
+
+impl From<request_response::Event<u64, PayloadResponse>> for Event {
+    /// Converts [request_response::Event] to [Event]
+    fn from(value: request_response::Event<u64, PayloadResponse>) -> Self {
+        Event::Sync(value)
+    }
+}
+
+impl From<Infallible> for Event {
+    /// Converts the events of behaviours that never emit any
+    fn from(value: Infallible) -> Self {
+        match value {}
+    }
+}
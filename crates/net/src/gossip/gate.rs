@@ -0,0 +1,643 @@
+//! Connection Gating
+
+use libp2p::{
+    core::{transport::PortUse, Endpoint},
+    multiaddr::Protocol,
+    swarm::{
+        dummy, ConnectionDenied, ConnectionId, FromSwarm, NetworkBehaviour, THandler,
+        THandlerInEvent, THandlerOutEvent, ToSwarm,
+    },
+    Multiaddr, PeerId,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    fmt,
+    net::IpAddr,
+    str::FromStr,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// The default maximum number of connected peers.
+pub const DEFAULT_MAX_PEERS: usize = 30;
+
+/// The default maximum number of connections from a single IP address.
+pub const DEFAULT_MAX_PEERS_PER_IP: usize = 3;
+
+/// The default maximum number of connections from a single subnet.
+pub const DEFAULT_MAX_PEERS_PER_SUBNET: usize = 10;
+
+/// The prefix length of the IPv4 subnets that connections are limited by.
+pub const IPV4_SUBNET_PREFIX: u8 = 24;
+
+/// The prefix length of the IPv6 subnets that connections are limited by.
+pub const IPV6_SUBNET_PREFIX: u8 = 64;
+
+/// The connection limits enforced by the [ConnectionGate].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GateConfig {
+    /// The maximum number of connected peers.
+    pub max_peers: usize,
+    /// The maximum number of connections from a single IP address.
+    pub max_peers_per_ip: usize,
+    /// The maximum number of connections from a single subnet, see [Subnet::of].
+    pub max_peers_per_subnet: usize,
+}
+
+impl Default for GateConfig {
+    fn default() -> Self {
+        Self {
+            max_peers: DEFAULT_MAX_PEERS,
+            max_peers_per_ip: DEFAULT_MAX_PEERS_PER_IP,
+            max_peers_per_subnet: DEFAULT_MAX_PEERS_PER_SUBNET,
+        }
+    }
+}
+
+/// An IP subnet, such as `10.0.0.0/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Subnet {
+    /// The network address, with the host bits cleared.
+    addr: IpAddr,
+    /// The prefix length.
+    prefix: u8,
+}
+
+impl Subnet {
+    /// Creates the subnet of the address with the given prefix length.
+    pub fn new(addr: IpAddr, prefix: u8) -> eyre::Result<Self> {
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        if prefix > max {
+            eyre::bail!("invalid prefix length {} for {}", prefix, addr);
+        }
+        Ok(Self { addr: mask(addr, prefix), prefix })
+    }
+
+    /// Returns the subnet connections from the address are limited by, which is its
+    /// `/24` for IPv4 and its `/64` for IPv6.
+    pub fn of(addr: IpAddr) -> Self {
+        let prefix = if addr.is_ipv4() { IPV4_SUBNET_PREFIX } else { IPV6_SUBNET_PREFIX };
+        Self { addr: mask(addr, prefix), prefix }
+    }
+
+    /// Returns whether the address is in the subnet.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        addr.is_ipv4() == self.addr.is_ipv4() && mask(addr, self.prefix) == self.addr
+    }
+}
+
+impl FromStr for Subnet {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = s.split_once('/').ok_or_else(|| eyre::eyre!("missing prefix"))?;
+        Self::new(addr.parse()?, prefix.parse()?)
+    }
+}
+
+impl fmt::Display for Subnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// Clears the host bits of the address.
+fn mask(addr: IpAddr, prefix: u8) -> IpAddr {
+    match addr {
+        IpAddr::V4(ip) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            IpAddr::V4((u32::from(ip) & mask).into())
+        }
+        IpAddr::V6(ip) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            IpAddr::V6((u128::from(ip) & mask).into())
+        }
+    }
+}
+
+/// Returns the IP address of a multiaddr.
+pub fn multiaddr_ip(addr: &Multiaddr) -> Option<IpAddr> {
+    addr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
+/// The reason a connection was denied by the [ConnectionGate].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Denied {
+    /// The peer, its IP address or its subnet is banned.
+    Banned,
+    /// The maximum number of peers is connected.
+    MaxPeers,
+    /// The maximum number of connections from the IP address is reached.
+    MaxPeersPerIp,
+    /// The maximum number of connections from the subnet is reached.
+    MaxPeersPerSubnet,
+    /// The peer is already connected or being dialed.
+    Duplicate,
+}
+
+impl fmt::Display for Denied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Banned => write!(f, "peer is banned"),
+            Self::MaxPeers => write!(f, "maximum number of peers reached"),
+            Self::MaxPeersPerIp => write!(f, "maximum number of peers per IP reached"),
+            Self::MaxPeersPerSubnet => write!(f, "maximum number of peers per subnet reached"),
+            Self::Duplicate => write!(f, "peer is already connected or being dialed"),
+        }
+    }
+}
+
+impl std::error::Error for Denied {}
+
+/// A [NetworkBehaviour] gating the connections of the swarm.
+///
+/// Connections are denied to banned peers, IP addresses and subnets, and inbound
+/// connections beyond the [GateConfig] limits. Dials to peers or addresses that are
+/// already connected or being dialed are dropped, and so are dials once the maximum
+/// number of peers is connected. Loopback addresses are exempt from the per IP and
+/// subnet limits, and protected peers from every limit but bans.
+///
+/// Bans expire lazily, and are pruned by [ConnectionGate::expire_bans].
+#[derive(Debug, Default)]
+pub struct ConnectionGate {
+    /// The connection limits.
+    pub config: GateConfig,
+    /// The established connections, with their peer and remote address.
+    connections: HashMap<ConnectionId, (PeerId, Multiaddr)>,
+    /// The pending dials, with their peer if known and addresses.
+    dialing: HashMap<ConnectionId, (Option<PeerId>, Vec<Multiaddr>)>,
+    /// Peers exempt from the connection limits.
+    protected: HashSet<PeerId>,
+    /// Banned peers, until the given time or indefinitely.
+    peer_bans: HashMap<PeerId, Option<Instant>>,
+    /// Banned IP addresses, until the given time or indefinitely.
+    ip_bans: HashMap<IpAddr, Option<Instant>>,
+    /// Banned subnets, until the given time or indefinitely.
+    subnet_bans: HashMap<Subnet, Option<Instant>>,
+}
+
+impl ConnectionGate {
+    /// Creates a new [ConnectionGate] enforcing the given limits.
+    pub fn new(config: GateConfig) -> Self {
+        Self { config, ..Default::default() }
+    }
+
+    /// Exempts the peer from the connection limits, e.g. for static peers.
+    pub fn protect(&mut self, peer: PeerId) {
+        self.protected.insert(peer);
+    }
+
+    /// Bans the peer for the given duration, or indefinitely if it overflows.
+    pub fn ban_peer(&mut self, peer: PeerId, duration: Duration) {
+        self.peer_bans.insert(peer, Instant::now().checked_add(duration));
+    }
+
+    /// Bans the IP address for the given duration, or indefinitely if it overflows.
+    pub fn ban_ip(&mut self, ip: IpAddr, duration: Duration) {
+        self.ip_bans.insert(ip, Instant::now().checked_add(duration));
+    }
+
+    /// Bans the subnet for the given duration, or indefinitely if it overflows.
+    pub fn ban_subnet(&mut self, subnet: Subnet, duration: Duration) {
+        self.subnet_bans.insert(subnet, Instant::now().checked_add(duration));
+    }
+
+    /// Lifts the ban of the peer.
+    pub fn unban_peer(&mut self, peer: &PeerId) {
+        self.peer_bans.remove(peer);
+    }
+
+    /// Lifts the ban of the IP address.
+    pub fn unban_ip(&mut self, ip: &IpAddr) {
+        self.ip_bans.remove(ip);
+    }
+
+    /// Lifts the ban of the subnet.
+    pub fn unban_subnet(&mut self, subnet: &Subnet) {
+        self.subnet_bans.remove(subnet);
+    }
+
+    /// Returns whether the peer is banned.
+    pub fn is_peer_banned(&self, peer: &PeerId) -> bool {
+        self.peer_bans.get(peer).is_some_and(active)
+    }
+
+    /// Returns whether the IP address, or a subnet containing it, is banned.
+    pub fn is_ip_banned(&self, ip: IpAddr) -> bool {
+        self.ip_bans.get(&ip).is_some_and(active)
+            || self.subnet_bans.iter().any(|(subnet, until)| subnet.contains(ip) && active(until))
+    }
+
+    /// Returns the banned peers, with the remaining duration of their ban, or `None` if
+    /// they are banned indefinitely.
+    pub fn peer_bans(&self) -> impl Iterator<Item = (PeerId, Option<Duration>)> + '_ {
+        let now = Instant::now();
+        self.peer_bans.iter().filter(|(_, until)| active(until)).map(move |(peer, until)| {
+            (*peer, until.map(|until| until.saturating_duration_since(now)))
+        })
+    }
+
+    /// Drops the expired bans, returning the peers whose ban expired.
+    pub fn expire_bans(&mut self) -> Vec<PeerId> {
+        let mut expired = Vec::new();
+        self.peer_bans.retain(|peer, until| {
+            let active = active(until);
+            if !active {
+                expired.push(*peer);
+            }
+            active
+        });
+        self.ip_bans.retain(|_, until| active(until));
+        self.subnet_bans.retain(|_, until| active(until));
+        expired
+    }
+
+    /// Returns the peers connected from a banned IP address or subnet.
+    pub fn banned_connections(&self) -> Vec<PeerId> {
+        let mut peers = self
+            .connections
+            .values()
+            .filter(|(_, addr)| multiaddr_ip(addr).is_some_and(|ip| self.is_ip_banned(ip)))
+            .map(|(peer, _)| *peer)
+            .collect::<Vec<_>>();
+        peers.sort();
+        peers.dedup();
+        peers
+    }
+
+    /// Returns the number of connected peers.
+    pub fn connected_peers(&self) -> usize {
+        self.connections.values().map(|(peer, _)| peer).collect::<HashSet<_>>().len()
+    }
+
+    /// Returns whether the peer is connected.
+    fn is_connected(&self, peer: &PeerId) -> bool {
+        self.connections.values().any(|(connected, _)| connected == peer)
+    }
+
+    /// Checks a connection from or to the remote address against the bans and, unless
+    /// the peer is protected, the connection limits.
+    fn check_addr(&self, remote: &Multiaddr, peer: Option<&PeerId>) -> Result<(), Denied> {
+        let Some(ip) = multiaddr_ip(remote) else {
+            return Ok(());
+        };
+        if self.is_ip_banned(ip) {
+            return Err(Denied::Banned);
+        }
+        if peer.is_some_and(|peer| self.protected.contains(peer)) || ip.is_loopback() {
+            return Ok(());
+        }
+
+        let subnet = Subnet::of(ip);
+        let (mut same_ip, mut same_subnet) = (0, 0);
+        for other in self.connections.values().filter_map(|(_, addr)| multiaddr_ip(addr)) {
+            same_ip += usize::from(other == ip);
+            same_subnet += usize::from(subnet.contains(other));
+        }
+        if same_ip >= self.config.max_peers_per_ip {
+            return Err(Denied::MaxPeersPerIp);
+        }
+        if same_subnet >= self.config.max_peers_per_subnet {
+            return Err(Denied::MaxPeersPerSubnet);
+        }
+        Ok(())
+    }
+
+    /// Checks a new peer against the ban list and the peer limit.
+    fn check_peer(&self, peer: &PeerId) -> Result<(), Denied> {
+        if self.is_peer_banned(peer) {
+            return Err(Denied::Banned);
+        }
+        if self.protected.contains(peer) || self.is_connected(peer) {
+            return Ok(());
+        }
+        if self.connected_peers() >= self.config.max_peers {
+            return Err(Denied::MaxPeers);
+        }
+        Ok(())
+    }
+
+    /// Checks a dial, dropping dials to peers or addresses already connected or dialed.
+    fn check_dial(&self, peer: Option<&PeerId>, addresses: &[Multiaddr]) -> Result<(), Denied> {
+        if let Some(peer) = peer {
+            if self.is_peer_banned(peer) {
+                return Err(Denied::Banned);
+            }
+            let dialing = self.dialing.values().any(|(dialed, _)| dialed.as_ref() == Some(peer));
+            if dialing || self.is_connected(peer) {
+                return Err(Denied::Duplicate);
+            }
+        }
+        for addr in addresses {
+            let connected = self.connections.values().any(|(_, remote)| remote == addr);
+            let dialing = self.dialing.values().any(|(_, dialed)| dialed.contains(addr));
+            if connected || dialing {
+                return Err(Denied::Duplicate);
+            }
+            if multiaddr_ip(addr).is_some_and(|ip| self.is_ip_banned(ip)) {
+                return Err(Denied::Banned);
+            }
+        }
+        let protected = peer.is_some_and(|peer| self.protected.contains(peer));
+        if !protected && self.connected_peers() >= self.config.max_peers {
+            return Err(Denied::MaxPeers);
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether a ban until the given time, or indefinitely, is active.
+fn active(until: &Option<Instant>) -> bool {
+    until.map_or(true, |until| until > Instant::now())
+}
+
+impl NetworkBehaviour for ConnectionGate {
+    type ConnectionHandler = dummy::ConnectionHandler;
+    type ToSwarm = Infallible;
+
+    fn handle_pending_inbound_connection(
+        &mut self,
+        _: ConnectionId,
+        _: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<(), ConnectionDenied> {
+        self.check_addr(remote_addr, None).map_err(ConnectionDenied::new)
+    }
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        _: ConnectionId,
+        peer: PeerId,
+        _: &Multiaddr,
+        _: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.check_peer(&peer).map_err(ConnectionDenied::new)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn handle_pending_outbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        maybe_peer: Option<PeerId>,
+        addresses: &[Multiaddr],
+        _: Endpoint,
+    ) -> Result<Vec<Multiaddr>, ConnectionDenied> {
+        self.check_dial(maybe_peer.as_ref(), addresses).map_err(ConnectionDenied::new)?;
+        self.dialing.insert(connection_id, (maybe_peer, addresses.to_vec()));
+        Ok(Vec::new())
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        addr: &Multiaddr,
+        _: Endpoint,
+        _: PortUse,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        let denied = match self.check_peer(&peer) {
+            // A dial that passed the peer limit may race with inbound connections.
+            Err(Denied::MaxPeers) => Ok(()),
+            result => result,
+        }
+        // The address is only known once dialed, so its limits are checked here.
+        .and_then(|()| self.check_addr(addr, Some(&peer)));
+        if let Err(denied) = denied {
+            self.dialing.remove(&connection_id);
+            return Err(ConnectionDenied::new(denied));
+        }
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm<'_>) {
+        match event {
+            FromSwarm::ConnectionEstablished(established) => {
+                self.dialing.remove(&established.connection_id);
+                let addr = established.endpoint.get_remote_address().clone();
+                self.connections.insert(established.connection_id, (established.peer_id, addr));
+            }
+            FromSwarm::ConnectionClosed(closed) => {
+                self.connections.remove(&closed.connection_id);
+            }
+            FromSwarm::DialFailure(failure) => {
+                self.dialing.remove(&failure.connection_id);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        _: PeerId,
+        _: ConnectionId,
+        _: THandlerOutEvent<Self>,
+    ) {
+    }
+
+    fn poll(&mut self, _: &mut Context<'_>) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::core::ConnectedPoint;
+
+    fn addr(s: &str) -> Multiaddr {
+        s.parse().unwrap()
+    }
+
+    fn connect(gate: &mut ConnectionGate, peer: PeerId, remote: &str) -> ConnectionId {
+        let id = ConnectionId::new_unchecked(gate.connections.len() + gate.dialing.len() + 1000);
+        gate.connections.insert(id, (peer, addr(remote)));
+        id
+    }
+
+    fn denied(result: Result<(), ConnectionDenied>) -> Denied {
+        result.unwrap_err().downcast::<Denied>().unwrap()
+    }
+
+    #[test]
+    fn test_subnet() {
+        let subnet: Subnet = "10.1.2.3/16".parse().unwrap();
+        assert_eq!(subnet.to_string(), "10.1.0.0/16");
+        assert!(subnet.contains("10.1.200.1".parse().unwrap()));
+        assert!(!subnet.contains("10.2.0.1".parse().unwrap()));
+        assert!(!subnet.contains("::1".parse().unwrap()));
+
+        let subnet = Subnet::of("2001:db8::1".parse().unwrap());
+        assert_eq!(subnet.to_string(), "2001:db8::/64");
+        assert!(subnet.contains("2001:db8::ffff".parse().unwrap()));
+        assert_eq!("0.0.0.0/0".parse::<Subnet>().unwrap().prefix, 0);
+        assert!("10.0.0.0/33".parse::<Subnet>().is_err());
+        assert!("10.0.0.0".parse::<Subnet>().is_err());
+    }
+
+    #[test]
+    fn test_inbound_limits() {
+        let config = GateConfig { max_peers: 3, max_peers_per_ip: 1, max_peers_per_subnet: 2 };
+        let mut gate = ConnectionGate::new(config);
+        let local = addr("/ip4/0.0.0.0/tcp/9222");
+        let pending = |gate: &mut ConnectionGate, remote: &str| {
+            gate.handle_pending_inbound_connection(
+                ConnectionId::new_unchecked(0),
+                &local,
+                &addr(remote),
+            )
+        };
+
+        connect(&mut gate, PeerId::random(), "/ip4/1.2.3.4/tcp/1");
+        assert_eq!(denied(pending(&mut gate, "/ip4/1.2.3.4/tcp/2")), Denied::MaxPeersPerIp);
+        assert!(pending(&mut gate, "/ip4/1.2.3.5/tcp/1").is_ok());
+        connect(&mut gate, PeerId::random(), "/ip4/1.2.3.5/tcp/1");
+        assert_eq!(denied(pending(&mut gate, "/ip4/1.2.3.6/tcp/1")), Denied::MaxPeersPerSubnet);
+        assert!(pending(&mut gate, "/ip4/127.0.0.1/tcp/1").is_ok());
+
+        connect(&mut gate, PeerId::random(), "/ip4/5.6.7.8/tcp/1");
+        let peer = PeerId::random();
+        let established = gate
+            .handle_established_inbound_connection(
+                ConnectionId::new_unchecked(1),
+                peer,
+                &local,
+                &addr("/ip4/9.9.9.9/tcp/1"),
+            )
+            .map(|_| ());
+        assert_eq!(denied(established), Denied::MaxPeers);
+
+        gate.protect(peer);
+        assert!(gate
+            .handle_established_inbound_connection(
+                ConnectionId::new_unchecked(1),
+                peer,
+                &local,
+                &addr("/ip4/9.9.9.9/tcp/1"),
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_outbound_limits() {
+        let config = GateConfig { max_peers: 10, max_peers_per_ip: 1, max_peers_per_subnet: 2 };
+        let mut gate = ConnectionGate::new(config);
+        let established = |gate: &mut ConnectionGate, id: usize, peer: PeerId, remote: &str| {
+            gate.handle_established_outbound_connection(
+                ConnectionId::new_unchecked(id),
+                peer,
+                &addr(remote),
+                Endpoint::Dialer,
+                PortUse::Reuse,
+            )
+            .map(|_| ())
+        };
+
+        connect(&mut gate, PeerId::random(), "/ip4/1.2.3.4/tcp/1");
+        let ip = established(&mut gate, 1, PeerId::random(), "/ip4/1.2.3.4/tcp/2");
+        assert_eq!(denied(ip), Denied::MaxPeersPerIp);
+        assert!(established(&mut gate, 2, PeerId::random(), "/ip4/1.2.3.5/tcp/1").is_ok());
+        connect(&mut gate, PeerId::random(), "/ip4/1.2.3.5/tcp/1");
+        let subnet = established(&mut gate, 3, PeerId::random(), "/ip4/1.2.3.6/tcp/1");
+        assert_eq!(denied(subnet), Denied::MaxPeersPerSubnet);
+
+        let peer = PeerId::random();
+        gate.protect(peer);
+        assert!(established(&mut gate, 4, peer, "/ip4/1.2.3.6/tcp/1").is_ok());
+    }
+
+    #[test]
+    fn test_bans() {
+        let mut gate = ConnectionGate::default();
+        let local = addr("/ip4/0.0.0.0/tcp/9222");
+        let (peer, other) = (PeerId::random(), PeerId::random());
+        connect(&mut gate, other, "/ip4/10.0.0.1/tcp/1");
+
+        let (expired, forever) = (PeerId::random(), PeerId::random());
+        gate.ban_peer(peer, Duration::from_secs(60));
+        gate.ban_peer(expired, Duration::ZERO);
+        gate.ban_peer(forever, Duration::MAX);
+        gate.ban_ip("1.2.3.4".parse().unwrap(), Duration::from_secs(60));
+        gate.ban_subnet("10.0.0.0/8".parse().unwrap(), Duration::MAX);
+        gate.ban_ip("5.6.7.8".parse().unwrap(), Duration::ZERO);
+
+        assert!(gate.is_peer_banned(&peer));
+        assert!(gate.is_ip_banned("1.2.3.4".parse().unwrap()));
+        assert!(gate.is_ip_banned("10.20.30.40".parse().unwrap()));
+        assert!(!gate.is_ip_banned("5.6.7.8".parse().unwrap()));
+        assert_eq!(gate.banned_connections(), [other]);
+
+        let pending = gate.handle_pending_inbound_connection(
+            ConnectionId::new_unchecked(0),
+            &local,
+            &addr("/ip4/1.2.3.4/tcp/1"),
+        );
+        assert_eq!(denied(pending), Denied::Banned);
+        let dial = gate
+            .handle_pending_outbound_connection(
+                ConnectionId::new_unchecked(0),
+                Some(peer),
+                &[],
+                Endpoint::Dialer,
+            )
+            .map(|_| ());
+        assert_eq!(denied(dial), Denied::Banned);
+
+        let bans = gate.peer_bans().collect::<HashMap<_, _>>();
+        assert_eq!(bans.len(), 2);
+        assert!(bans[&peer].is_some_and(|remaining| remaining <= Duration::from_secs(60)));
+        assert_eq!(bans[&forever], None);
+
+        assert_eq!(gate.expire_bans(), [expired]);
+        assert_eq!(gate.ip_bans.len(), 1);
+        gate.unban_peer(&peer);
+        gate.unban_subnet(&"10.0.0.0/8".parse().unwrap());
+        assert!(!gate.is_peer_banned(&peer));
+        assert!(gate.banned_connections().is_empty());
+    }
+
+    #[test]
+    fn test_dial_dedup() {
+        let mut gate = ConnectionGate::new(GateConfig { max_peers: 2, ..Default::default() });
+        let (peer, other) = (PeerId::random(), PeerId::random());
+        let dial = |gate: &mut ConnectionGate, id: usize, peer: Option<PeerId>, remote: &str| {
+            gate.handle_pending_outbound_connection(
+                ConnectionId::new_unchecked(id),
+                peer,
+                &[addr(remote)],
+                Endpoint::Dialer,
+            )
+            .map(|_| ())
+        };
+
+        assert!(dial(&mut gate, 1, Some(peer), "/ip4/1.1.1.1/tcp/1").is_ok());
+        assert_eq!(denied(dial(&mut gate, 2, Some(peer), "/ip4/1.1.1.2/tcp/1")), Denied::Duplicate);
+        assert_eq!(denied(dial(&mut gate, 3, None, "/ip4/1.1.1.1/tcp/1")), Denied::Duplicate);
+
+        let endpoint = ConnectedPoint::Dialer {
+            address: addr("/ip4/1.1.1.1/tcp/1"),
+            role_override: Endpoint::Dialer,
+            port_use: PortUse::Reuse,
+        };
+        gate.on_swarm_event(FromSwarm::ConnectionEstablished(
+            libp2p::swarm::behaviour::ConnectionEstablished {
+                peer_id: peer,
+                connection_id: ConnectionId::new_unchecked(1),
+                endpoint: &endpoint,
+                failed_addresses: &[],
+                other_established: 0,
+            },
+        ));
+        assert!(gate.dialing.is_empty());
+        assert_eq!(gate.connected_peers(), 1);
+        assert_eq!(denied(dial(&mut gate, 4, Some(peer), "/ip4/1.1.1.2/tcp/1")), Denied::Duplicate);
+        assert_eq!(denied(dial(&mut gate, 5, None, "/ip4/1.1.1.1/tcp/1")), Denied::Duplicate);
+
+        connect(&mut gate, other, "/ip4/2.2.2.2/tcp/1");
+        assert_eq!(denied(dial(&mut gate, 6, None, "/ip4/3.3.3.3/tcp/1")), Denied::MaxPeers);
+    }
+}
@@ -6,11 +6,12 @@ use std::{
 };
 
 use alloy_primitives::Address;
-use alloy_rpc_types_engine::ExecutionPayload;
 use libp2p::gossipsub::{IdentTopic, Message, MessageAcceptance, TopicHash};
 use op_alloy_rpc_types_engine::OpNetworkPayloadEnvelope;
 use tokio::sync::watch;
 
+use super::publisher::BlockVersion;
+
 /// This trait defines the functionality required to process incoming messages
 /// and determine their acceptance within the network.
 ///
@@ -30,8 +31,9 @@ pub struct BlockHandler {
     /// Chain ID of the L2 blockchain. Used to filter out gossip messages intended for other
     /// blockchains.
     pub chain_id: u64,
-    /// A channel sender to forward new blocks to other modules
-    pub block_sender: Sender<ExecutionPayload>,
+    /// A channel sender to forward new blocks to other modules, along with the
+    /// parent beacon block root carried by V3 envelopes.
+    pub block_sender: Sender<OpNetworkPayloadEnvelope>,
     /// A [Receiver] to monitor changes to the unsafe block signer.
     pub unsafe_signer_recv: watch::Receiver<Address>,
     /// The libp2p topic for pre Canyon/Shangai blocks.
@@ -64,7 +66,7 @@ impl Handler for BlockHandler {
         match decoded {
             Ok(envelope) => {
                 if self.block_valid(&envelope) {
-                    _ = self.block_sender.send(envelope.payload);
+                    _ = self.block_sender.send(envelope);
                     MessageAcceptance::Accept
                 } else {
                     tracing::warn!("invalid unsafe block");
@@ -89,7 +91,7 @@ impl BlockHandler {
     pub fn new(
         chain_id: u64,
         unsafe_recv: watch::Receiver<Address>,
-    ) -> (Self, Receiver<ExecutionPayload>) {
+    ) -> (Self, Receiver<OpNetworkPayloadEnvelope>) {
         let (sender, recv) = channel();
 
         let handler = Self {
@@ -104,6 +106,15 @@ impl BlockHandler {
         (handler, recv)
     }
 
+    /// Returns the topic of blocks with the given envelope version.
+    pub const fn topic(&self, version: BlockVersion) -> &IdentTopic {
+        match version {
+            BlockVersion::V1 => &self.blocks_v1_topic,
+            BlockVersion::V2 => &self.blocks_v2_topic,
+            BlockVersion::V3 => &self.blocks_v3_topic,
+        }
+    }
+
     /// Determines if a block is valid.
     ///
     /// True if the block is less than 1 minute old, and correctly signed by the unsafe block
@@ -4,4 +4,7 @@ pub mod behaviour;
 pub mod config;
 pub mod driver;
 pub mod event;
+pub mod gate;
 pub mod handler;
+pub mod publisher;
+pub mod score;
@@ -0,0 +1,202 @@
+//! Block Publisher
+
+use alloy_primitives::B256;
+use alloy_rpc_types_engine::ExecutionPayloadV3;
+use eyre::Result;
+use k256::ecdsa::SigningKey;
+use op_alloy_rpc_types_engine::PayloadHash;
+use snap::raw::Encoder;
+use ssz::Encode;
+
+/// The version of a gossiped block envelope, which selects its topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockVersion {
+    /// Pre Canyon blocks, without withdrawals.
+    V1,
+    /// Canyon and Delta blocks, with withdrawals.
+    V2,
+    /// Ecotone blocks, carrying the parent beacon block root.
+    V3,
+}
+
+/// An unsafe block to sign and publish.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsafeBlock {
+    /// The execution payload, encoded in the envelope version of its timestamp.
+    pub payload: ExecutionPayloadV3,
+    /// The parent beacon block root, required from Ecotone.
+    pub parent_beacon_block_root: Option<B256>,
+}
+
+/// Signs and encodes unsafe blocks with the sequencer key.
+#[derive(Debug, Clone)]
+pub struct BlockPublisher {
+    /// Chain ID of the L2 blockchain, part of the signed message.
+    pub chain_id: u64,
+    /// The sequencer key signing the blocks.
+    signer: SigningKey,
+    /// The Canyon activation timestamp.
+    pub canyon_time: Option<u64>,
+    /// The Ecotone activation timestamp.
+    pub ecotone_time: Option<u64>,
+}
+
+impl BlockPublisher {
+    /// Creates a new [BlockPublisher] signing with the given sequencer key.
+    pub const fn new(
+        chain_id: u64,
+        signer: SigningKey,
+        canyon_time: Option<u64>,
+        ecotone_time: Option<u64>,
+    ) -> Self {
+        Self { chain_id, signer, canyon_time, ecotone_time }
+    }
+
+    /// Returns the [BlockVersion] of a block with the given timestamp.
+    pub fn version(&self, timestamp: u64) -> BlockVersion {
+        let active = |time: Option<u64>| time.is_some_and(|time| timestamp >= time);
+        if active(self.ecotone_time) {
+            BlockVersion::V3
+        } else if active(self.canyon_time) {
+            BlockVersion::V2
+        } else {
+            BlockVersion::V1
+        }
+    }
+
+    /// Encodes the block in the envelope version of its timestamp, signs the payload hash
+    /// and snappy-compresses the signed envelope.
+    ///
+    /// The envelope is the 65 byte signature followed by the SSZ encoded payload, which
+    /// is prefixed with the parent beacon block root from Ecotone.
+    pub fn encode(&self, block: &UnsafeBlock) -> Result<(BlockVersion, Vec<u8>)> {
+        let payload = &block.payload;
+        let version = self.version(payload.payload_inner.payload_inner.timestamp);
+        let data = match version {
+            BlockVersion::V1 => payload.payload_inner.payload_inner.as_ssz_bytes(),
+            BlockVersion::V2 => payload.payload_inner.as_ssz_bytes(),
+            BlockVersion::V3 => {
+                let root = block
+                    .parent_beacon_block_root
+                    .ok_or_else(|| eyre::eyre!("missing parent beacon block root"))?;
+                [root.as_slice(), payload.as_ssz_bytes().as_slice()].concat()
+            }
+        };
+
+        let msg = PayloadHash::from(data.as_slice()).signature_message(self.chain_id);
+        let (signature, recovery_id) = self
+            .signer
+            .sign_prehash_recoverable(msg.as_slice())
+            .map_err(|e| eyre::eyre!("failed to sign block: {}", e))?;
+
+        let mut envelope = Vec::with_capacity(65 + data.len());
+        envelope.extend_from_slice(&signature.to_bytes());
+        envelope.push(recovery_id.to_byte());
+        envelope.extend_from_slice(&data);
+
+        let compressed = Encoder::new()
+            .compress_vec(&envelope)
+            .map_err(|e| eyre::eyre!("failed to compress block: {}", e))?;
+        Ok((version, compressed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gossip::handler::{BlockHandler, Handler};
+    use alloy_primitives::{Address, Bloom, Bytes, U256};
+    use alloy_rpc_types_engine::{ExecutionPayloadV1, ExecutionPayloadV2};
+    use libp2p::gossipsub::{Message, MessageAcceptance};
+    use op_alloy_rpc_types_engine::OpExecutionPayload;
+    use std::time::SystemTime;
+
+    fn unsafe_block(timestamp: u64) -> UnsafeBlock {
+        let payload = ExecutionPayloadV3 {
+            payload_inner: ExecutionPayloadV2 {
+                payload_inner: ExecutionPayloadV1 {
+                    parent_hash: B256::repeat_byte(1),
+                    fee_recipient: Address::repeat_byte(2),
+                    state_root: B256::repeat_byte(3),
+                    receipts_root: B256::repeat_byte(4),
+                    logs_bloom: Bloom::default(),
+                    prev_randao: B256::repeat_byte(5),
+                    block_number: 100,
+                    gas_limit: 30_000_000,
+                    gas_used: 21_000,
+                    timestamp,
+                    extra_data: Bytes::new(),
+                    base_fee_per_gas: U256::from(7),
+                    block_hash: B256::repeat_byte(6),
+                    transactions: vec![Bytes::from_static(&[0x7e, 0x01])],
+                },
+                withdrawals: vec![],
+            },
+            blob_gas_used: 0,
+            excess_blob_gas: 0,
+        };
+        UnsafeBlock { payload, parent_beacon_block_root: Some(B256::repeat_byte(8)) }
+    }
+
+    #[test]
+    fn test_version_by_timestamp() {
+        let publisher =
+            BlockPublisher::new(10, SigningKey::from_slice(&[1; 32]).unwrap(), Some(10), Some(20));
+        assert_eq!(publisher.version(9), BlockVersion::V1);
+        assert_eq!(publisher.version(10), BlockVersion::V2);
+        assert_eq!(publisher.version(20), BlockVersion::V3);
+
+        let publisher = BlockPublisher { canyon_time: None, ecotone_time: None, ..publisher };
+        assert_eq!(publisher.version(u64::MAX), BlockVersion::V1);
+    }
+
+    #[test]
+    fn test_publish_round_trip() {
+        let key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+        let signer = Address::from_private_key(&key);
+        let (_sender, signer_recv) = tokio::sync::watch::channel(signer);
+        let (handler, blocks) = BlockHandler::new(10, signer_recv);
+
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+        let block = unsafe_block(now);
+        for (canyon, ecotone) in [(None, None), (Some(0), None), (Some(0), Some(0))] {
+            let publisher = BlockPublisher::new(10, key.clone(), canyon, ecotone);
+            let (version, data) = publisher.encode(&block).unwrap();
+            let topic = handler.topic(version).hash();
+            let msg = Message { source: None, data, sequence_number: None, topic };
+            assert!(matches!(handler.handle(msg), MessageAcceptance::Accept));
+
+            let envelope = blocks.try_recv().unwrap();
+            let inner = block.payload.clone();
+            let (payload, root) = match version {
+                BlockVersion::V1 => {
+                    (OpExecutionPayload::V1(inner.payload_inner.payload_inner), None)
+                }
+                BlockVersion::V2 => (OpExecutionPayload::V2(inner.payload_inner), None),
+                BlockVersion::V3 => (OpExecutionPayload::V3(inner), block.parent_beacon_block_root),
+            };
+            assert_eq!(envelope.payload, payload);
+            assert_eq!(envelope.parent_beacon_block_root, root);
+        }
+
+        // Blocks signed by another key are rejected.
+        let publisher =
+            BlockPublisher::new(10, SigningKey::from_slice(&[1; 32]).unwrap(), None, None);
+        let (version, data) = publisher.encode(&block).unwrap();
+        let msg = Message {
+            source: None,
+            data,
+            sequence_number: None,
+            topic: handler.topic(version).hash(),
+        };
+        assert!(matches!(handler.handle(msg), MessageAcceptance::Reject));
+    }
+
+    #[test]
+    fn test_encode_requires_beacon_root_from_ecotone() {
+        let publisher =
+            BlockPublisher::new(10, SigningKey::from_slice(&[1; 32]).unwrap(), Some(0), Some(0));
+        let block = UnsafeBlock { parent_beacon_block_root: None, ..unsafe_block(1) };
+        assert!(publisher.encode(&block).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! Gossipsub peer scoring.
+//!
+//! The parameters mirror the "light" peer scoring of the OP Stack's `op-node`, scaled
+//! to the block time of the chain.
+
+use libp2p::gossipsub::{PeerScoreParams, PeerScoreThresholds, TopicHash, TopicScoreParams};
+use std::{collections::HashMap, time::Duration};
+
+/// Scores decaying below this value are reset to zero.
+pub const DECAY_TO_ZERO: f64 = 0.01;
+
+/// The default block time of OP Stack chains, in seconds.
+pub const DEFAULT_BLOCK_TIME: u64 = 2;
+
+/// The default score below which peers are disconnected and banned.
+pub const DEFAULT_BAN_THRESHOLD: f64 = -100.0;
+
+/// The default duration of a peer ban.
+pub const DEFAULT_BAN_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// The number of blocks in a scoring epoch.
+const BLOCKS_PER_EPOCH: u32 = 6;
+
+/// Returns the per-slot decay factor that decays a score to [DECAY_TO_ZERO]
+/// over `duration`.
+pub fn score_decay(duration: Duration, slot: Duration) -> f64 {
+    let slots = duration.as_secs_f64() / slot.as_secs_f64();
+    DECAY_TO_ZERO.powf(1.0 / slots)
+}
+
+/// Returns the OP Stack [PeerScoreThresholds].
+pub fn peer_score_thresholds() -> PeerScoreThresholds {
+    PeerScoreThresholds {
+        gossip_threshold: -10.0,
+        publish_threshold: -40.0,
+        graylist_threshold: -40.0,
+        accept_px_threshold: 20.0,
+        opportunistic_graft_threshold: 0.05,
+    }
+}
+
+/// Configures gossipsub peer scoring and the banning of low scoring peers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreConfig {
+    /// The block time of the chain in seconds, which paces score decay.
+    pub block_time: u64,
+    /// Peers scoring below this value are disconnected and banned.
+    pub ban_threshold: f64,
+    /// How long banned peers are refused.
+    pub ban_duration: Duration,
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        Self {
+            block_time: DEFAULT_BLOCK_TIME,
+            ban_threshold: DEFAULT_BAN_THRESHOLD,
+            ban_duration: DEFAULT_BAN_DURATION,
+        }
+    }
+}
+
+impl ScoreConfig {
+    /// Returns the duration of a slot, falling back to the default block time if unset.
+    pub const fn slot(&self) -> Duration {
+        match self.block_time {
+            0 => Duration::from_secs(DEFAULT_BLOCK_TIME),
+            secs => Duration::from_secs(secs),
+        }
+    }
+
+    /// Returns the duration of a scoring epoch.
+    fn epoch(&self) -> Duration {
+        self.slot() * BLOCKS_PER_EPOCH
+    }
+
+    /// Returns the [PeerScoreParams] for the given block topics, ordered from the oldest
+    /// to the newest block version.
+    ///
+    /// Mesh delivery scoring only applies to the newest topic: blocks are published on
+    /// a single topic at a time, so every mesh peer of an older topic would otherwise
+    /// be penalized for under-delivering.
+    pub fn peer_score_params(&self, topics: &[TopicHash]) -> PeerScoreParams {
+        let slot = self.slot();
+        let epoch = self.epoch();
+        let topics = topics
+            .iter()
+            .enumerate()
+            .map(|(i, topic)| (topic.clone(), self.topic_score_params(i + 1 == topics.len())))
+            .collect::<HashMap<_, _>>();
+
+        PeerScoreParams {
+            topics,
+            topic_score_cap: 34.0,
+            app_specific_weight: 1.0,
+            ip_colocation_factor_weight: -35.0,
+            ip_colocation_factor_threshold: 10.0,
+            behaviour_penalty_weight: -16.0,
+            behaviour_penalty_threshold: 6.0,
+            behaviour_penalty_decay: score_decay(10 * epoch, slot),
+            decay_interval: slot,
+            decay_to_zero: DECAY_TO_ZERO,
+            retain_score: 100 * epoch,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the [TopicScoreParams] of a block topic.
+    ///
+    /// Peers are rewarded for time in the mesh and first deliveries, and penalized for
+    /// invalid messages and, if `mesh_deliveries` is set, for under-delivering blocks.
+    pub fn topic_score_params(&self, mesh_deliveries: bool) -> TopicScoreParams {
+        let slot = self.slot();
+        let epoch = self.epoch();
+        let mesh_weight = if mesh_deliveries { -0.717 } else { 0.0 };
+
+        TopicScoreParams {
+            topic_weight: 0.8,
+            time_in_mesh_weight: 0.0324,
+            time_in_mesh_quantum: slot,
+            time_in_mesh_cap: 300.0,
+            first_message_deliveries_weight: 1.0,
+            first_message_deliveries_decay: score_decay(20 * epoch, slot),
+            first_message_deliveries_cap: 23.0,
+            mesh_message_deliveries_weight: mesh_weight,
+            mesh_message_deliveries_decay: score_decay(5 * epoch, slot),
+            mesh_message_deliveries_cap: 14.0,
+            mesh_message_deliveries_threshold: 4.0,
+            mesh_message_deliveries_window: Duration::from_secs(2),
+            mesh_message_deliveries_activation: 4 * epoch,
+            mesh_failure_penalty_weight: mesh_weight,
+            mesh_failure_penalty_decay: score_decay(5 * epoch, slot),
+            invalid_message_deliveries_weight: -140.4475,
+            invalid_message_deliveries_decay: score_decay(50 * epoch, slot),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::gossipsub::IdentTopic;
+
+    #[test]
+    fn test_score_decay() {
+        let slot = Duration::from_secs(2);
+        let decay = score_decay(Duration::from_secs(20), slot);
+        assert!((decay.powi(10) - DECAY_TO_ZERO).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_params_are_valid() {
+        let topics = (0..3)
+            .map(|v| IdentTopic::new(format!("/optimism/10/{v}/blocks")).hash())
+            .collect::<Vec<_>>();
+        for block_time in [0, 1, 2, 12] {
+            let config = ScoreConfig { block_time, ..Default::default() };
+            let params = config.peer_score_params(&topics);
+            params.validate().unwrap();
+            assert_eq!(params.topics[&topics[0]].mesh_message_deliveries_weight, 0.0);
+            assert!(params.topics[&topics[2]].mesh_message_deliveries_weight < 0.0);
+        }
+        peer_score_thresholds().validate().unwrap();
+    }
+}
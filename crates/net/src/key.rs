@@ -0,0 +1,160 @@
+//! Node Identity
+
+use alloy_primitives::hex;
+use discv5::enr::{CombinedKey, CombinedPublicKey, Enr, EnrPublicKey};
+use eyre::Result;
+use libp2p::PeerId;
+use libp2p_identity::{secp256k1, Keypair, PublicKey};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tracing::info;
+
+/// The name of the node key file within the data directory.
+pub const NODE_KEY_FILE: &str = "node_key";
+
+/// The secp256k1 key identifying the node.
+///
+/// The same key backs the libp2p identity and the discv5 ENR, so the ENR advertised by
+/// discovery corresponds to the peer id of the gossip swarm.
+#[derive(Debug, Clone)]
+pub struct NodeKey(secp256k1::Keypair);
+
+impl NodeKey {
+    /// Generates a new random [NodeKey].
+    pub fn random() -> Self {
+        Self(secp256k1::Keypair::generate())
+    }
+
+    /// Creates a [NodeKey] from the 32 byte secret key.
+    pub fn from_bytes(mut bytes: [u8; 32]) -> Result<Self> {
+        let secret = secp256k1::SecretKey::try_from_bytes(&mut bytes)
+            .map_err(|e| eyre::eyre!("invalid node key: {}", e))?;
+        Ok(Self(secret.into()))
+    }
+
+    /// Loads the node key from the data directory, generating and persisting a new key
+    /// if there is none.
+    ///
+    /// The key is stored as a hex encoded secret key, readable by the owner only.
+    pub fn load_or_create(data_dir: impl AsRef<Path>) -> Result<Self> {
+        let path = Self::path(data_dir.as_ref());
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                let bytes = hex::decode(contents.trim())
+                    .map_err(|e| eyre::eyre!("invalid node key {}: {}", path.display(), e))?;
+                let bytes = <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| {
+                    eyre::eyre!("invalid node key {}: expected 32 bytes", path.display())
+                })?;
+                Self::from_bytes(bytes)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = Self::random();
+                key.save(&path)?;
+                info!("Generated a new node key at {}", path.display());
+                Ok(key)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the path of the node key file within the data directory.
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join(NODE_KEY_FILE)
+    }
+
+    /// Returns the 32 byte secret key.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.secret().to_bytes()
+    }
+
+    /// Returns the libp2p [Keypair] of the node.
+    pub fn keypair(&self) -> Keypair {
+        self.0.clone().into()
+    }
+
+    /// Returns the discv5 [CombinedKey] of the node.
+    pub fn combined_key(&self) -> CombinedKey {
+        CombinedKey::secp256k1_from_bytes(&mut self.to_bytes())
+            .expect("a valid secp256k1 secret key")
+    }
+
+    /// Returns the peer id of the node.
+    pub fn peer_id(&self) -> PeerId {
+        PublicKey::from(self.0.public().clone()).to_peer_id()
+    }
+
+    /// Writes the key to the path, creating its directory if needed.
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(path)?.write_all(hex::encode(self.to_bytes()).as_bytes())?;
+        Ok(())
+    }
+}
+
+impl TryFrom<Keypair> for NodeKey {
+    type Error = eyre::Report;
+
+    /// Converts a secp256k1 [Keypair], since discv5 ENRs are signed with secp256k1 keys.
+    fn try_from(keypair: Keypair) -> Result<Self> {
+        keypair
+            .try_into_secp256k1()
+            .map(Self)
+            .map_err(|_| eyre::eyre!("node keypair must be secp256k1"))
+    }
+}
+
+/// Returns the libp2p peer id of the node with the given ENR.
+pub fn enr_peer_id(enr: &Enr<CombinedKey>) -> Result<PeerId> {
+    let CombinedPublicKey::Secp256k1(key) = enr.public_key() else {
+        eyre::bail!("ENR key is not secp256k1");
+    };
+    let key = secp256k1::PublicKey::try_from_bytes(&key.encode())
+        .map_err(|e| eyre::eyre!("invalid ENR key: {}", e))?;
+    Ok(PublicKey::from(key).to_peer_id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_or_create() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = NodeKey::load_or_create(dir.path()).unwrap();
+        let reloaded = NodeKey::load_or_create(dir.path()).unwrap();
+        assert_eq!(key.to_bytes(), reloaded.to_bytes());
+        assert_eq!(key.peer_id(), reloaded.peer_id());
+
+        fs::write(NodeKey::path(dir.path()), "0xdead").unwrap();
+        assert!(NodeKey::load_or_create(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_enr_matches_peer_id() {
+        let key = NodeKey::random();
+        assert_eq!(key.keypair().public().to_peer_id(), key.peer_id());
+
+        let enr = Enr::builder().build(&key.combined_key()).unwrap();
+        assert_eq!(enr_peer_id(&enr).unwrap(), key.peer_id());
+
+        let other = Enr::builder().build(&CombinedKey::generate_secp256k1()).unwrap();
+        assert_ne!(enr_peer_id(&other).unwrap(), key.peer_id());
+    }
+
+    #[test]
+    fn test_from_keypair() {
+        let keypair = Keypair::generate_secp256k1();
+        let key = NodeKey::try_from(keypair.clone()).unwrap();
+        assert_eq!(key.peer_id(), keypair.public().to_peer_id());
+        assert_eq!(key.keypair().public(), keypair.public());
+    }
+}
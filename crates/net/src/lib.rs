@@ -7,4 +7,7 @@ pub mod builder;
 pub mod discovery;
 pub mod driver;
 pub mod gossip;
+pub mod key;
+pub mod peerstore;
+pub mod sync;
 pub mod types;
@@ -0,0 +1,299 @@
+//! Persistent store of discovered nodes and gossip peers.
+
+use discv5::enr::{CombinedKey, Enr, NodeId};
+use eyre::Result;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+use tracing::warn;
+
+/// The name of the peerstore file within the data directory.
+pub const PEERSTORE_FILE: &str = "peerstore.json";
+
+/// Entries that were not seen for this long are expired.
+pub const DEFAULT_PEER_EXPIRY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The interval at which the peerstore is expired and persisted.
+pub const PEERSTORE_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A [Peerstore] shared by the discovery and gossip services.
+pub type SharedPeerstore = Arc<Mutex<Peerstore>>;
+
+/// A discovered node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeEntry {
+    /// The latest known ENR of the node.
+    pub enr: Enr<CombinedKey>,
+    /// When the node was last discovered, in seconds since the unix epoch.
+    pub last_seen: u64,
+}
+
+/// Metadata of a gossip peer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PeerEntry {
+    /// When the peer was last connected, in seconds since the unix epoch.
+    pub last_seen: u64,
+    /// The last inspected gossip score of the peer.
+    pub score: f64,
+}
+
+/// The serialized form of a [Peerstore].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PeerstoreFile {
+    /// The discovered nodes, as ENR strings with their last seen time.
+    nodes: Vec<(String, u64)>,
+    /// The gossip peers by peer id.
+    peers: HashMap<String, PeerEntry>,
+    /// The banned peers by peer id, with when their ban expires in seconds since the
+    /// unix epoch.
+    #[serde(default)]
+    bans: HashMap<String, u64>,
+}
+
+/// Remembers discovered nodes and gossip peer metadata across restarts.
+///
+/// Stores opened in a data directory are persisted with [Peerstore::save], and entries
+/// not seen within the expiry are dropped on load and by [Peerstore::expire].
+///
+/// Peer bans are held by the [ConnectionGate](crate::gossip::gate::ConnectionGate); the
+/// store only persists them, and hands the persisted bans back with [Peerstore::bans].
+#[derive(Debug, Clone)]
+pub struct Peerstore {
+    /// The file the store is persisted to, if any.
+    path: Option<PathBuf>,
+    /// How long entries are kept after they were last seen.
+    expiry: Duration,
+    /// The discovered nodes.
+    nodes: HashMap<NodeId, NodeEntry>,
+    /// The gossip peers.
+    peers: HashMap<PeerId, PeerEntry>,
+}
+
+impl Default for Peerstore {
+    fn default() -> Self {
+        Self {
+            path: None,
+            expiry: DEFAULT_PEER_EXPIRY,
+            nodes: HashMap::new(),
+            peers: HashMap::new(),
+        }
+    }
+}
+
+impl Peerstore {
+    /// Opens the peerstore of the data directory, creating the directory if needed and
+    /// loading the persisted entries.
+    ///
+    /// Entries that fail to parse are skipped, and an unreadable store is started afresh.
+    pub fn open(data_dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = data_dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let path = dir.join(PEERSTORE_FILE);
+        let file = read(&path)?;
+
+        let mut store = Self { path: Some(path), ..Default::default() };
+        for (enr, last_seen) in file.nodes {
+            if let Ok(enr) = Enr::<CombinedKey>::from_str(&enr) {
+                store.nodes.insert(enr.node_id(), NodeEntry { enr, last_seen });
+            }
+        }
+        for (peer, entry) in file.peers {
+            if let Ok(peer) = PeerId::from_str(&peer) {
+                store.peers.insert(peer, entry);
+            }
+        }
+        store.expire();
+        Ok(store)
+    }
+
+    /// Sets how long entries are kept after they were last seen.
+    pub const fn with_expiry(mut self, expiry: Duration) -> Self {
+        self.expiry = expiry;
+        self
+    }
+
+    /// Wraps the store to share it between services.
+    pub fn shared(self) -> SharedPeerstore {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Returns the file the store is persisted to, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the ENRs of the discovered nodes.
+    pub fn enrs(&self) -> impl Iterator<Item = &Enr<CombinedKey>> {
+        self.nodes.values().map(|node| &node.enr)
+    }
+
+    /// Records a discovered node, keeping the ENR with the highest sequence number.
+    pub fn add_enr(&mut self, enr: Enr<CombinedKey>) {
+        let last_seen = now();
+        let node =
+            self.nodes.entry(enr.node_id()).or_insert(NodeEntry { enr: enr.clone(), last_seen });
+        if enr.seq() >= node.enr.seq() {
+            node.enr = enr;
+        }
+        node.last_seen = last_seen;
+    }
+
+    /// Returns the metadata of a gossip peer.
+    pub fn peer(&self, peer: &PeerId) -> Option<&PeerEntry> {
+        self.peers.get(peer)
+    }
+
+    /// Records that the gossip peer was seen, with its latest score if known.
+    pub fn record_peer(&mut self, peer: PeerId, score: Option<f64>) {
+        let entry = self.peers.entry(peer).or_default();
+        entry.last_seen = now();
+        if let Some(score) = score {
+            entry.score = score;
+        }
+    }
+
+    /// Returns the persisted peer bans that have not expired, with their remaining
+    /// duration, to restore them to the connection gate.
+    pub fn bans(&self) -> Result<Vec<(PeerId, Duration)>> {
+        let Some(path) = &self.path else {
+            return Ok(Vec::new());
+        };
+        let now = now();
+        let bans = read(path)?
+            .bans
+            .into_iter()
+            .filter(|(_, until)| *until > now)
+            .filter_map(|(peer, until)| {
+                let peer = PeerId::from_str(&peer).ok()?;
+                let remaining = match until {
+                    u64::MAX => Duration::MAX,
+                    until => Duration::from_secs(until - now),
+                };
+                Some((peer, remaining))
+            })
+            .collect();
+        Ok(bans)
+    }
+
+    /// Drops the entries that were not seen within the expiry.
+    pub fn expire(&mut self) {
+        let cutoff = now().saturating_sub(self.expiry.as_secs());
+        self.nodes.retain(|_, node| node.last_seen >= cutoff);
+        self.peers.retain(|_, entry| entry.last_seen >= cutoff);
+    }
+
+    /// Persists the store along with the given peer bans, if it was opened in a data
+    /// directory.
+    ///
+    /// The bans are given with their remaining duration, or `None` if indefinite, as
+    /// returned by [ConnectionGate::peer_bans](crate::gossip::gate::ConnectionGate::peer_bans).
+    /// The store is written to a temporary file first and then renamed so that a crash
+    /// mid-write never leaves a truncated store behind.
+    pub fn save(&self, bans: impl IntoIterator<Item = (PeerId, Option<Duration>)>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let now = now();
+        let file = PeerstoreFile {
+            nodes: self.nodes.values().map(|node| (node.enr.to_base64(), node.last_seen)).collect(),
+            peers: self.peers.iter().map(|(peer, entry)| (peer.to_string(), *entry)).collect(),
+            bans: bans
+                .into_iter()
+                .map(|(peer, remaining)| {
+                    let until = remaining.map_or(u64::MAX, |d| now.saturating_add(d.as_secs()));
+                    (peer.to_string(), until)
+                })
+                .collect(),
+        };
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&file)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Reads the persisted store, starting afresh if it is missing or unreadable.
+fn read(path: &Path) -> Result<PeerstoreFile> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable peerstore {}: {}", path.display(), e);
+            PeerstoreFile::default()
+        })),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PeerstoreFile::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Returns the current time in seconds since the unix epoch.
+fn now() -> u64 {
+    unix(SystemTime::now())
+}
+
+/// Converts the time to seconds since the unix epoch.
+fn unix(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::bootnodes::BOOTNODES;
+
+    #[test]
+    fn test_persist_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = Peerstore::open(dir.path()).unwrap();
+        assert_eq!(store.path(), Some(dir.path().join(PEERSTORE_FILE).as_path()));
+        assert_eq!(store.enrs().count(), 0);
+
+        let (peer, banned, forever) = (PeerId::random(), PeerId::random(), PeerId::random());
+        store.add_enr(BOOTNODES[0].clone());
+        store.add_enr(BOOTNODES[1].clone());
+        store.record_peer(peer, Some(-1.5));
+        store.save([(banned, Some(Duration::from_secs(600))), (forever, None)]).unwrap();
+
+        let reloaded = Peerstore::open(dir.path()).unwrap();
+        let mut enrs = reloaded.enrs().cloned().collect::<Vec<_>>();
+        enrs.sort_by_key(|enr| enr.node_id());
+        let mut expected = BOOTNODES[..2].to_vec();
+        expected.sort_by_key(|enr| enr.node_id());
+        assert_eq!(enrs, expected);
+        assert_eq!(reloaded.peer(&peer).unwrap().score, -1.5);
+        let bans = reloaded.bans().unwrap().into_iter().collect::<HashMap<_, _>>();
+        assert_eq!(bans.len(), 2);
+        assert!(bans[&banned] <= Duration::from_secs(600));
+        assert_eq!(bans[&forever], Duration::MAX);
+    }
+
+    #[test]
+    fn test_expire() {
+        let mut store = Peerstore::default().with_expiry(Duration::from_secs(60));
+        let (stale, fresh) = (PeerId::random(), PeerId::random());
+        store.add_enr(BOOTNODES[0].clone());
+        store.add_enr(BOOTNODES[1].clone());
+        store.nodes.get_mut(&BOOTNODES[0].node_id()).unwrap().last_seen = 0;
+        store.record_peer(fresh, None);
+        store.peers.insert(stale, PeerEntry { last_seen: 0, ..Default::default() });
+
+        store.expire();
+        assert_eq!(store.enrs().collect::<Vec<_>>(), [&BOOTNODES[1]]);
+        assert!(store.peer(&stale).is_none());
+        assert!(store.peer(&fresh).is_some());
+    }
+
+    #[test]
+    fn test_unreadable_store_starts_afresh() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(PEERSTORE_FILE), b"not json").unwrap();
+        let store = Peerstore::open(dir.path()).unwrap();
+        assert_eq!(store.enrs().count(), 0);
+        assert!(store.bans().unwrap().is_empty());
+    }
+}
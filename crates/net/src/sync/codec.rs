@@ -0,0 +1,329 @@
+//! Payload By Number Codec
+
+use alloy_primitives::B256;
+use alloy_rpc_types_engine::{ExecutionPayloadV1, ExecutionPayloadV2, ExecutionPayloadV3};
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use libp2p::{request_response, StreamProtocol};
+use op_alloy_consensus::OpTxEnvelope;
+use op_alloy_rpc_types_engine::OpExecutionPayload;
+use snap::{read::FrameDecoder, write::FrameEncoder};
+use ssz::{Decode, Encode};
+use std::io::{self, Read, Write};
+
+use crate::gossip::config::MAX_GOSSIP_SIZE;
+
+/// The maximum size of a compressed or decompressed payload response.
+pub const MAX_PAYLOAD_SIZE: usize = MAX_GOSSIP_SIZE;
+
+/// The result code of a successful response.
+pub const RESULT_SUCCESS: u8 = 0;
+
+/// The response to a payload request.
+pub type PayloadResponse = Result<SyncPayload, ResultCode>;
+
+/// Returns the payload by number protocol of the chain.
+pub fn protocol(chain_id: u64) -> StreamProtocol {
+    StreamProtocol::try_from_owned(format!("/opstack/req/payload_by_number/{}/0", chain_id))
+        .expect("protocol starts with a slash")
+}
+
+/// The result code of a failed payload request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ResultCode {
+    /// The payload is not known to the server.
+    NotFound = 1,
+    /// The request was invalid.
+    InvalidRequest = 2,
+    /// The server failed to serve the payload.
+    Unknown = 3,
+}
+
+impl From<u8> for ResultCode {
+    fn from(code: u8) -> Self {
+        match code {
+            1 => Self::NotFound,
+            2 => Self::InvalidRequest,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// A payload served by number, without the gossip signature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncPayload {
+    /// The execution payload.
+    pub payload: OpExecutionPayload,
+    /// The parent beacon block root, carried by Ecotone payloads.
+    pub parent_beacon_block_root: Option<B256>,
+}
+
+impl SyncPayload {
+    /// Returns the block number of the payload.
+    pub const fn block_number(&self) -> u64 {
+        match &self.payload {
+            OpExecutionPayload::V1(payload) => payload.block_number,
+            OpExecutionPayload::V2(payload) => payload.payload_inner.block_number,
+            OpExecutionPayload::V3(payload) => payload.payload_inner.payload_inner.block_number,
+        }
+    }
+
+    /// Returns the block hash claimed by the payload.
+    pub const fn block_hash(&self) -> B256 {
+        match &self.payload {
+            OpExecutionPayload::V1(payload) => payload.block_hash,
+            OpExecutionPayload::V2(payload) => payload.payload_inner.block_hash,
+            OpExecutionPayload::V3(payload) => payload.payload_inner.payload_inner.block_hash,
+        }
+    }
+
+    /// Returns the parent hash of the payload.
+    pub const fn parent_hash(&self) -> B256 {
+        match &self.payload {
+            OpExecutionPayload::V1(payload) => payload.parent_hash,
+            OpExecutionPayload::V2(payload) => payload.payload_inner.parent_hash,
+            OpExecutionPayload::V3(payload) => payload.payload_inner.payload_inner.parent_hash,
+        }
+    }
+
+    /// Computes the hash of the block built from the payload.
+    ///
+    /// Returns `None` if the payload does not convert to a block, such as when its
+    /// transactions fail to decode.
+    pub fn compute_block_hash(&self) -> Option<B256> {
+        let header = match self.payload.clone() {
+            OpExecutionPayload::V1(payload) => {
+                payload.try_into_block::<OpTxEnvelope>().ok()?.header
+            }
+            OpExecutionPayload::V2(payload) => {
+                payload.try_into_block::<OpTxEnvelope>().ok()?.header
+            }
+            OpExecutionPayload::V3(payload) => {
+                let mut header = payload.try_into_block::<OpTxEnvelope>().ok()?.header;
+                header.parent_beacon_block_root = self.parent_beacon_block_root;
+                header
+            }
+        };
+        Some(header.hash_slow())
+    }
+
+    /// Encodes the payload, returning its response version and SSZ bytes.
+    ///
+    /// Version 0 holds a pre Ecotone payload, and version 1 an Ecotone payload prefixed
+    /// with its parent beacon block root.
+    pub fn encode(&self) -> io::Result<(u32, Vec<u8>)> {
+        Ok(match &self.payload {
+            OpExecutionPayload::V1(payload) => (0, payload.as_ssz_bytes()),
+            OpExecutionPayload::V2(payload) => (0, payload.as_ssz_bytes()),
+            OpExecutionPayload::V3(payload) => {
+                let root = self.parent_beacon_block_root.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "missing parent beacon block root")
+                })?;
+                (1, [root.as_slice(), payload.as_ssz_bytes().as_slice()].concat())
+            }
+        })
+    }
+
+    /// Decodes a payload of the given response version.
+    ///
+    /// Version 0 payloads are decoded as Canyon payloads first, falling back to pre Canyon
+    /// payloads, since the SSZ offsets of either fail to decode as the other.
+    pub fn decode(version: u32, data: &[u8]) -> io::Result<Self> {
+        let invalid = |e: ssz::DecodeError| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("invalid payload: {:?}", e))
+        };
+        match version {
+            0 => {
+                let payload = ExecutionPayloadV2::from_ssz_bytes(data)
+                    .map(OpExecutionPayload::V2)
+                    .or_else(|_| {
+                        ExecutionPayloadV1::from_ssz_bytes(data).map(OpExecutionPayload::V1)
+                    })
+                    .map_err(invalid)?;
+                Ok(Self { payload, parent_beacon_block_root: None })
+            }
+            1 if data.len() >= 32 => {
+                let root = B256::from_slice(&data[..32]);
+                let payload = ExecutionPayloadV3::from_ssz_bytes(&data[32..]).map_err(invalid)?;
+                Ok(Self {
+                    payload: OpExecutionPayload::V3(payload),
+                    parent_beacon_block_root: Some(root),
+                })
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported payload version {}", version),
+            )),
+        }
+    }
+}
+
+/// Encodes payload by number requests and responses.
+///
+/// A request is the little-endian block number. A response starts with its result code,
+/// and successful responses continue with the little-endian `u32` payload version and
+/// the snappy frame compressed payload.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PayloadByNumberCodec;
+
+#[async_trait]
+impl request_response::Codec for PayloadByNumberCodec {
+    type Protocol = StreamProtocol;
+    type Request = u64;
+    type Response = PayloadResponse;
+
+    async fn read_request<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<u64>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let mut number = [0u8; 8];
+        io.read_exact(&mut number).await?;
+        Ok(u64::from_le_bytes(number))
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        _: &StreamProtocol,
+        io: &mut T,
+    ) -> io::Result<PayloadResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let mut code = [0u8; 1];
+        io.read_exact(&mut code).await?;
+        if code[0] != RESULT_SUCCESS {
+            return Ok(Err(ResultCode::from(code[0])));
+        }
+
+        let mut version = [0u8; 4];
+        io.read_exact(&mut version).await?;
+        let mut compressed = Vec::new();
+        io.take(MAX_PAYLOAD_SIZE as u64).read_to_end(&mut compressed).await?;
+        let mut data = Vec::new();
+        Read::take(FrameDecoder::new(compressed.as_slice()), MAX_PAYLOAD_SIZE as u64)
+            .read_to_end(&mut data)?;
+
+        SyncPayload::decode(u32::from_le_bytes(version), &data).map(Ok)
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &StreamProtocol,
+        io: &mut T,
+        number: u64,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        io.write_all(&number.to_le_bytes()).await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &StreamProtocol,
+        io: &mut T,
+        response: PayloadResponse,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let payload = match response {
+            Ok(payload) => payload,
+            Err(code) => return io.write_all(&[code as u8]).await,
+        };
+
+        let (version, data) = payload.encode()?;
+        let mut encoder = FrameEncoder::new(Vec::new());
+        encoder.write_all(&data)?;
+        let compressed = encoder.into_inner().map_err(|e| io::Error::other(e.to_string()))?;
+
+        io.write_all(&[RESULT_SUCCESS]).await?;
+        io.write_all(&version.to_le_bytes()).await?;
+        io.write_all(&compressed).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, Bloom, Bytes, U256};
+    use futures::{executor::block_on, io::Cursor};
+    use request_response::Codec;
+
+    fn payload_v1(block_number: u64) -> ExecutionPayloadV1 {
+        ExecutionPayloadV1 {
+            parent_hash: B256::repeat_byte(1),
+            fee_recipient: Address::repeat_byte(2),
+            state_root: B256::repeat_byte(3),
+            receipts_root: B256::repeat_byte(4),
+            logs_bloom: Bloom::default(),
+            prev_randao: B256::repeat_byte(5),
+            block_number,
+            gas_limit: 30_000_000,
+            gas_used: 21_000,
+            timestamp: 1_700_000_000,
+            extra_data: Bytes::new(),
+            base_fee_per_gas: U256::from(7),
+            block_hash: B256::repeat_byte(6),
+            transactions: vec![Bytes::from_static(&[0x7e, 0x01])],
+        }
+    }
+
+    fn round_trip(response: PayloadResponse) -> PayloadResponse {
+        let protocol = protocol(10);
+        let mut codec = PayloadByNumberCodec;
+        let mut io = Cursor::new(Vec::new());
+        block_on(codec.write_response(&protocol, &mut io, response)).unwrap();
+        io.set_position(0);
+        block_on(codec.read_response(&protocol, &mut io)).unwrap()
+    }
+
+    #[test]
+    fn test_protocol() {
+        assert_eq!(protocol(10).as_ref(), "/opstack/req/payload_by_number/10/0");
+    }
+
+    #[test]
+    fn test_request_round_trip() {
+        let protocol = protocol(10);
+        let mut codec = PayloadByNumberCodec;
+        let mut io = Cursor::new(Vec::new());
+        block_on(codec.write_request(&protocol, &mut io, 0x0102)).unwrap();
+        assert_eq!(io.get_ref(), &[0x02, 0x01, 0, 0, 0, 0, 0, 0]);
+        io.set_position(0);
+        assert_eq!(block_on(codec.read_request(&protocol, &mut io)).unwrap(), 0x0102);
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let v1 = payload_v1(1);
+        let v2 = ExecutionPayloadV2 { payload_inner: payload_v1(2), withdrawals: vec![] };
+        let v3 = ExecutionPayloadV3 {
+            payload_inner: ExecutionPayloadV2 { payload_inner: payload_v1(3), withdrawals: vec![] },
+            blob_gas_used: 0,
+            excess_blob_gas: 0,
+        };
+        let payloads = [
+            SyncPayload { payload: OpExecutionPayload::V1(v1), parent_beacon_block_root: None },
+            SyncPayload { payload: OpExecutionPayload::V2(v2), parent_beacon_block_root: None },
+            SyncPayload {
+                payload: OpExecutionPayload::V3(v3),
+                parent_beacon_block_root: Some(B256::repeat_byte(8)),
+            },
+        ];
+        for (number, payload) in (1..).zip(payloads) {
+            let decoded = round_trip(Ok(payload.clone())).unwrap();
+            assert_eq!(decoded.block_number(), number);
+            assert_eq!(decoded, payload);
+        }
+    }
+
+    #[test]
+    fn test_error_round_trip() {
+        for code in [ResultCode::NotFound, ResultCode::InvalidRequest, ResultCode::Unknown] {
+            assert_eq!(round_trip(Err(code)), Err(code));
+        }
+        assert_eq!(ResultCode::from(42), ResultCode::Unknown);
+    }
+}
@@ -0,0 +1,117 @@
+//! Rate Limiting
+
+use libp2p::PeerId;
+use std::{collections::HashMap, time::Instant};
+
+/// The number of peers tracked by a [PeerRateLimiter] before idle peers are pruned.
+const MAX_TRACKED_PEERS: usize = 1024;
+
+/// A token bucket allowing `rate` requests per second, in bursts of up to `burst`.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimiter {
+    /// The tokens refilled per second.
+    rate: f64,
+    /// The maximum number of tokens.
+    burst: f64,
+    /// The available tokens.
+    tokens: f64,
+    /// The last refill.
+    last: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a new [RateLimiter] with a full bucket.
+    pub fn new(rate: f64, burst: u32) -> Self {
+        Self { rate, burst: f64::from(burst), tokens: f64::from(burst), last: Instant::now() }
+    }
+
+    /// Takes a token if one is available.
+    pub fn try_acquire(&mut self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    /// Takes a token if one is available at `now`.
+    fn try_acquire_at(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    /// Refills the tokens accrued since the last refill.
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last = now;
+    }
+
+    /// Returns whether the bucket is full, so the limiter can be dropped.
+    fn is_idle(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.burst
+    }
+}
+
+/// A [RateLimiter] per peer.
+#[derive(Debug, Clone)]
+pub struct PeerRateLimiter {
+    /// The tokens refilled per second for each peer.
+    rate: f64,
+    /// The maximum number of tokens for each peer.
+    burst: u32,
+    /// The limiters of the peers.
+    peers: HashMap<PeerId, RateLimiter>,
+}
+
+impl PeerRateLimiter {
+    /// Creates a new [PeerRateLimiter].
+    pub fn new(rate: f64, burst: u32) -> Self {
+        Self { rate, burst, peers: HashMap::new() }
+    }
+
+    /// Takes a token of the peer if one is available.
+    pub fn try_acquire(&mut self, peer: PeerId) -> bool {
+        let now = Instant::now();
+        if self.peers.len() >= MAX_TRACKED_PEERS {
+            self.peers.retain(|_, limiter| !limiter.is_idle(now));
+        }
+        let (rate, burst) = (self.rate, self.burst);
+        self.peers.entry(peer).or_insert_with(|| RateLimiter::new(rate, burst)).try_acquire_at(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rate_limiter_refills() {
+        let mut limiter = RateLimiter::new(2.0, 2);
+        let now = limiter.last;
+        assert!(limiter.try_acquire_at(now));
+        assert!(limiter.try_acquire_at(now));
+        assert!(!limiter.try_acquire_at(now));
+
+        assert!(limiter.try_acquire_at(now + Duration::from_millis(500)));
+        assert!(!limiter.try_acquire_at(now + Duration::from_millis(500)));
+
+        // Refills are capped at the burst.
+        let later = now + Duration::from_secs(10);
+        assert!(limiter.is_idle(later));
+        assert!(limiter.try_acquire_at(later));
+        assert!(limiter.try_acquire_at(later));
+        assert!(!limiter.try_acquire_at(later));
+    }
+
+    #[test]
+    fn test_peer_rate_limiter() {
+        let mut limiter = PeerRateLimiter::new(1.0, 1);
+        let (a, b) = (PeerId::random(), PeerId::random());
+        assert!(limiter.try_acquire(a));
+        assert!(!limiter.try_acquire(a));
+        assert!(limiter.try_acquire(b));
+    }
+}
@@ -0,0 +1,8 @@
+//! Request-response sync of unsafe payloads by block number.
+//!
+//! Implements the `/opstack/req/payload_by_number/<chain>/0` protocol, used to fill
+//! the gaps left by missed gossip.
+
+pub mod codec;
+pub mod limiter;
+pub mod payloads;
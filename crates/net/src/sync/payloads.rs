@@ -0,0 +1,544 @@
+//! Payload Sync
+
+use alloy_primitives::B256;
+use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
+use libp2p::{
+    request_response::{self, OutboundRequestId, ResponseChannel},
+    PeerId,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    ops::Range,
+    time::Duration,
+};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, warn};
+
+use super::{
+    codec::{PayloadByNumberCodec, PayloadResponse, ResultCode, SyncPayload},
+    limiter::{PeerRateLimiter, RateLimiter},
+};
+
+/// The payload requests served per second across all peers.
+pub const GLOBAL_SERVER_RATE: f64 = 50.0;
+
+/// The burst of payload requests served across all peers.
+pub const GLOBAL_SERVER_BURST: u32 = 3;
+
+/// The payload requests served per second to each peer.
+pub const PEER_SERVER_RATE: f64 = 1.0;
+
+/// The burst of payload requests served to each peer.
+pub const PEER_SERVER_BURST: u32 = 2;
+
+/// The payload requests sent per second.
+pub const CLIENT_RATE: f64 = 10.0;
+
+/// The burst of payload requests sent.
+pub const CLIENT_BURST: u32 = 10;
+
+/// The maximum number of payload requests in flight.
+pub const MAX_IN_FLIGHT: usize = 16;
+
+/// The maximum number of queued payload requests.
+pub const MAX_QUEUED: usize = 1024;
+
+/// The number of times a payload is requested before giving up.
+pub const MAX_ATTEMPTS: u32 = 3;
+
+/// How long the application has to look up a requested payload.
+pub const LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long peers serving invalid payloads are banned.
+pub const INVALID_PAYLOAD_BAN: Duration = Duration::from_secs(60 * 60);
+
+/// The capacity of the lookup and payload channels.
+const CHANNEL_CAPACITY: usize = 64;
+
+/// A payload requested by a peer, to be looked up by the application.
+#[derive(Debug)]
+pub struct PayloadLookup {
+    /// The requested block number.
+    pub number: u64,
+    /// Receives the payload, or `None` if it is unknown.
+    pub response: oneshot::Sender<Option<SyncPayload>>,
+}
+
+/// A range of payloads to fetch, anchored to the known block following the range.
+///
+/// Fetched payloads are only forwarded once they link to the anchor through their parent
+/// hashes, so that peers cannot serve blocks of another chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadRequest {
+    /// The block numbers to fetch.
+    pub range: Range<u64>,
+    /// The hash of the last block of the range, the parent hash of the known block
+    /// following it.
+    pub end_hash: B256,
+}
+
+/// A pending lookup, resolving to the response channel, the requested number and the
+/// payload found.
+type Lookup = BoxFuture<'static, (ResponseChannel<PayloadResponse>, u64, Option<SyncPayload>)>;
+
+/// The client and server state of the payload by number protocol.
+///
+/// Requested ranges are queued and sent to the connected peers in turn, retrying failed
+/// requests with the next peer. Fetched payloads are verified against their block hash
+/// and forwarded from the end of the range down, as they link to the known block following
+/// it. Inbound requests are rate limited, and served by the application through the
+/// [PayloadLookup] channel.
+pub struct PayloadSync {
+    /// The queued block numbers and the attempts made to fetch them.
+    queue: BTreeMap<u64, u32>,
+    /// The requests in flight, with their block number and attempt.
+    in_flight: HashMap<OutboundRequestId, (u64, u32)>,
+    /// The hashes pending payloads must have, known from the block following them.
+    expected: BTreeMap<u64, B256>,
+    /// The fetched payloads waiting for the block following them to be verified, with
+    /// the peer that served them and the attempts made to fetch them.
+    unverified: BTreeMap<u64, (PeerId, u32, SyncPayload)>,
+    /// The peers that served invalid payloads, until taken to be banned.
+    misbehaving: Vec<PeerId>,
+    /// The index of the next peer to request from.
+    next_peer: usize,
+    /// Limits the requests sent.
+    client_limiter: RateLimiter,
+    /// Limits the requests served across all peers.
+    global_limiter: RateLimiter,
+    /// Limits the requests served to each peer.
+    peer_limiter: PeerRateLimiter,
+    /// The lookups awaiting the application.
+    lookups: FuturesUnordered<Lookup>,
+    /// Sends lookups to the application.
+    lookup_sender: mpsc::Sender<PayloadLookup>,
+    /// Receives lookups, until taken by the application.
+    lookup_recv: Option<mpsc::Receiver<PayloadLookup>>,
+    /// Sends fetched payloads to the application.
+    payload_sender: mpsc::Sender<SyncPayload>,
+    /// Receives fetched payloads, until taken by the application.
+    payload_recv: Option<mpsc::Receiver<SyncPayload>>,
+}
+
+impl fmt::Debug for PayloadSync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PayloadSync")
+            .field("queued", &self.queue.len())
+            .field("in_flight", &self.in_flight.len())
+            .field("unverified", &self.unverified.len())
+            .field("lookups", &self.lookups.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for PayloadSync {
+    fn default() -> Self {
+        let (lookup_sender, lookup_recv) = mpsc::channel(CHANNEL_CAPACITY);
+        let (payload_sender, payload_recv) = mpsc::channel(CHANNEL_CAPACITY);
+        Self {
+            queue: BTreeMap::new(),
+            in_flight: HashMap::new(),
+            expected: BTreeMap::new(),
+            unverified: BTreeMap::new(),
+            misbehaving: Vec::new(),
+            next_peer: 0,
+            client_limiter: RateLimiter::new(CLIENT_RATE, CLIENT_BURST),
+            global_limiter: RateLimiter::new(GLOBAL_SERVER_RATE, GLOBAL_SERVER_BURST),
+            peer_limiter: PeerRateLimiter::new(PEER_SERVER_RATE, PEER_SERVER_BURST),
+            lookups: FuturesUnordered::new(),
+            lookup_sender,
+            lookup_recv: Some(lookup_recv),
+            payload_sender,
+            payload_recv: Some(payload_recv),
+        }
+    }
+}
+
+impl PayloadSync {
+    /// Takes the receiver of the payloads fetched from peers.
+    pub fn take_payload_recv(&mut self) -> Option<mpsc::Receiver<SyncPayload>> {
+        self.payload_recv.take()
+    }
+
+    /// Takes the receiver of the payloads requested by peers.
+    ///
+    /// Until taken, every request is answered with [ResultCode::NotFound].
+    pub fn take_lookup_recv(&mut self) -> Option<mpsc::Receiver<PayloadLookup>> {
+        self.lookup_recv.take()
+    }
+
+    /// Returns the number of payloads queued or in flight.
+    pub fn pending(&self) -> usize {
+        self.queue.len() + self.in_flight.len()
+    }
+
+    /// Takes the peers that served invalid payloads since the last call, to be banned.
+    pub fn take_misbehaving(&mut self) -> Vec<PeerId> {
+        std::mem::take(&mut self.misbehaving)
+    }
+
+    /// Queues the block numbers of the requested range, skipping those already pending.
+    ///
+    /// Blocks are queued from the end of the range, since payloads are verified from the
+    /// known block following it.
+    pub fn enqueue(&mut self, request: PayloadRequest) {
+        let PayloadRequest { range, end_hash } = request;
+        let Some(last) = range.end.checked_sub(1).filter(|last| *last >= range.start) else {
+            return;
+        };
+        self.expected.insert(last, end_hash);
+        for number in range.rev() {
+            if self.queue.len() >= MAX_QUEUED {
+                warn!("Payload request queue full, dropping requests up to block {}", number);
+                return;
+            }
+            if !self.in_flight.values().any(|(n, _)| *n == number) {
+                self.queue.entry(number).or_insert(0);
+            }
+        }
+    }
+
+    /// Sends queued requests to the given peers in turn, within the client rate limit.
+    pub fn send_requests(
+        &mut self,
+        behaviour: &mut request_response::Behaviour<PayloadByNumberCodec>,
+        peers: &[PeerId],
+    ) {
+        while !peers.is_empty()
+            && !self.queue.is_empty()
+            && self.in_flight.len() < MAX_IN_FLIGHT
+            && self.client_limiter.try_acquire()
+        {
+            let Some((number, attempts)) = self.queue.pop_first() else { return };
+            let peer = peers[self.next_peer % peers.len()];
+            self.next_peer = self.next_peer.wrapping_add(1);
+            debug!("Requesting payload {} from peer {}", number, peer);
+            let id = behaviour.send_request(&peer, number);
+            self.in_flight.insert(id, (number, attempts + 1));
+        }
+    }
+
+    /// Handles an event of the payload by number protocol.
+    pub fn handle_event(
+        &mut self,
+        behaviour: &mut request_response::Behaviour<PayloadByNumberCodec>,
+        event: request_response::Event<u64, PayloadResponse>,
+    ) {
+        match event {
+            request_response::Event::Message { peer, message, .. } => match message {
+                request_response::Message::Request { request, channel, .. } => {
+                    self.serve(behaviour, peer, request, channel)
+                }
+                request_response::Message::Response { request_id, response, .. } => {
+                    self.handle_response(peer, request_id, response)
+                }
+            },
+            request_response::Event::OutboundFailure { peer, request_id, error, .. } => {
+                debug!("Payload request to peer {} failed: {}", peer, error);
+                if let Some((number, attempts)) = self.in_flight.remove(&request_id) {
+                    self.retry(number, attempts);
+                }
+            }
+            request_response::Event::InboundFailure { peer, error, .. } => {
+                debug!("Serving payload to peer {} failed: {}", peer, error);
+            }
+            request_response::Event::ResponseSent { .. } => {}
+        }
+    }
+
+    /// Resolves the next lookup answered by the application.
+    ///
+    /// Pends forever if there are no lookups, so it can be raced against other events.
+    pub async fn next_lookup(
+        &mut self,
+    ) -> (ResponseChannel<PayloadResponse>, u64, Option<SyncPayload>) {
+        match self.lookups.next().await {
+            Some(lookup) => lookup,
+            None => futures::future::pending().await,
+        }
+    }
+
+    /// Responds to a peer with the payload found by the application.
+    pub fn respond(
+        &mut self,
+        behaviour: &mut request_response::Behaviour<PayloadByNumberCodec>,
+        channel: ResponseChannel<PayloadResponse>,
+        number: u64,
+        payload: Option<SyncPayload>,
+    ) {
+        let response = match payload {
+            Some(payload) if payload.block_number() == number => Ok(payload),
+            Some(payload) => {
+                warn!("Lookup of payload {} returned payload {}", number, payload.block_number());
+                Err(ResultCode::Unknown)
+            }
+            None => Err(ResultCode::NotFound),
+        };
+        if behaviour.send_response(channel, response).is_err() {
+            debug!("Peer closed the request for payload {}", number);
+        }
+    }
+
+    /// Serves a payload request, within the server rate limits.
+    fn serve(
+        &mut self,
+        behaviour: &mut request_response::Behaviour<PayloadByNumberCodec>,
+        peer: PeerId,
+        number: u64,
+        channel: ResponseChannel<PayloadResponse>,
+    ) {
+        if !self.peer_limiter.try_acquire(peer) || !self.global_limiter.try_acquire() {
+            // Dropping the channel resets the stream without a response.
+            warn!("Rate limiting payload request from peer {}", peer);
+            return;
+        }
+        if self.lookup_recv.is_some() {
+            _ = behaviour.send_response(channel, Err(ResultCode::NotFound));
+            return;
+        }
+
+        let (response, lookup) = oneshot::channel();
+        if self.lookup_sender.try_send(PayloadLookup { number, response }).is_err() {
+            warn!("Payload lookups are backed up, failing request from peer {}", peer);
+            _ = behaviour.send_response(channel, Err(ResultCode::Unknown));
+            return;
+        }
+        self.lookups.push(
+            async move {
+                let payload = tokio::time::timeout(LOOKUP_TIMEOUT, lookup)
+                    .await
+                    .ok()
+                    .and_then(Result::ok)
+                    .flatten();
+                (channel, number, payload)
+            }
+            .boxed(),
+        );
+    }
+
+    /// Verifies a fetched payload and forwards it to the application once it links to the
+    /// requested range, retrying failed requests.
+    fn handle_response(
+        &mut self,
+        peer: PeerId,
+        request_id: OutboundRequestId,
+        response: PayloadResponse,
+    ) {
+        let Some((number, attempts)) = self.in_flight.remove(&request_id) else { return };
+        self.handle_payload(peer, number, attempts, response);
+    }
+
+    /// Handles the response of a peer to the request of a payload.
+    fn handle_payload(
+        &mut self,
+        peer: PeerId,
+        number: u64,
+        attempts: u32,
+        response: PayloadResponse,
+    ) {
+        match response {
+            Ok(payload) if payload.block_number() != number => {
+                warn!(
+                    "Peer {} returned payload {} for request {}",
+                    peer,
+                    payload.block_number(),
+                    number
+                );
+                self.reject(peer, number, attempts);
+            }
+            Ok(payload) if payload.compute_block_hash() != Some(payload.block_hash()) => {
+                warn!("Peer {} returned payload {} with an invalid block hash", peer, number);
+                self.reject(peer, number, attempts);
+            }
+            Ok(payload) => {
+                debug!("Received payload {} from peer {}", number, peer);
+                self.unverified.insert(number, (peer, attempts, payload));
+                if self.unverified.len() > MAX_QUEUED {
+                    self.unverified.pop_first();
+                }
+                self.verify(number);
+            }
+            Err(code) => {
+                debug!("Peer {} failed request for payload {}: {:?}", peer, number, code);
+                self.retry(number, attempts);
+            }
+        }
+    }
+
+    /// Forwards the fetched payloads matching their expected hash, walking down the parent
+    /// hashes from the given block number.
+    fn verify(&mut self, mut number: u64) {
+        while let Some(expected) = self.expected.get(&number).copied() {
+            let Some((peer, attempts, payload)) = self.unverified.remove(&number) else { return };
+            if payload.block_hash() != expected {
+                warn!("Peer {} returned payload {} of another chain", peer, number);
+                self.reject(peer, number, attempts);
+                return;
+            }
+            self.expected.remove(&number);
+            let parent_hash = payload.parent_hash();
+            if self.payload_sender.try_send(payload).is_err() {
+                warn!("Fetched payloads are backed up, dropping payload {}", number);
+            }
+
+            let Some(parent) = number.checked_sub(1).filter(|parent| self.is_pending(*parent))
+            else {
+                return;
+            };
+            self.expected.insert(parent, parent_hash);
+            number = parent;
+        }
+    }
+
+    /// Returns whether the block number is queued, in flight or awaiting verification.
+    fn is_pending(&self, number: u64) -> bool {
+        self.queue.contains_key(&number)
+            || self.unverified.contains_key(&number)
+            || self.in_flight.values().any(|(n, _)| *n == number)
+    }
+
+    /// Records the peer as misbehaving and requests the payload again.
+    fn reject(&mut self, peer: PeerId, number: u64, attempts: u32) {
+        self.misbehaving.push(peer);
+        self.retry(number, attempts);
+    }
+
+    /// Queues a failed request again, unless it ran out of attempts.
+    fn retry(&mut self, number: u64, attempts: u32) {
+        if attempts >= MAX_ATTEMPTS {
+            warn!("Giving up on payload {} after {} attempts", number, attempts);
+            self.expected.remove(&number);
+            return;
+        }
+        self.queue.entry(number).or_insert(attempts);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, Bloom, Bytes, U256};
+    use alloy_rpc_types_engine::ExecutionPayloadV1;
+    use op_alloy_rpc_types_engine::OpExecutionPayload;
+
+    fn request(range: Range<u64>) -> PayloadRequest {
+        PayloadRequest { range, end_hash: B256::ZERO }
+    }
+
+    /// Returns a payload with a valid block hash.
+    fn payload(number: u64, parent_hash: B256) -> SyncPayload {
+        let payload = ExecutionPayloadV1 {
+            parent_hash,
+            fee_recipient: Address::ZERO,
+            state_root: B256::ZERO,
+            receipts_root: B256::ZERO,
+            logs_bloom: Bloom::default(),
+            prev_randao: B256::ZERO,
+            block_number: number,
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            timestamp: number * 2,
+            extra_data: Bytes::new(),
+            base_fee_per_gas: U256::from(7),
+            block_hash: B256::ZERO,
+            transactions: Vec::new(),
+        };
+        let mut payload = SyncPayload {
+            payload: OpExecutionPayload::V1(payload),
+            parent_beacon_block_root: None,
+        };
+        let hash = payload.compute_block_hash().unwrap();
+        if let OpExecutionPayload::V1(payload) = &mut payload.payload {
+            payload.block_hash = hash;
+        }
+        payload
+    }
+
+    /// Returns a chain of payloads with the given block numbers.
+    fn chain(numbers: Range<u64>) -> Vec<SyncPayload> {
+        let mut parent_hash = B256::ZERO;
+        numbers
+            .map(|number| {
+                let payload = payload(number, parent_hash);
+                parent_hash = payload.block_hash();
+                payload
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_payloads_forwarded_once_linked() {
+        let mut sync = PayloadSync::default();
+        let mut payloads = sync.take_payload_recv().unwrap();
+        let chain = chain(1..4);
+        sync.enqueue(PayloadRequest { range: 1..4, end_hash: chain[2].block_hash() });
+        let peer = PeerId::random();
+
+        // The first block waits for the blocks following it.
+        sync.handle_payload(peer, 1, 1, Ok(chain[0].clone()));
+        assert!(payloads.try_recv().is_err());
+
+        sync.handle_payload(peer, 3, 1, Ok(chain[2].clone()));
+        sync.handle_payload(peer, 2, 1, Ok(chain[1].clone()));
+        for expected in chain.iter().rev() {
+            assert_eq!(payloads.try_recv().unwrap(), *expected);
+        }
+        assert!(sync.take_misbehaving().is_empty());
+        assert!(sync.expected.is_empty());
+        assert!(sync.unverified.is_empty());
+    }
+
+    #[test]
+    fn test_mismatching_payloads_rejected() {
+        let mut sync = PayloadSync::default();
+        let mut payloads = sync.take_payload_recv().unwrap();
+        let chain = chain(1..3);
+        sync.enqueue(PayloadRequest { range: 1..3, end_hash: chain[1].block_hash() });
+        sync.queue.clear();
+        let (honest, forger, other_chain) = (PeerId::random(), PeerId::random(), PeerId::random());
+
+        // The right block number, with a block hash not matching its contents.
+        let mut forged = chain[1].clone();
+        if let OpExecutionPayload::V1(payload) = &mut forged.payload {
+            payload.block_hash = B256::repeat_byte(9);
+        }
+        sync.handle_payload(forger, 2, 1, Ok(forged));
+
+        // A valid block of another chain.
+        sync.handle_payload(other_chain, 2, 2, Ok(payload(2, B256::repeat_byte(9))));
+        assert!(payloads.try_recv().is_err());
+        assert_eq!(sync.take_misbehaving(), [forger, other_chain]);
+        assert_eq!(sync.queue.get(&2), Some(&1));
+
+        sync.handle_payload(honest, 2, 3, Ok(chain[1].clone()));
+        assert_eq!(payloads.try_recv().unwrap(), chain[1]);
+        assert!(sync.take_misbehaving().is_empty());
+    }
+
+    #[test]
+    fn test_enqueue_and_retry() {
+        let mut sync = PayloadSync::default();
+        sync.enqueue(request(10..13));
+        sync.enqueue(request(11..14));
+        assert_eq!(sync.queue.keys().copied().collect::<Vec<_>>(), [10, 11, 12, 13]);
+
+        sync.queue.clear();
+        sync.retry(10, 1);
+        sync.retry(11, MAX_ATTEMPTS);
+        assert_eq!(sync.queue.get(&10), Some(&1));
+        assert!(!sync.queue.contains_key(&11));
+
+        sync.enqueue(request(0..(MAX_QUEUED as u64 * 2)));
+        assert_eq!(sync.pending(), MAX_QUEUED);
+    }
+
+    #[test]
+    fn test_take_channels() {
+        let mut sync = PayloadSync::default();
+        assert!(sync.take_payload_recv().is_some());
+        assert!(sync.take_payload_recv().is_none());
+        assert!(sync.take_lookup_recv().is_some());
+        assert!(sync.take_lookup_recv().is_none());
+    }
+}
@@ -2,16 +2,96 @@
 
 #[cfg(any(test, feature = "arbitrary"))]
 use arbitrary::{Arbitrary, Unstructured};
-use discv5::enr::{CombinedKey, Enr};
+use discv5::{
+    enr::{CombinedKey, Enr},
+    ListenConfig,
+};
 use eyre::Result;
 use libp2p::{multiaddr::Protocol, Multiaddr};
-use std::net::{IpAddr, SocketAddr};
+use std::net::{IpAddr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+/// The IP address families the local node can reach peers over.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "arbitrary"), derive(arbitrary::Arbitrary))]
+pub enum Reachable {
+    /// Only IPv4 peers are reachable.
+    Ipv4,
+    /// Only IPv6 peers are reachable.
+    Ipv6,
+    /// Both IPv4 and IPv6 peers are reachable, preferring IPv4.
+    #[default]
+    DualStack,
+}
+
+impl From<&ListenConfig> for Reachable {
+    /// Derives the reachable families from the families discovery listens on.
+    fn from(config: &ListenConfig) -> Self {
+        match config {
+            ListenConfig::Ipv4 { .. } => Self::Ipv4,
+            ListenConfig::Ipv6 { .. } => Self::Ipv6,
+            ListenConfig::DualStack { .. } => Self::DualStack,
+        }
+    }
+}
 
 /// A wrapper around a peer's [SocketAddr].
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Peer {
-    /// The peer's [SocketAddr].
+    /// The peer's [SocketAddr] to dial, of the preferred reachable family.
     pub socket: SocketAddr,
+    /// The peer's IPv4 [SocketAddr], if known.
+    pub ipv4: Option<SocketAddrV4>,
+    /// The peer's IPv6 [SocketAddr], if known.
+    pub ipv6: Option<SocketAddrV6>,
+}
+
+impl Peer {
+    /// Creates a [Peer] from its addresses, dialing the address of the reachable family.
+    ///
+    /// Unspecified addresses, e.g. `0.0.0.0`, are carried but never dialed.
+    pub fn new(
+        ipv4: Option<SocketAddrV4>,
+        ipv6: Option<SocketAddrV6>,
+        reachable: Reachable,
+    ) -> Result<Self> {
+        let v4 = ipv4.filter(|socket| !socket.ip().is_unspecified()).map(SocketAddr::V4);
+        let v6 = ipv6.filter(|socket| !socket.ip().is_unspecified()).map(SocketAddr::V6);
+        let socket = match reachable {
+            Reachable::Ipv4 => v4,
+            Reachable::Ipv6 => v6,
+            Reachable::DualStack => v4.or(v6),
+        }
+        .ok_or_else(|| eyre::eyre!("no reachable address"))?;
+        Ok(Self { socket, ipv4, ipv6 })
+    }
+
+    /// Converts an [Enr] to a Peer, reading both its IPv4 and IPv6 TCP addresses and
+    /// dialing the address of the reachable family.
+    ///
+    /// As specified by EIP-778, the IPv6 address uses the `tcp` port if `tcp6` is omitted.
+    pub fn from_enr(enr: &Enr<CombinedKey>, reachable: Reachable) -> Result<Self> {
+        let (ip4, ip6) = (enr.ip4(), enr.ip6());
+        if ip4.is_none() && ip6.is_none() {
+            eyre::bail!("missing ip");
+        }
+        let ipv4 = ip4.zip(enr.tcp4()).map(|(ip, port)| SocketAddrV4::new(ip, port));
+        let ipv6 = ip6
+            .zip(enr.tcp6().or_else(|| enr.tcp4()))
+            .map(|(ip, port)| SocketAddrV6::new(ip, port, 0, 0));
+        if ipv4.is_none() && ipv6.is_none() {
+            eyre::bail!("missing port");
+        }
+        Self::new(ipv4, ipv6, reachable)
+    }
+}
+
+impl From<SocketAddr> for Peer {
+    fn from(socket: SocketAddr) -> Self {
+        match socket {
+            SocketAddr::V4(v4) => Self { socket, ipv4: Some(v4), ipv6: None },
+            SocketAddr::V6(v6) => Self { socket, ipv4: None, ipv6: Some(v6) },
+        }
+    }
 }
 
 #[cfg(any(test, feature = "arbitrary"))]
@@ -21,12 +101,12 @@ impl<'a> Arbitrary<'a> for Peer {
             true => {
                 let ipv6 = u.arbitrary::<[u8; 16]>()?;
                 let port = u.arbitrary::<u16>()?;
-                Ok(Peer { socket: SocketAddr::new(IpAddr::V6(ipv6.into()), port) })
+                Ok(Peer::from(SocketAddr::new(IpAddr::V6(ipv6.into()), port)))
             }
             false => {
                 let ipv4 = u.arbitrary::<u8>()?;
                 let port = u.arbitrary::<u16>()?;
-                Ok(Peer { socket: SocketAddr::new(IpAddr::V4([ipv4; 4].into()), port) })
+                Ok(Peer::from(SocketAddr::new(IpAddr::V4([ipv4; 4].into()), port)))
             }
         }
     }
@@ -35,12 +115,9 @@ impl<'a> Arbitrary<'a> for Peer {
 impl TryFrom<&Enr<CombinedKey>> for Peer {
     type Error = eyre::Report;
 
-    /// Converts an [Enr] to a Peer
+    /// Converts an [Enr] to a Peer, preferring its IPv4 address on dual stack hosts.
     fn try_from(value: &Enr<CombinedKey>) -> Result<Self> {
-        let ip = value.ip4().ok_or(eyre::eyre!("missing ip"))?;
-        let port = value.tcp4().ok_or(eyre::eyre!("missing port"))?;
-        let socket = SocketAddr::new(IpAddr::V4(ip), port);
-        Ok(Peer { socket })
+        Self::from_enr(value, Reachable::DualStack)
     }
 }
 
@@ -80,7 +157,7 @@ impl TryFrom<&Multiaddr> for Peer {
         }
         let ip = ip.ok_or(eyre::eyre!("missing ip"))?;
         let port = port.ok_or(eyre::eyre!("missing port"))?;
-        Ok(Peer { socket: SocketAddr::new(ip, port) })
+        Ok(Peer::from(SocketAddr::new(ip, port)))
     }
 }
 
@@ -125,5 +202,103 @@ mod tests {
         let enr = Enr::<CombinedKey>::builder().ip4(ip).tcp4(port).build(&key).unwrap();
         let peer = Peer::try_from(&enr).unwrap();
         assert_eq!(peer.socket, SocketAddr::new(IpAddr::V4(ip), port));
+        assert_eq!(peer.ipv6, None);
+    }
+
+    #[test]
+    fn test_peer_from_dual_stack_enr() {
+        let key = CombinedKey::generate_secp256k1();
+        let ip4 = std::net::Ipv4Addr::new(192, 168, 0, 1);
+        let ip6 = std::net::Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let enr = Enr::<CombinedKey>::builder()
+            .ip4(ip4)
+            .tcp4(9222)
+            .ip6(ip6)
+            .tcp6(9333)
+            .build(&key)
+            .unwrap();
+
+        let peer = Peer::try_from(&enr).unwrap();
+        assert_eq!(peer.socket, SocketAddr::new(IpAddr::V4(ip4), 9222));
+        assert_eq!(peer.ipv4, Some(SocketAddrV4::new(ip4, 9222)));
+        assert_eq!(peer.ipv6, Some(SocketAddrV6::new(ip6, 9333, 0, 0)));
+
+        let peer = Peer::from_enr(&enr, Reachable::Ipv6).unwrap();
+        assert_eq!(peer.socket, SocketAddr::new(IpAddr::V6(ip6), 9333));
+
+        let enr = Enr::<CombinedKey>::builder().ip6(ip6).tcp6(9333).build(&key).unwrap();
+        assert_eq!(Peer::try_from(&enr).unwrap().socket, SocketAddr::new(IpAddr::V6(ip6), 9333));
+        let err = Peer::from_enr(&enr, Reachable::Ipv4).unwrap_err();
+        assert_eq!(err.to_string(), "no reachable address");
+
+        // Without a `tcp6` port, the IPv6 address uses the `tcp` port.
+        let enr = Enr::<CombinedKey>::builder().ip6(ip6).tcp4(9222).build(&key).unwrap();
+        assert_eq!(Peer::try_from(&enr).unwrap().socket, SocketAddr::new(IpAddr::V6(ip6), 9222));
+    }
+
+    #[test]
+    fn test_peer_from_arbitrary_enr() {
+        arbtest::arbtest(|u| {
+            let ip4 = u.arbitrary::<Option<[u8; 4]>>()?.map(std::net::Ipv4Addr::from);
+            let tcp4 = u.arbitrary::<Option<u16>>()?;
+            let ip6 = u.arbitrary::<Option<[u8; 16]>>()?.map(std::net::Ipv6Addr::from);
+            let tcp6 = u.arbitrary::<Option<u16>>()?;
+            let reachable = u.arbitrary::<Reachable>()?;
+
+            let key = CombinedKey::generate_secp256k1();
+            let mut builder = Enr::<CombinedKey>::builder();
+            if let Some(ip) = ip4 {
+                builder.ip4(ip);
+            }
+            if let Some(port) = tcp4 {
+                builder.tcp4(port);
+            }
+            if let Some(ip) = ip6 {
+                builder.ip6(ip);
+            }
+            if let Some(port) = tcp6 {
+                builder.tcp6(port);
+            }
+            let enr = builder.build(&key).unwrap();
+
+            let port6 = tcp6.or(tcp4);
+            match Peer::from_enr(&enr, reachable) {
+                Ok(peer) => {
+                    // The dialed address is one of the carried addresses, never unspecified.
+                    let carried = [peer.ipv4.map(SocketAddr::V4), peer.ipv6.map(SocketAddr::V6)];
+                    assert!(carried.contains(&Some(peer.socket)));
+                    assert!(!peer.socket.ip().is_unspecified());
+
+                    // Its family is reachable, preferring IPv4 on dual stack hosts.
+                    let dialable4 = peer.ipv4.is_some_and(|socket| !socket.ip().is_unspecified());
+                    match reachable {
+                        Reachable::Ipv4 => assert!(peer.socket.is_ipv4()),
+                        Reachable::Ipv6 => assert!(peer.socket.is_ipv6()),
+                        Reachable::DualStack => assert_eq!(peer.socket.is_ipv4(), dialable4),
+                    }
+
+                    // The carried addresses are those of the ENR.
+                    assert_eq!(
+                        peer.ipv4.map(|socket| (*socket.ip(), socket.port())),
+                        ip4.zip(tcp4)
+                    );
+                    assert_eq!(
+                        peer.ipv6.map(|socket| (*socket.ip(), socket.port())),
+                        ip6.zip(port6)
+                    );
+                }
+                Err(_) => {
+                    // No address of a reachable family can be dialed.
+                    let dialable4 = ip4.is_some_and(|ip| !ip.is_unspecified()) && tcp4.is_some();
+                    let dialable6 = ip6.is_some_and(|ip| !ip.is_unspecified()) && port6.is_some();
+                    match reachable {
+                        Reachable::Ipv4 => assert!(!dialable4),
+                        Reachable::Ipv6 => assert!(!dialable6),
+                        Reachable::DualStack => assert!(!dialable4 && !dialable6),
+                    }
+                }
+            }
+            Ok(())
+        });
     }
 }
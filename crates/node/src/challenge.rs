@@ -0,0 +1,375 @@
+//! Verification of on-chain output proposals against the locally derived chain.
+
+use alloy_eips::BlockId;
+use alloy_primitives::{address, keccak256, Address, Bytes, B256, U256};
+use alloy_provider::{network::primitives::BlockTransactionsKind, Provider, ReqwestProvider};
+use alloy_rpc_types_eth::{TransactionInput, TransactionRequest};
+use alloy_sol_types::SolCall;
+use hilo_driver::DriverEvent;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{Config, NodeError};
+
+/// The interval at which new proposals are fetched from L1.
+pub const PROPOSAL_POLL_INTERVAL: Duration = Duration::from_secs(12);
+
+/// The number of challenge events to buffer for each subscriber.
+pub const CHALLENGE_EVENT_CHANNEL_SIZE: usize = 64;
+
+/// The address of the `L2ToL1MessagePasser` predeploy, whose storage root is
+/// committed to in the output root.
+pub(crate) const L2_TO_L1_MESSAGE_PASSER: Address =
+    address!("4200000000000000000000000000000000000016");
+
+/// The metric counting proposals that matched the locally derived output root.
+const VERIFIED_METRIC: &str = "hilo_challenge_proposals_verified_total";
+
+/// The metric counting proposals that disagreed with the locally derived output root.
+const MISMATCH_METRIC: &str = "hilo_challenge_proposals_mismatched_total";
+
+#[allow(missing_docs, unreachable_pub)]
+mod bindings {
+    alloy_sol_types::sol! {
+        struct OutputProposal {
+            bytes32 outputRoot;
+            uint128 timestamp;
+            uint128 l2BlockNumber;
+        }
+
+        function nextOutputIndex() external view returns (uint256);
+        function getL2Output(uint256 _l2OutputIndex) external view returns (OutputProposal memory);
+
+        function gameCount() external view returns (uint256 gameCount_);
+        function gameAtIndex(uint256 _index) external view returns (uint32 gameType_, uint64 timestamp_, address proxy_);
+
+        function rootClaim() external pure returns (bytes32 rootClaim_);
+        function l2BlockNumber() external pure returns (uint256 l2BlockNumber_);
+    }
+}
+
+/// The L1 contract output proposals are read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProposalSource {
+    /// The legacy `L2OutputOracle` contract.
+    OutputOracle(Address),
+    /// The `DisputeGameFactory` contract of fault-proof enabled chains.
+    DisputeGameFactory(Address),
+}
+
+/// An output proposal read from L1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Proposal {
+    /// The index of the proposal in the oracle, or of the game in the factory.
+    pub index: u64,
+    /// The L2 block number the output root was proposed for.
+    pub l2_block_number: u64,
+    /// The proposed output root.
+    pub output_root: B256,
+    /// The dispute game proxy, if the proposal was made through the factory.
+    pub game: Option<Address>,
+}
+
+/// An event emitted by the [ChallengeVerifier].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChallengeEvent {
+    /// The proposal matches the locally derived output root.
+    ProposalVerified(Proposal),
+    /// The proposal disagrees with the locally derived output root.
+    ProposalMismatch {
+        /// The offending proposal.
+        proposal: Proposal,
+        /// The output root derived locally for the proposal's L2 block.
+        local_output_root: B256,
+    },
+}
+
+/// Computes a version 0 output root.
+///
+/// The output root commits to the L2 state root, the storage root of the
+/// `L2ToL1MessagePasser` and the L2 block hash.
+pub fn output_root_v0(state_root: B256, message_passer_root: B256, block_hash: B256) -> B256 {
+    let mut preimage = [0u8; 128];
+    preimage[32..64].copy_from_slice(state_root.as_slice());
+    preimage[64..96].copy_from_slice(message_passer_root.as_slice());
+    preimage[96..128].copy_from_slice(block_hash.as_slice());
+    keccak256(preimage)
+}
+
+/// Verifies output proposals made on L1 against the chain derived by the node.
+///
+/// New proposals are polled from the [ProposalSource] every [PROPOSAL_POLL_INTERVAL]
+/// (shorter in devnet mode)
+/// and kept pending until the derived safe head reaches their L2 block. The output
+/// root of that block is then computed from the local execution client and compared
+/// with the proposed root.
+#[derive(Debug)]
+pub struct ChallengeVerifier {
+    /// The L1 RPC the proposals are read from.
+    l1: ReqwestProvider,
+    /// The RPC of the local execution client.
+    l2: ReqwestProvider,
+    /// The contract proposals are read from.
+    source: ProposalSource,
+    /// The index of the next proposal to fetch. Set on the first poll if unset.
+    next_index: Option<u64>,
+    /// Fetched proposals that have not been verified yet, keyed by L2 block number.
+    pending: BTreeMap<u64, Vec<Proposal>>,
+    /// The latest derived L2 safe head.
+    safe_head: u64,
+    /// The channel challenge events are published on.
+    events: broadcast::Sender<ChallengeEvent>,
+    /// The interval at which new proposals are fetched.
+    poll_interval: Duration,
+}
+
+impl ChallengeVerifier {
+    /// Creates a new [ChallengeVerifier] from the node [Config].
+    pub fn new(
+        config: &Config,
+        events: broadcast::Sender<ChallengeEvent>,
+    ) -> Result<Self, NodeError> {
+        let source = config.proposal_source().ok_or(NodeError::MissingProposalSource)?;
+        Ok(Self {
+            l1: ReqwestProvider::new_http(config.l1_rpc_url.clone()),
+            l2: ReqwestProvider::new_http(config.l2_rpc_url.clone()),
+            source,
+            next_index: None,
+            pending: BTreeMap::new(),
+            safe_head: 0,
+            events,
+            poll_interval: config.poll_interval(PROPOSAL_POLL_INTERVAL),
+        })
+    }
+
+    /// Sets the index of the first proposal to verify.
+    ///
+    /// By default only proposals made after the verifier started are verified.
+    pub const fn with_start_index(mut self, index: u64) -> Self {
+        self.next_index = Some(index);
+        self
+    }
+
+    /// Runs the verifier, tracking the safe head through the given driver events.
+    ///
+    /// Returns once the driver event channel is closed.
+    pub async fn run(
+        mut self,
+        mut driver_events: broadcast::Receiver<DriverEvent>,
+    ) -> Result<(), NodeError> {
+        info!("Verifying output proposals from {:?}", self.source);
+        let mut poll = tokio::time::interval(self.poll_interval);
+        loop {
+            tokio::select! {
+                _ = poll.tick() => {
+                    if let Err(e) = self.poll_proposals().await {
+                        warn!("Failed to fetch output proposals: {}", e);
+                    }
+                }
+                event = driver_events.recv() => match event {
+                    Ok(DriverEvent::SafeHeadUpdated(head)) => {
+                        self.safe_head = head.block_info.number;
+                    }
+                    Ok(DriverEvent::PipelineReset { l2_safe_head, .. }) => {
+                        self.safe_head = l2_safe_head.block_info.number;
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!("Challenge verifier skipped {} driver events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+            }
+            self.verify_pending().await;
+        }
+    }
+
+    /// Fetches all proposals made since the last poll.
+    async fn poll_proposals(&mut self) -> Result<(), NodeError> {
+        let count = self.proposal_count().await?;
+        let start = *self.next_index.get_or_insert(count);
+        for index in start..count {
+            let proposal = self.proposal(index).await?;
+            debug!(
+                "Fetched output proposal #{} for L2 block {}: {}",
+                proposal.index, proposal.l2_block_number, proposal.output_root
+            );
+            self.pending.entry(proposal.l2_block_number).or_default().push(proposal);
+            self.next_index = Some(index + 1);
+        }
+        Ok(())
+    }
+
+    /// Verifies all pending proposals at or below the safe head.
+    ///
+    /// Proposals whose output root cannot be computed yet are kept pending.
+    async fn verify_pending(&mut self) {
+        while let Some(number) = self.pending.keys().next().copied() {
+            if number > self.safe_head {
+                return;
+            }
+            let local_output_root = match self.output_root_at(number).await {
+                Ok(root) => root,
+                Err(e) => {
+                    warn!("Failed to compute the output root of L2 block {}: {}", number, e);
+                    return;
+                }
+            };
+            for proposal in self.pending.remove(&number).unwrap_or_default() {
+                self.check(proposal, local_output_root);
+            }
+        }
+    }
+
+    /// Compares a proposal with the locally derived output root and publishes the outcome.
+    fn check(&self, proposal: Proposal, local_output_root: B256) {
+        if proposal.output_root == local_output_root {
+            info!(
+                "Output proposal #{} for L2 block {} verified",
+                proposal.index, proposal.l2_block_number
+            );
+            metrics::counter!(VERIFIED_METRIC).increment(1);
+            _ = self.events.send(ChallengeEvent::ProposalVerified(proposal));
+        } else {
+            error!(
+                "Output proposal #{} for L2 block {} disagrees with the derived chain: proposed {}, derived {}",
+                proposal.index, proposal.l2_block_number, proposal.output_root, local_output_root
+            );
+            metrics::counter!(MISMATCH_METRIC).increment(1);
+            _ = self.events.send(ChallengeEvent::ProposalMismatch { proposal, local_output_root });
+        }
+    }
+
+    /// Computes the output root of the given L2 block from the local execution client.
+    async fn output_root_at(&self, number: u64) -> Result<B256, NodeError> {
+        let block = self
+            .l2
+            .get_block(BlockId::number(number), BlockTransactionsKind::Hashes)
+            .await?
+            .ok_or_else(|| NodeError::Provider(format!("L2 block {number} not found")))?;
+        let proof = self
+            .l2
+            .get_proof(L2_TO_L1_MESSAGE_PASSER, Vec::new())
+            .block_id(BlockId::number(number))
+            .await?;
+        Ok(output_root_v0(block.header.state_root, proof.storage_hash, block.header.hash))
+    }
+
+    /// Returns the number of proposals made so far.
+    async fn proposal_count(&self) -> Result<u64, NodeError> {
+        let count = match self.source {
+            ProposalSource::OutputOracle(oracle) => {
+                let data = self.call(oracle, bindings::nextOutputIndexCall {}).await?;
+                bindings::nextOutputIndexCall::abi_decode_returns(&data, true)
+                    .map_err(|e| NodeError::ProposalDecode(e.to_string()))?
+                    ._0
+            }
+            ProposalSource::DisputeGameFactory(factory) => {
+                let data = self.call(factory, bindings::gameCountCall {}).await?;
+                bindings::gameCountCall::abi_decode_returns(&data, true)
+                    .map_err(|e| NodeError::ProposalDecode(e.to_string()))?
+                    .gameCount_
+            }
+        };
+        Ok(count.saturating_to())
+    }
+
+    /// Fetches the proposal with the given index.
+    async fn proposal(&self, index: u64) -> Result<Proposal, NodeError> {
+        match self.source {
+            ProposalSource::OutputOracle(oracle) => {
+                let call = bindings::getL2OutputCall { _l2OutputIndex: U256::from(index) };
+                let data = self.call(oracle, call).await?;
+                decode_output_proposal(index, &data)
+            }
+            ProposalSource::DisputeGameFactory(factory) => {
+                let call = bindings::gameAtIndexCall { _index: U256::from(index) };
+                let data = self.call(factory, call).await?;
+                let game = bindings::gameAtIndexCall::abi_decode_returns(&data, true)
+                    .map_err(|e| NodeError::ProposalDecode(e.to_string()))?
+                    .proxy_;
+                let data = self.call(game, bindings::rootClaimCall {}).await?;
+                let output_root = bindings::rootClaimCall::abi_decode_returns(&data, true)
+                    .map_err(|e| NodeError::ProposalDecode(e.to_string()))?
+                    .rootClaim_;
+                let data = self.call(game, bindings::l2BlockNumberCall {}).await?;
+                let l2_block_number = bindings::l2BlockNumberCall::abi_decode_returns(&data, true)
+                    .map_err(|e| NodeError::ProposalDecode(e.to_string()))?
+                    .l2BlockNumber_;
+                Ok(Proposal {
+                    index,
+                    l2_block_number: l2_block_number.saturating_to(),
+                    output_root,
+                    game: Some(game),
+                })
+            }
+        }
+    }
+
+    /// Performs an `eth_call` against the latest L1 block.
+    async fn call<C: SolCall>(&self, to: Address, call: C) -> Result<Bytes, NodeError> {
+        let tx = TransactionRequest::default()
+            .to(to)
+            .input(TransactionInput::new(call.abi_encode().into()));
+        Ok(self.l1.call(&tx).await?)
+    }
+}
+
+/// Decodes the return data of `L2OutputOracle.getL2Output` into a [Proposal].
+fn decode_output_proposal(index: u64, data: &[u8]) -> Result<Proposal, NodeError> {
+    let output = bindings::getL2OutputCall::abi_decode_returns(data, true)
+        .map_err(|e| NodeError::ProposalDecode(e.to_string()))?
+        ._0;
+    Ok(Proposal {
+        index,
+        l2_block_number: u64::try_from(output.l2BlockNumber).unwrap_or(u64::MAX),
+        output_root: output.outputRoot,
+        game: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolValue;
+
+    #[test]
+    fn test_output_root_v0_preimage() {
+        let state_root = B256::with_last_byte(1);
+        let message_passer_root = B256::with_last_byte(2);
+        let block_hash = B256::with_last_byte(3);
+        let mut preimage = vec![0u8; 32];
+        preimage.extend_from_slice(state_root.as_slice());
+        preimage.extend_from_slice(message_passer_root.as_slice());
+        preimage.extend_from_slice(block_hash.as_slice());
+        assert_eq!(
+            output_root_v0(state_root, message_passer_root, block_hash),
+            keccak256(&preimage)
+        );
+        assert_ne!(
+            output_root_v0(block_hash, message_passer_root, state_root),
+            keccak256(preimage)
+        );
+    }
+
+    #[test]
+    fn test_decode_output_proposal() {
+        let output = bindings::OutputProposal {
+            outputRoot: B256::with_last_byte(7),
+            timestamp: 1_700_000_000,
+            l2BlockNumber: 1800,
+        };
+        let proposal = decode_output_proposal(3, &output.abi_encode()).unwrap();
+        assert_eq!(
+            proposal,
+            Proposal {
+                index: 3,
+                l2_block_number: 1800,
+                output_root: B256::with_last_byte(7),
+                game: None
+            }
+        );
+    }
+}
@@ -1,11 +1,17 @@
 //! Contains the configuration for the hilo-node.
 
-use crate::SyncMode;
+use crate::{HealthConfig, NetworkConfig, ProposalSource, SyncMode};
+use alloy_primitives::Address;
 use alloy_rpc_types_engine::JwtSecret;
+use hilo_driver::{L1ChainSpec, DEVNET_POLL_INTERVAL};
 use op_alloy_genesis::RollupConfig;
 use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, time::Duration};
 use url::Url;
 
+/// The default time the execution client is given to sync to a trusted block.
+pub const DEFAULT_EL_SYNC_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
 /// An error thrown by a [Config] operation.
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
@@ -18,6 +24,24 @@ pub enum ConfigError {
     /// An L1 chain provider error.
     #[error("L1 chain provider error: {0}")]
     ChainProvider(String),
+    /// The L2 chain ID does not match the chain ID of the rollup config.
+    #[error("l2 chain id {0} does not match the rollup config chain id {1}")]
+    ChainIdMismatch(u64, u64),
+    /// The L1 chain ID of the chain spec does not match the rollup config.
+    #[error("l1 chain spec chain id {0} does not match the rollup config l1 chain id {1}")]
+    L1ChainIdMismatch(u64, u64),
+    /// The sync mode requires a trusted L2 RPC.
+    #[error("{0} sync requires a checkpoint sync url")]
+    MissingCheckpointSyncUrl(SyncMode),
+    /// Challenge sync requires an `L2OutputOracle` or `DisputeGameFactory` address.
+    #[error("challenge sync requires an l2 output oracle or dispute game factory address")]
+    MissingProposalSource,
+    /// The provider cache size is zero.
+    #[error("the cache size must be greater than zero")]
+    ZeroCacheSize,
+    /// The rollup node and admin RPCs are configured on the same address.
+    #[error("the rpc and admin rpc cannot both be served on {0}")]
+    RpcAddressConflict(Url),
 }
 
 /// The global node configuration.
@@ -45,13 +69,108 @@ pub struct Config {
     pub checkpoint_sync_url: Option<Url>,
     /// The hilo-node RPC server
     pub rpc_url: Option<Url>,
-    /// The devnet mode.
-    /// If devnet is enabled.
+    /// The address to serve the admin RPC on. The admin RPC is disabled if unset.
+    #[serde(default)]
+    pub admin_rpc_url: Option<Url>,
+    /// The address to serve the `/healthz` and `/readyz` probes on. Disabled if unset.
+    #[serde(default)]
+    pub health_url: Option<Url>,
+    /// The thresholds of the health checks.
+    #[serde(default)]
+    pub health: HealthConfig,
+    /// Whether the node runs against a local devnet.
+    ///
+    /// Devnets poll their clients more often, skip the default bootnodes,
+    /// and tolerate an unreachable blob archiver.
     pub devnet: bool,
+    /// The L1 chain spec, used instead of querying the beacon node when set.
+    #[serde(default)]
+    pub l1_chain_spec: Option<L1ChainSpec>,
+    /// Whether to skip the startup consistency checks against the connected clients.
+    #[serde(default)]
+    pub skip_preflight: bool,
     /// The mode to sync.
     pub sync_mode: SyncMode,
+    /// The time the execution client is given to sync to a trusted block in checkpoint
+    /// and fast sync, before the node gives up.
+    #[serde(default = "default_el_sync_timeout")]
+    pub el_sync_timeout: Duration,
     /// The cache size for in-memory providers.
     pub cache_size: usize,
+    /// The data directory used to persist node state such as pipeline checkpoints.
+    #[serde(default)]
+    pub data_dir: Option<PathBuf>,
+    /// The p2p networking config. Networking is disabled if unset.
+    #[serde(default)]
+    pub network: Option<NetworkConfig>,
+    /// The `L2OutputOracle` address proposals are verified against in challenge sync.
+    #[serde(default)]
+    pub l2_output_oracle: Option<Address>,
+    /// The `DisputeGameFactory` address proposals are verified against in challenge sync.
+    /// Takes precedence over the `l2_output_oracle`.
+    #[serde(default)]
+    pub dispute_game_factory: Option<Address>,
+}
+
+/// Returns the [DEFAULT_EL_SYNC_TIMEOUT], for configs that do not set one.
+const fn default_el_sync_timeout() -> Duration {
+    DEFAULT_EL_SYNC_TIMEOUT
+}
+
+impl Config {
+    /// Returns the [ProposalSource] used to verify output proposals, if configured.
+    pub fn proposal_source(&self) -> Option<ProposalSource> {
+        self.dispute_game_factory
+            .map(ProposalSource::DisputeGameFactory)
+            .or(self.l2_output_oracle.map(ProposalSource::OutputOracle))
+    }
+
+    /// Returns the interval to poll a client at, shortened to the
+    /// [DEVNET_POLL_INTERVAL] in devnet mode.
+    pub const fn poll_interval(&self, default: Duration) -> Duration {
+        if self.devnet {
+            DEVNET_POLL_INTERVAL
+        } else {
+            default
+        }
+    }
+
+    /// Checks that the config is consistent before the node is started.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.l2_chain_id != self.rollup_config.l2_chain_id {
+            return Err(ConfigError::ChainIdMismatch(
+                self.l2_chain_id,
+                self.rollup_config.l2_chain_id,
+            ));
+        }
+        if let Some(spec) = self.l1_chain_spec {
+            if spec.chain_id != self.rollup_config.l1_chain_id {
+                return Err(ConfigError::L1ChainIdMismatch(
+                    spec.chain_id,
+                    self.rollup_config.l1_chain_id,
+                ));
+            }
+        }
+        if matches!(self.sync_mode, SyncMode::Fast | SyncMode::Checkpoint)
+            && self.checkpoint_sync_url.is_none()
+        {
+            return Err(ConfigError::MissingCheckpointSyncUrl(self.sync_mode));
+        }
+        if self.sync_mode == SyncMode::Challenge && self.proposal_source().is_none() {
+            return Err(ConfigError::MissingProposalSource);
+        }
+        if self.cache_size == 0 {
+            return Err(ConfigError::ZeroCacheSize);
+        }
+        if let (Some(rpc), Some(admin)) = (&self.rpc_url, &self.admin_rpc_url) {
+            if rpc.host() == admin.host()
+                && rpc.port_or_known_default() == admin.port_or_known_default()
+            {
+                return Err(ConfigError::RpcAddressConflict(admin.clone()));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl From<Config> for hilo_driver::Config {
@@ -67,6 +186,9 @@ impl From<Config> for hilo_driver::Config {
             rpc_url: config.rpc_url,
             cache_size: config.cache_size,
             jwt_secret: config.jwt_secret,
+            data_dir: config.data_dir,
+            devnet: config.devnet,
+            l1_chain_spec: config.l1_chain_spec,
         }
     }
 }
@@ -87,37 +209,91 @@ where
     JwtSecret::from_hex(s).map_err(serde::de::Error::custom)
 }
 
+#[cfg(test)]
+impl Config {
+    /// Returns an OP Mainnet config against local clients, for tests to override.
+    pub(crate) fn test_default() -> Self {
+        let local = |port: u16| Url::parse(&format!("http://127.0.0.1:{port}")).unwrap();
+        Self {
+            l2_chain_id: 10,
+            l1_rpc_url: local(8545),
+            l1_beacon_url: local(8555),
+            blob_archiver_url: None,
+            l2_rpc_url: local(9545),
+            l2_engine_url: local(9555),
+            rollup_config: op_alloy_registry::ROLLUP_CONFIGS.get(&10).unwrap().clone(),
+            jwt_secret: JwtSecret::random(),
+            checkpoint_sync_url: None,
+            rpc_url: None,
+            admin_rpc_url: None,
+            health_url: None,
+            health: HealthConfig::default(),
+            devnet: false,
+            l1_chain_spec: None,
+            skip_preflight: false,
+            sync_mode: SyncMode::Full,
+            el_sync_timeout: DEFAULT_EL_SYNC_TIMEOUT,
+            cache_size: 256,
+            data_dir: None,
+            network: None,
+            l2_output_oracle: None,
+            dispute_game_factory: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use op_alloy_registry::ROLLUP_CONFIGS;
 
     #[test]
     fn test_roundtrip_config() {
-        let rollup_config = ROLLUP_CONFIGS.get(&10).unwrap().clone();
-        let l1_rpc_url = Url::parse("http://127.0.0.1:8545").unwrap();
-        let l1_beacon_url = Url::parse("http://127.0.0.1:8555").unwrap();
-        let l2_rpc_url = Url::parse("http://127.0.0.1:9545").unwrap();
-        let l2_engine_url = Url::parse("http://127.0.0.1:9555").unwrap();
-        let jwt_secret = JwtSecret::random();
         let config = Config {
-            l2_chain_id: 10,
-            l1_rpc_url,
-            l2_rpc_url,
-            l1_beacon_url,
-            blob_archiver_url: None,
-            l2_engine_url,
-            rollup_config,
-            jwt_secret,
-            checkpoint_sync_url: None,
-            rpc_url: None,
-            devnet: false,
+            admin_rpc_url: Some(Url::parse("http://127.0.0.1:9546").unwrap()),
+            health_url: Some(Url::parse("http://127.0.0.1:9547").unwrap()),
             sync_mode: SyncMode::Fast,
-            cache_size: 256,
+            data_dir: Some(PathBuf::from("/tmp/hilo")),
+            network: Some(NetworkConfig {
+                listen_addr: "0.0.0.0:9222".parse().unwrap(),
+                unsafe_block_signer: Address::with_last_byte(2),
+                discovery_interval: std::time::Duration::from_secs(10),
+                bootnodes: vec!["/ip4/127.0.0.1/tcp/9223".into()],
+                static_peers: Vec::new(),
+                no_default_bootnodes: false,
+            }),
+            dispute_game_factory: Some(Address::with_last_byte(1)),
+            ..Config::test_default()
         };
 
         let serialized = serde_json::to_string(&config).unwrap();
         let deserialized: Config = serde_json::from_str(&serialized).unwrap();
         assert_eq!(config, deserialized);
+        assert_eq!(
+            config.proposal_source(),
+            Some(ProposalSource::DisputeGameFactory(Address::with_last_byte(1)))
+        );
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let mut config = Config {
+            rpc_url: Some(Url::parse("http://127.0.0.1:9546").unwrap()),
+            admin_rpc_url: Some(Url::parse("http://127.0.0.1:9547").unwrap()),
+            ..Config::test_default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.l2_chain_id = 8453;
+        assert!(matches!(config.validate(), Err(ConfigError::ChainIdMismatch(8453, 10))));
+        config.l2_chain_id = 10;
+
+        config.sync_mode = SyncMode::Checkpoint;
+        assert!(matches!(config.validate(), Err(ConfigError::MissingCheckpointSyncUrl(_))));
+        config.sync_mode = SyncMode::Challenge;
+        assert!(matches!(config.validate(), Err(ConfigError::MissingProposalSource)));
+        config.sync_mode = SyncMode::Full;
+
+        config.admin_rpc_url = config.rpc_url.clone();
+        assert!(matches!(config.validate(), Err(ConfigError::RpcAddressConflict(_))));
     }
 }
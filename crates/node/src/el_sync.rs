@@ -0,0 +1,287 @@
+//! Execution layer sync against a trusted L2 RPC.
+
+use alloy_eips::{BlockId, BlockNumberOrTag};
+use alloy_primitives::B256;
+use alloy_provider::{network::primitives::BlockTransactionsKind, Provider, ReqwestProvider};
+use alloy_rpc_types_engine::{ForkchoiceState, PayloadStatusEnum};
+use hilo_engine::{Engine, EngineClient};
+use op_alloy_protocol::BlockInfo;
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use tokio::time::{sleep, timeout};
+
+use crate::{Config, NodeError};
+
+/// The interval at which the execution client's sync status is polled.
+const SYNC_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The subset of the `admin_nodeInfo` response needed to peer with a node.
+#[derive(Debug, Deserialize)]
+struct NodeInfo {
+    /// The enode URL of the node.
+    enode: String,
+}
+
+/// Syncs the execution client to blocks fetched from a trusted L2 RPC.
+///
+/// Used by checkpoint and fast sync to let the execution client sync the chain
+/// itself before derivation takes over.
+#[derive(Debug)]
+pub(crate) struct ElSyncer {
+    /// The trusted L2 RPC to fetch the checkpoint from.
+    trusted: ReqwestProvider,
+    /// The RPC of the local execution client.
+    l2: ReqwestProvider,
+    /// The engine API client of the local execution client.
+    engine: EngineClient,
+    /// The interval at which the execution client's sync status is polled.
+    poll_interval: Duration,
+    /// The time the execution client is given to sync to a target.
+    sync_timeout: Duration,
+}
+
+impl ElSyncer {
+    /// Creates a new [ElSyncer] from the node [Config].
+    pub(crate) fn new(config: &Config) -> Result<Self, NodeError> {
+        let trusted =
+            config.checkpoint_sync_url.clone().ok_or(NodeError::MissingCheckpointSyncUrl)?;
+        let engine = EngineClient::new_http(
+            config.l2_engine_url.clone(),
+            config.l2_rpc_url.clone(),
+            Arc::new(config.rollup_config.clone()),
+            config.jwt_secret,
+        );
+        Ok(Self {
+            trusted: ReqwestProvider::new_http(trusted),
+            l2: ReqwestProvider::new_http(config.l2_rpc_url.clone()),
+            engine,
+            poll_interval: config.poll_interval(SYNC_POLL_INTERVAL),
+            sync_timeout: config.el_sync_timeout,
+        })
+    }
+
+    /// Fetches a block from the trusted L2 RPC.
+    pub(crate) async fn trusted_block(&self, id: BlockId) -> Result<BlockInfo, NodeError> {
+        let block = self
+            .trusted
+            .get_block(id, BlockTransactionsKind::Hashes)
+            .await?
+            .ok_or_else(|| NodeError::TrustedBlockNotFound(format!("{id:?}")))?;
+        Ok(BlockInfo {
+            hash: block.header.hash,
+            number: block.header.number,
+            parent_hash: block.header.parent_hash,
+            timestamp: block.header.timestamp,
+        })
+    }
+
+    /// Adds the trusted execution client as a peer of the local execution client.
+    ///
+    /// This requires the `admin` namespace on both nodes. Failures are logged and
+    /// ignored, since the execution client can still sync from its other peers.
+    pub(crate) async fn add_trusted_peer(&self) {
+        let info = match self
+            .trusted
+            .raw_request::<_, NodeInfo>("admin_nodeInfo".into(), Vec::<String>::new())
+            .await
+        {
+            Ok(info) => info,
+            Err(e) => {
+                warn!("Failed to fetch the trusted node's enode, skipping admin_addPeer: {}", e);
+                return;
+            }
+        };
+        match self.l2.raw_request::<_, bool>("admin_addPeer".into(), [&info.enode]).await {
+            Ok(true) => info!("Added trusted peer {}", info.enode),
+            Ok(false) => warn!("Execution client refused trusted peer {}", info.enode),
+            Err(e) => warn!("Failed to add trusted peer: {}", e),
+        }
+    }
+
+    /// Snap-syncs the execution client to the unsafe head of the trusted node, then sets
+    /// its safe and finalized heads to those of the trusted node.
+    ///
+    /// Returns the safe head, which derivation starts from.
+    pub(crate) async fn fast_sync(&self) -> Result<BlockInfo, NodeError> {
+        let tip = self.trusted_block(BlockId::Number(BlockNumberOrTag::Latest)).await?;
+        info!("Fast syncing to unsafe L2 block {} ({})", tip.number, tip.hash);
+        self.add_trusted_peer().await;
+
+        // Only the head is set while syncing, so the execution client can snap-sync
+        // without first validating the safe and finalized chain.
+        self.sync_to(tip, B256::ZERO, B256::ZERO).await?;
+
+        // The trusted node kept advancing while the execution client synced, so its heads
+        // are read again. The unsafe head is read last, so that the safe and finalized
+        // heads are never ahead of it.
+        let finalized = self.trusted_block(BlockId::Number(BlockNumberOrTag::Finalized)).await?;
+        let safe = self.trusted_block(BlockId::Number(BlockNumberOrTag::Safe)).await?;
+        let tip = self.trusted_block(BlockId::Number(BlockNumberOrTag::Latest)).await?;
+        info!(
+            "Reconciling unsafe head {}, safe head {} and finalized head {}",
+            tip.number, safe.number, finalized.number
+        );
+        self.sync_to(tip, safe.hash, finalized.hash).await?;
+        Ok(safe)
+    }
+
+    /// Drives the execution client to the given head with forkchoice updates until it
+    /// reports the head as `VALID`.
+    ///
+    /// The execution client syncs the missing chain from its peers while it reports
+    /// `SYNCING`, and its progress is logged on every poll. Gives up with
+    /// [NodeError::SyncTimeout] if the head is not valid within the configured
+    /// [Config::el_sync_timeout].
+    pub(crate) async fn sync_to(
+        &self,
+        head: BlockInfo,
+        safe: B256,
+        finalized: B256,
+    ) -> Result<(), NodeError> {
+        let forkchoice = ForkchoiceState {
+            head_block_hash: head.hash,
+            safe_block_hash: safe,
+            finalized_block_hash: finalized,
+        };
+        timeout(self.sync_timeout, self.poll_sync(head, forkchoice))
+            .await
+            .map_err(|_| NodeError::SyncTimeout(head.number, self.sync_timeout))?
+    }
+
+    /// Sends the forkchoice state until the execution client reports the head as `VALID`.
+    async fn poll_sync(
+        &self,
+        head: BlockInfo,
+        forkchoice: ForkchoiceState,
+    ) -> Result<(), NodeError> {
+        loop {
+            match self.engine.forkchoice_update(forkchoice, None).await {
+                Ok(update) => match update.payload_status.status {
+                    PayloadStatusEnum::Valid => {
+                        info!("Execution client synced to L2 block {}", head.number);
+                        return Ok(());
+                    }
+                    PayloadStatusEnum::Invalid { validation_error } => {
+                        return Err(NodeError::SyncTargetRejected(head.hash, validation_error));
+                    }
+                    PayloadStatusEnum::Syncing | PayloadStatusEnum::Accepted => {
+                        match self.l2.get_block_number().await {
+                            Ok(current) => info!(
+                                "Execution client syncing: at L2 block {} of {}",
+                                current, head.number
+                            ),
+                            Err(_) => info!("Execution client syncing to L2 block {}", head.number),
+                        }
+                    }
+                },
+                Err(e) => warn!("Forkchoice update failed, retrying: {}", e),
+            }
+            sleep(self.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SyncMode;
+    use hilo_driver::test_utils::{rollup_config, StandIn, UNREACHABLE};
+    use url::Url;
+
+    /// Returns a node config syncing the local execution client from the trusted node.
+    fn config(trusted: &StandIn, local: &StandIn) -> Config {
+        let unreachable = Url::parse(UNREACHABLE).unwrap();
+        Config {
+            l2_chain_id: 901,
+            l1_rpc_url: unreachable.clone(),
+            l1_beacon_url: unreachable,
+            l2_rpc_url: local.url.clone(),
+            l2_engine_url: local.url.clone(),
+            rollup_config: rollup_config(),
+            checkpoint_sync_url: Some(trusted.url.clone()),
+            devnet: true,
+            skip_preflight: true,
+            sync_mode: SyncMode::Fast,
+            ..Config::test_default()
+        }
+    }
+
+    /// Returns the forkchoice state of the given heads.
+    fn forkchoice(head: BlockInfo, safe: B256, finalized: B256) -> ForkchoiceState {
+        ForkchoiceState {
+            head_block_hash: head.hash,
+            safe_block_hash: safe,
+            finalized_block_hash: finalized,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fast_sync_reads_heads_after_syncing() {
+        let (trusted, local) = (StandIn::start().await, StandIn::start().await);
+        trusted.extend_l2(10);
+        local.set_syncing(u64::MAX);
+        let syncer = ElSyncer::new(&config(&trusted, &local)).unwrap();
+
+        let sync = tokio::spawn(async move { syncer.fast_sync().await });
+        while local.forkchoices().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        // The trusted node advances while the execution client syncs, and its safe head
+        // passes the unsafe head the execution client started syncing to.
+        trusted.extend_l2(10);
+        trusted.set_l2_heads(15, 12);
+        local.set_syncing(0);
+
+        let safe = sync.await.unwrap().unwrap();
+        assert_eq!(safe, trusted.l2_block_info(15));
+        let (start, tip) = (trusted.l2_block_info(10), trusted.l2_block_info(20));
+        let (safe, finalized) = (safe.hash, trusted.l2_block_info(12).hash);
+        let forkchoices = local.forkchoices();
+        let (reconciled, syncing) = forkchoices.split_last().unwrap();
+        assert!(syncing.iter().all(|state| *state == forkchoice(start, B256::ZERO, B256::ZERO)));
+        assert_eq!(*reconciled, forkchoice(tip, safe, finalized));
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_sync_by_hash() {
+        let (trusted, local) = (StandIn::start().await, StandIn::start().await);
+        trusted.extend_l2(42);
+        local.set_syncing(2);
+        let syncer = ElSyncer::new(&config(&trusted, &local)).unwrap();
+
+        let hash = trusted.l2_block_info(42).hash;
+        let checkpoint = syncer.trusted_block(BlockId::hash(hash)).await.unwrap();
+        assert_eq!(checkpoint, trusted.l2_block_info(42));
+        syncer.add_trusted_peer().await;
+        syncer.sync_to(checkpoint, checkpoint.hash, checkpoint.hash).await.unwrap();
+
+        // The checkpoint is sent until the execution client reports it as valid.
+        let forkchoice = forkchoice(checkpoint, checkpoint.hash, checkpoint.hash);
+        assert_eq!(local.forkchoices(), vec![forkchoice; 3]);
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_not_found() {
+        let (trusted, local) = (StandIn::start().await, StandIn::start().await);
+        let syncer = ElSyncer::new(&config(&trusted, &local)).unwrap();
+
+        let result = syncer.trusted_block(BlockId::hash(B256::repeat_byte(0xff))).await;
+        assert!(matches!(result, Err(NodeError::TrustedBlockNotFound(_))));
+        assert!(local.forkchoices().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sync_timeout() {
+        let (trusted, local) = (StandIn::start().await, StandIn::start().await);
+        trusted.extend_l2(42);
+        local.set_syncing(u64::MAX);
+        let config =
+            Config { el_sync_timeout: Duration::from_millis(500), ..config(&trusted, &local) };
+        let syncer = ElSyncer::new(&config).unwrap();
+
+        let checkpoint = trusted.l2_block_info(42);
+        let result = syncer.sync_to(checkpoint, checkpoint.hash, checkpoint.hash).await;
+        assert!(matches!(result, Err(NodeError::SyncTimeout(42, _))));
+        assert!(!local.forkchoices().is_empty());
+    }
+}
@@ -1,7 +1,9 @@
 //! Node error types.
 
-use crate::ConfigError;
+use crate::{ConfigError, PreflightError};
+use alloy_primitives::B256;
 use hilo_driver::DriverError;
+use std::time::Duration;
 
 /// A high-level `Node`error.
 #[derive(Debug, thiserror::Error)]
@@ -15,11 +17,61 @@ pub enum NodeError {
     /// An error thrown by a [crate::Config] operation.
     #[error("config error: {0}")]
     Beacon(#[from] ConfigError),
+    /// An error reading or writing pipeline checkpoints.
+    #[error("checkpoint error: {0}")]
+    Checkpoint(String),
+    /// Checkpoint and fast sync require a trusted L2 RPC.
+    #[error("checkpoint and fast sync require a checkpoint sync url")]
+    MissingCheckpointSyncUrl,
+    /// The checkpoint hash could not be parsed.
+    #[error("invalid checkpoint hash: {0}")]
+    InvalidCheckpointHash(String),
+    /// A block was not found on the trusted L2 RPC.
+    #[error("trusted block not found: {0}")]
+    TrustedBlockNotFound(String),
+    /// The execution client reported the sync target as invalid.
+    #[error("execution client rejected sync target {0}: {1}")]
+    SyncTargetRejected(B256, String),
+    /// The execution client did not sync to the target in time.
+    #[error("execution client did not sync to L2 block {0} within {1:?}")]
+    SyncTimeout(u64, Duration),
+    /// Challenge sync requires an `L2OutputOracle` or `DisputeGameFactory` address.
+    #[error("challenge sync requires an L2 output oracle or dispute game factory address")]
+    MissingProposalSource,
+    /// An output proposal could not be decoded.
+    #[error("failed to decode output proposal: {0}")]
+    ProposalDecode(String),
+    /// The rollup node RPC server could not be started.
+    #[error("rpc server error: {0}")]
+    Rpc(String),
+    /// The health check server could not be started.
+    #[error("health server error: {0}")]
+    Health(String),
+    /// P2P networking could not be started.
+    #[error("network error: {0}")]
+    Network(String),
+    /// The startup consistency checks found mismatches.
+    #[error("preflight checks failed:\n{}", list(.0))]
+    Preflight(Vec<PreflightError>),
+    /// The metrics exporter failed.
+    #[error("metrics exporter error: {0}")]
+    Metrics(String),
+    /// A supervised subsystem failed fatally.
+    #[error("{0} failed: {1}")]
+    TaskFailed(&'static str, String),
+    /// A supervised subsystem panicked.
+    #[error("task panicked: {0}")]
+    TaskPanicked(String),
     /// An error thrown by the driver.
     #[error("driver error: {0}")]
     Driver(#[from] DriverError),
 }
 
+/// Lists the preflight failures, one per line.
+fn list(failures: &[PreflightError]) -> String {
+    failures.iter().map(|failure| format!("  - {failure}")).collect::<Vec<_>>().join("\n")
+}
+
 impl From<alloy_transport::TransportError> for NodeError {
     fn from(e: alloy_transport::TransportError) -> Self {
         Self::Provider(e.to_string())
@@ -32,6 +84,7 @@ impl From<hilo_driver::ConfigError> for NodeError {
             hilo_driver::ConfigError::Beacon(e) => Self::Beacon(ConfigError::Beacon(e)),
             hilo_driver::ConfigError::L2ChainProvider(e) => Self::Provider(e),
             hilo_driver::ConfigError::ChainProvider(e) => Self::Provider(e),
+            hilo_driver::ConfigError::Checkpoint(e) => Self::Checkpoint(e),
         }
     }
 }
@@ -0,0 +1,416 @@
+//! Liveness and readiness probes of the node, served over HTTP.
+
+use alloy_provider::Provider;
+use alloy_rpc_types_eth::SyncStatus;
+use hilo_driver::{DriverCommand, DriverEvent};
+use hilo_engine::EngineClient;
+use http_body_util::Full;
+use hyper::{
+    body::{Bytes, Incoming},
+    header::CONTENT_TYPE,
+    server::conn::http1,
+    service::service_fn,
+    Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use op_alloy_protocol::L2BlockInfo;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    net::TcpListener,
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc, watch,
+    },
+};
+use url::Url;
+
+use crate::{rpc::bind_addr, NodeError};
+
+/// The default time without a new L1 head after which the node is unhealthy.
+pub const DEFAULT_MAX_L1_SILENCE: Duration = Duration::from_secs(120);
+
+/// The default age of the L2 safe head after which the node is not ready.
+pub const DEFAULT_MAX_SAFE_LAG: Duration = Duration::from_secs(30 * 60);
+
+/// The time the engine is given to answer before it is considered unreachable.
+const ENGINE_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The thresholds of the health checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthConfig {
+    /// The time without a new L1 head after which L1 notifications are considered stalled.
+    pub max_l1_silence: Duration,
+    /// The age of the L2 safe head after which derivation is considered to be lagging.
+    pub max_safe_lag: Duration,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self { max_l1_silence: DEFAULT_MAX_L1_SILENCE, max_safe_lag: DEFAULT_MAX_SAFE_LAG }
+    }
+}
+
+/// The result of a single health check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Check {
+    /// Whether the check passed.
+    pub healthy: bool,
+    /// A description of the checked state.
+    pub detail: String,
+}
+
+impl Check {
+    /// Creates a new [Check].
+    pub fn new(healthy: bool, detail: impl Into<String>) -> Self {
+        Self { healthy, detail: detail.into() }
+    }
+}
+
+/// The result of a probe, served as the JSON body of `/healthz` and `/readyz`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// Whether all checks passed.
+    pub healthy: bool,
+    /// The individual checks, keyed by name.
+    pub checks: BTreeMap<String, Check>,
+}
+
+impl HealthReport {
+    /// Creates a [HealthReport] from the named checks.
+    pub fn new(checks: impl IntoIterator<Item = (&'static str, Check)>) -> Self {
+        let checks = checks
+            .into_iter()
+            .map(|(name, check)| (name.to_string(), check))
+            .collect::<BTreeMap<_, _>>();
+        Self { healthy: checks.values().all(|check| check.healthy), checks }
+    }
+}
+
+/// The state the health checks are evaluated against.
+#[derive(Debug)]
+struct State {
+    /// When tracking started, bounding the wait for the first L1 head.
+    started: Instant,
+    /// The latest L1 head received and when it was received.
+    l1_head: Option<(u64, Instant)>,
+    /// The L2 safe head.
+    safe_head: Option<L2BlockInfo>,
+    /// The number of connected gossip peers, if networking is enabled.
+    peers: Option<watch::Receiver<usize>>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self { started: Instant::now(), l1_head: None, safe_head: None, peers: None }
+    }
+}
+
+/// The node state tracked for health checks, updated from [DriverEvent]s.
+#[derive(Debug, Clone, Default)]
+pub struct HealthState(Arc<RwLock<State>>);
+
+impl HealthState {
+    /// Follows the given driver events until the channel is closed.
+    pub async fn track(self, mut events: broadcast::Receiver<DriverEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(event) => self.apply(&event),
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!("Health state skipped {} driver events", skipped);
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Applies a [DriverEvent] to the state.
+    pub fn apply(&self, event: &DriverEvent) {
+        let mut state = self.0.write();
+        match event {
+            DriverEvent::L1HeadReceived(head) => {
+                state.l1_head = Some((head.number, Instant::now()))
+            }
+            DriverEvent::SafeHeadUpdated(head) => state.safe_head = Some(*head),
+            DriverEvent::PipelineReset { l2_safe_head, .. } => {
+                state.safe_head = Some(*l2_safe_head)
+            }
+            _ => {}
+        }
+    }
+
+    /// Tracks the number of connected gossip peers.
+    pub fn track_peers(&self, peers: watch::Receiver<usize>) {
+        self.0.write().peers = Some(peers);
+    }
+
+    /// Checks that L1 heads were received within `max_silence`, or that the first
+    /// one is still expected.
+    pub fn l1_check(&self, max_silence: Duration) -> Check {
+        let state = self.0.read();
+        match state.l1_head {
+            Some((number, received)) if received.elapsed() <= max_silence => {
+                Check::new(true, format!("received L1 block {number}"))
+            }
+            Some((number, received)) => Check::new(
+                false,
+                format!("no L1 block since {number}, {}s ago", received.elapsed().as_secs()),
+            ),
+            None if state.started.elapsed() <= max_silence => {
+                Check::new(true, "waiting for the first L1 block")
+            }
+            None => Check::new(false, "no L1 block received yet"),
+        }
+    }
+
+    /// Checks that the L2 safe head is at most `max_lag` behind the given unix time.
+    pub fn derivation_check(&self, max_lag: Duration, now: u64) -> Check {
+        let Some(head) = self.0.read().safe_head else {
+            return Check::new(false, "no safe head derived yet");
+        };
+        let lag = now.saturating_sub(head.block_info.timestamp);
+        let detail = format!("safe head {} is {lag}s old", head.block_info.number);
+        Check::new(lag <= max_lag.as_secs(), detail)
+    }
+
+    /// Checks that gossip peers are connected, if networking is enabled.
+    pub fn peers_check(&self) -> Option<Check> {
+        let peers = *self.0.read().peers.as_ref()?.borrow();
+        Some(Check::new(peers > 0, format!("{peers} peers connected")))
+    }
+}
+
+/// Serves the `/healthz` liveness and `/readyz` readiness probes.
+///
+/// Both respond with a JSON [HealthReport], with status `200` if all checks pass
+/// and `503` otherwise.
+#[derive(Debug, Clone)]
+pub struct HealthServer {
+    /// The thresholds of the checks.
+    config: HealthConfig,
+    /// The tracked node state.
+    state: HealthState,
+    /// The sender of driver commands, closed once the driver stops.
+    commands: mpsc::Sender<DriverCommand>,
+    /// The engine API client the engine check is issued on.
+    ///
+    /// The check does not go through the driver, so that probes never wait for a
+    /// derivation step to complete.
+    engine: EngineClient,
+}
+
+impl HealthServer {
+    /// Creates a new [HealthServer].
+    pub const fn new(
+        config: HealthConfig,
+        state: HealthState,
+        commands: mpsc::Sender<DriverCommand>,
+        engine: EngineClient,
+    ) -> Self {
+        Self { config, state, commands, engine }
+    }
+
+    /// Returns the liveness of the node: the driver is running and L1 notifications
+    /// are flowing.
+    pub fn liveness(&self) -> HealthReport {
+        let driver = if self.commands.is_closed() {
+            Check::new(false, "driver stopped")
+        } else {
+            Check::new(true, "driver running")
+        };
+        HealthReport::new([
+            ("driver", driver),
+            ("l1", self.state.l1_check(self.config.max_l1_silence)),
+        ])
+    }
+
+    /// Returns the readiness of the node: L1 notifications are flowing, the engine is
+    /// reachable, derivation is within the configured lag and peers are connected when
+    /// networking is enabled.
+    pub async fn readiness(&self) -> HealthReport {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let mut checks = vec![
+            ("l1", self.state.l1_check(self.config.max_l1_silence)),
+            ("engine", self.engine_check().await),
+            ("derivation", self.state.derivation_check(self.config.max_safe_lag, now)),
+        ];
+        if let Some(peers) = self.state.peers_check() {
+            checks.push(("peers", peers));
+        }
+        HealthReport::new(checks)
+    }
+
+    /// Checks that the execution client answers on the engine API and is not syncing.
+    async fn engine_check(&self) -> Check {
+        match tokio::time::timeout(ENGINE_CHECK_TIMEOUT, self.engine.syncing()).await {
+            Ok(Ok(SyncStatus::None)) => Check::new(true, "engine ready"),
+            Ok(Ok(SyncStatus::Info(info))) => Check::new(
+                false,
+                format!("engine syncing at block {} of {}", info.current_block, info.highest_block),
+            ),
+            Ok(Err(e)) => Check::new(false, format!("engine unreachable: {e}")),
+            Err(_) => Check::new(false, "engine check timed out"),
+        }
+    }
+
+    /// Responds to a probe request.
+    async fn respond(&self, path: &str) -> Response<Full<Bytes>> {
+        let report = match path {
+            "/healthz" => self.liveness(),
+            "/readyz" => self.readiness().await,
+            _ => {
+                let mut response = Response::new(Full::default());
+                *response.status_mut() = StatusCode::NOT_FOUND;
+                return response;
+            }
+        };
+        let status = if report.healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+        let body = serde_json::to_vec(&report).unwrap_or_default();
+        Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Full::new(Bytes::from(body)))
+            .unwrap_or_default()
+    }
+
+    /// Serves the probes over HTTP at the given URL.
+    ///
+    /// The server runs until the returned future is dropped, and only resolves
+    /// if the listener cannot be bound.
+    pub async fn serve(self, url: &Url) -> Result<(), NodeError> {
+        let listener = TcpListener::bind(bind_addr(url)?)
+            .await
+            .map_err(|e| NodeError::Health(e.to_string()))?;
+        let addr = listener.local_addr().map_err(|e| NodeError::Health(e.to_string()))?;
+        info!("Health checks listening on {}", addr);
+
+        let server = Arc::new(self);
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("Failed to accept health check connection: {}", e);
+                    continue;
+                }
+            };
+            let server = server.clone();
+            tokio::spawn(async move {
+                let service = service_fn(|request: Request<Incoming>| {
+                    let server = server.clone();
+                    async move { Ok::<_, Infallible>(server.respond(request.uri().path()).await) }
+                });
+                if let Err(e) =
+                    http1::Builder::new().serve_connection(TokioIo::new(stream), service).await
+                {
+                    debug!("Health check connection failed: {}", e);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_eips::BlockNumHash;
+    use alloy_rpc_types_engine::JwtSecret;
+    use jsonrpsee::{
+        server::{Server, ServerHandle},
+        RpcModule,
+    };
+    use op_alloy_genesis::RollupConfig;
+    use op_alloy_protocol::BlockInfo;
+
+    /// Starts a stand-in engine API answering `eth_syncing` with the given status.
+    async fn stand_in_engine(status: serde_json::Value) -> (Url, ServerHandle) {
+        let mut module = RpcModule::new(status);
+        module.register_method("eth_syncing", |_, status, _| status.clone()).unwrap();
+        let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", server.local_addr().unwrap())).unwrap();
+        (url, server.start(module))
+    }
+
+    /// Returns a health server checking the engine at the given URL, with the driver stopped.
+    fn health_server(engine_url: Url) -> HealthServer {
+        let (commands, _) = mpsc::channel(1);
+        let engine = EngineClient::new_http(
+            engine_url.clone(),
+            engine_url,
+            Arc::new(RollupConfig::default()),
+            JwtSecret::random(),
+        );
+        HealthServer::new(HealthConfig::default(), HealthState::default(), commands, engine)
+    }
+
+    fn safe_head(number: u64, timestamp: u64) -> L2BlockInfo {
+        L2BlockInfo {
+            block_info: BlockInfo { number, timestamp, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_health_checks() {
+        let state = HealthState::default();
+        assert!(state.l1_check(DEFAULT_MAX_L1_SILENCE).healthy);
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(!state.l1_check(Duration::ZERO).healthy);
+        assert!(!state.derivation_check(DEFAULT_MAX_SAFE_LAG, 1_000).healthy);
+        assert_eq!(state.peers_check(), None);
+
+        state.apply(&DriverEvent::L1HeadReceived(BlockNumHash { number: 7, ..Default::default() }));
+        state.apply(&DriverEvent::SafeHeadUpdated(safe_head(100, 1_000)));
+        assert!(state.l1_check(DEFAULT_MAX_L1_SILENCE).healthy);
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(!state.l1_check(Duration::ZERO).healthy);
+        assert!(state.derivation_check(Duration::from_secs(60), 1_060).healthy);
+        assert!(!state.derivation_check(Duration::from_secs(60), 1_061).healthy);
+
+        let (peers, receiver) = watch::channel(0);
+        state.track_peers(receiver);
+        assert!(!state.peers_check().unwrap().healthy);
+        peers.send_replace(3);
+        assert!(state.peers_check().unwrap().healthy);
+    }
+
+    #[test]
+    fn test_health_report() {
+        let report = HealthReport::new([
+            ("l1", Check::new(true, "received L1 block 7")),
+            ("engine", Check::new(false, "engine check timed out")),
+        ]);
+        assert!(!report.healthy);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["checks"]["engine"]["healthy"], false);
+        assert_eq!(json["checks"]["l1"]["detail"], "received L1 block 7");
+    }
+
+    #[tokio::test]
+    async fn test_engine_check() {
+        // The engine is checked directly, without a running driver.
+        let (url, _server) = stand_in_engine(serde_json::json!(false)).await;
+        let health = health_server(url);
+        assert!(!health.liveness().checks["driver"].healthy);
+        assert_eq!(health.engine_check().await, Check::new(true, "engine ready"));
+
+        let (url, _server) = stand_in_engine(serde_json::json!({
+            "startingBlock": "0x0",
+            "currentBlock": "0x5",
+            "highestBlock": "0xa",
+        }))
+        .await;
+        let check = health_server(url).engine_check().await;
+        assert_eq!(check, Check::new(false, "engine syncing at block 5 of 10"));
+
+        let check = health_server(Url::parse("http://127.0.0.1:1").unwrap()).engine_check().await;
+        assert!(!check.healthy);
+        assert!(check.detail.starts_with("engine unreachable"));
+    }
+}
@@ -9,6 +9,37 @@ extern crate tracing;
 mod errors;
 pub use errors::NodeError;
 
+mod el_sync;
+
+mod challenge;
+pub use challenge::{
+    output_root_v0, ChallengeEvent, ChallengeVerifier, Proposal, ProposalSource,
+    CHALLENGE_EVENT_CHANNEL_SIZE, PROPOSAL_POLL_INTERVAL,
+};
+
+mod preflight;
+pub use preflight::{beacon_genesis_time, preflight, PreflightError, PREFLIGHT_TIMEOUT};
+
+mod network;
+pub use network::{parse_bootnodes, NetworkConfig, UNSAFE_BLOCK_CHANNEL_SIZE};
+
+mod health;
+pub use health::{
+    Check, HealthConfig, HealthReport, HealthServer, HealthState, DEFAULT_MAX_L1_SILENCE,
+    DEFAULT_MAX_SAFE_LAG,
+};
+
+mod rpc;
+pub use rpc::{
+    AdminApiServer, AdminRpc, LogFilterHandle, OutputResponse, RollupNodeApiServer, RollupRpc,
+    RollupState, SafeHeadResponse, SyncStatus, MAX_SAFE_HEAD_ENTRIES, NODE_VERSION,
+};
+
+mod supervisor;
+pub use supervisor::{
+    RestartPolicy, Supervisor, TaskFuture, DEFAULT_MAX_RESTARTS, DEFAULT_RESTART_BACKOFF,
+};
+
 mod node;
 pub use node::Node;
 
@@ -16,4 +47,4 @@ mod sync;
 pub use sync::SyncMode;
 
 mod config;
-pub use config::{Config, ConfigError};
+pub use config::{Config, ConfigError, DEFAULT_EL_SYNC_TIMEOUT};
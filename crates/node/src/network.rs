@@ -0,0 +1,132 @@
+//! P2P networking of the node.
+
+use alloy_primitives::Address;
+use hilo_net::{discovery::bootnodes::Bootnode, driver::NetworkDriver};
+use op_alloy_rpc_types_engine::OpNetworkPayloadEnvelope;
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
+use tokio::{
+    sync::{mpsc, watch},
+    task::AbortHandle,
+};
+
+use crate::NodeError;
+
+/// The number of gossiped unsafe blocks buffered for the driver.
+pub const UNSAFE_BLOCK_CHANNEL_SIZE: usize = 64;
+
+/// The p2p networking configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// The address gossip listens on over TCP and discovery over UDP.
+    pub listen_addr: SocketAddr,
+    /// The address unsafe blocks must be signed by.
+    pub unsafe_block_signer: Address,
+    /// The interval between discovery lookups for new peers.
+    pub discovery_interval: Duration,
+    /// ENRs or multiaddrs replacing the default bootnodes of the chain.
+    #[serde(default)]
+    pub bootnodes: Vec<String>,
+    /// Multiaddrs of peers that are always dialed.
+    #[serde(default)]
+    pub static_peers: Vec<String>,
+    /// Disables the default bootnodes of the chain.
+    #[serde(default)]
+    pub no_default_bootnodes: bool,
+}
+
+impl NetworkConfig {
+    /// Builds and runs the [NetworkDriver], forwarding the unsafe blocks it gossips
+    /// to `unsafe_blocks` and its peer count to `peers`.
+    ///
+    /// The node key, discovered nodes and peer metadata are persisted to `data_dir`, if
+    /// set, so the node keeps its peer id and ENR across restarts.
+    ///
+    /// The network driver hands out blocks over a blocking channel, so they are
+    /// forwarded from a blocking task. The configured bootnodes replace the default
+    /// bootnodes of the chain, which are skipped in devnet mode. Only resolves once
+    /// the network driver stops, so it can be restarted with the same channels.
+    pub async fn run(
+        &self,
+        chain_id: u64,
+        devnet: bool,
+        data_dir: Option<PathBuf>,
+        unsafe_blocks: mpsc::Sender<OpNetworkPayloadEnvelope>,
+        peers: watch::Sender<usize>,
+    ) -> Result<(), NodeError> {
+        let mut builder = NetworkDriver::builder();
+        builder
+            .with_chain_id(chain_id)
+            .with_unsafe_block_signer(self.unsafe_block_signer)
+            .with_gossip_addr(self.listen_addr)
+            .with_interval(self.discovery_interval);
+        if !self.bootnodes.is_empty() {
+            builder.with_bootnodes(parse_bootnodes(&self.bootnodes)?);
+        }
+        if devnet || self.no_default_bootnodes {
+            builder.without_default_bootnodes();
+        }
+        if !self.static_peers.is_empty() {
+            let peers = parse_bootnodes(&self.static_peers)?
+                .into_iter()
+                .map(|peer| peer.into_multiaddr())
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| NodeError::Network("static peers must be multiaddrs".into()))?;
+            builder.with_static_peers(peers);
+        }
+        if let Some(dir) = data_dir {
+            builder.with_data_dir(dir);
+        }
+        let mut driver = builder.build().map_err(|e| NodeError::Network(e.to_string()))?;
+        let blocks = driver
+            .take_unsafe_block_recv()
+            .ok_or_else(|| NodeError::Network("unsafe block receiver already taken".into()))?;
+        let mut peer_count = driver.peer_count();
+        let mut handle = driver.start().map_err(|e| NodeError::Network(e.to_string()))?;
+        info!("P2P networking started on {}", self.listen_addr);
+
+        tokio::task::spawn_blocking(move || {
+            while let Ok(envelope) = blocks.recv() {
+                if unsafe_blocks.blocking_send(envelope).is_err() {
+                    break;
+                }
+            }
+        });
+
+        // Stop the network driver if this future is dropped by a supervisor.
+        let abort = handle.abort_handle();
+        let _guard = AbortOnDrop(abort);
+        let result = loop {
+            tokio::select! {
+                result = &mut handle => break result,
+                changed = peer_count.changed() => match changed {
+                    Ok(()) => _ = peers.send_replace(*peer_count.borrow_and_update()),
+                    Err(_) => break (&mut handle).await,
+                },
+            }
+        };
+        peers.send_replace(0);
+        match result {
+            Ok(()) => Err(NodeError::Network("network driver stopped".into())),
+            Err(e) => Err(NodeError::Network(e.to_string())),
+        }
+    }
+}
+
+/// Parses bootnodes given as ENRs or multiaddrs.
+pub fn parse_bootnodes(bootnodes: &[String]) -> Result<Vec<Bootnode>, NodeError> {
+    bootnodes
+        .iter()
+        .map(|bootnode| bootnode.parse::<Bootnode>().map_err(|e| NodeError::Network(e.to_string())))
+        .collect()
+}
+
+/// Aborts a task when dropped.
+#[derive(Debug)]
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
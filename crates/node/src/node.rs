@@ -1,22 +1,51 @@
 //! Contains the core `Node` runner.
 
-use crate::{Config, NodeError, SyncMode};
-use hilo_driver::HiloDriver;
+use crate::{
+    el_sync::ElSyncer, preflight, AdminRpc, ChallengeEvent, ChallengeVerifier, Config,
+    HealthServer, HealthState, LogFilterHandle, NodeError, RestartPolicy, RollupRpc, RollupState,
+    Supervisor, SyncMode, TaskFuture, CHALLENGE_EVENT_CHANNEL_SIZE, UNSAFE_BLOCK_CHANNEL_SIZE,
+};
+use alloy_eips::{BlockId, BlockNumberOrTag};
+use alloy_primitives::B256;
+use hilo_driver::{DerivationSummary, HiloDriver, StandaloneContext};
+use hilo_engine::EngineClient;
+use parking_lot::Mutex;
+use std::{future::Future, str::FromStr, sync::Arc};
+use tokio::sync::{broadcast, mpsc, watch};
 
 /// The core node runner.
 #[derive(Debug)]
 pub struct Node {
     /// The node config.
     config: Config,
-    /// The [SyncMode] - currently full & checkpoint sync are supported
+    /// The [SyncMode] to run the node in
     sync_mode: SyncMode,
     /// The L2 block hash to begin syncing from
     checkpoint_hash: Option<String>,
+    /// The channel output proposal verification results are published on in challenge sync.
+    challenge_events: broadcast::Sender<ChallengeEvent>,
+    /// The handle used by the admin RPC to change the log filter.
+    log_filter: Option<LogFilterHandle>,
+    /// The state the health checks are evaluated against.
+    health: HealthState,
+    /// The metrics exporter, supervised alongside the other subsystems.
+    metrics: Mutex<Option<TaskFuture>>,
 }
 
 impl From<Config> for Node {
+    /// Creates a [Node] running in [SyncMode::Full], unless another mode is set with
+    /// [Node::with_sync_mode].
     fn from(config: Config) -> Self {
-        Self { config, sync_mode: SyncMode::Full, checkpoint_hash: None }
+        let (challenge_events, _) = broadcast::channel(CHALLENGE_EVENT_CHANNEL_SIZE);
+        Self {
+            config,
+            sync_mode: SyncMode::Full,
+            checkpoint_hash: None,
+            challenge_events,
+            log_filter: None,
+            health: HealthState::default(),
+            metrics: Mutex::new(None),
+        }
     }
 }
 
@@ -33,8 +62,34 @@ impl Node {
         self
     }
 
-    /// Begins the syncing process
+    /// Sets the [LogFilterHandle] the admin RPC uses to change the log level.
+    pub fn with_log_filter(mut self, log_filter: LogFilterHandle) -> Self {
+        self.log_filter = Some(log_filter);
+        self
+    }
+
+    /// Sets the metrics exporter future, which is supervised while the node runs.
+    ///
+    /// Failures of the exporter are logged, without stopping the node.
+    pub fn with_metrics(
+        self,
+        exporter: impl Future<Output = Result<(), NodeError>> + Send + 'static,
+    ) -> Self {
+        *self.metrics.lock() = Some(TaskFuture::new(exporter));
+        self
+    }
+
+    /// Subscribes to the [ChallengeEvent]s published while running in challenge sync.
+    pub fn subscribe_challenges(&self) -> broadcast::Receiver<ChallengeEvent> {
+        self.challenge_events.subscribe()
+    }
+
+    /// Begins the syncing process, after checking that the connected clients
+    /// serve the configured chains unless `skip_preflight` is set.
     pub async fn run(self) -> Result<(), NodeError> {
+        if !self.config.skip_preflight {
+            preflight(&self.config).await?;
+        }
         match self.sync_mode {
             SyncMode::Fast => self.fast_sync().await,
             SyncMode::Challenge => self.challenge_sync().await,
@@ -43,16 +98,38 @@ impl Node {
         }
     }
 
-    /// Fast sync mode - currently unsupported
+    /// Fast sync mode.
+    ///
+    /// Learns the unsafe tip from the trusted `checkpoint_sync_url` RPC and lets the
+    /// execution client snap-sync to it through forkchoice updates. Once the execution
+    /// client has the state, the safe and finalized heads are reconciled with the trusted
+    /// node and derivation starts from the safe head via the [HiloDriver].
     pub async fn fast_sync(&self) -> Result<(), NodeError> {
-        error!("fast sync is not implemented yet");
-        unimplemented!();
+        let safe = ElSyncer::new(&self.config)?.fast_sync().await?;
+        self.supervise(move |mut driver| async move {
+            driver.start_from(safe.number).await.map_err(Into::into)
+        })
+        .await
     }
 
-    /// Fast challenge mode - currently unsupported
+    /// Challenge sync mode.
+    ///
+    /// Derives the chain like full sync, while a [ChallengeVerifier] compares the
+    /// output proposals made on L1 with the output roots of the derived safe blocks.
+    /// Mismatches are logged, counted and published as [ChallengeEvent]s.
     pub async fn challenge_sync(&self) -> Result<(), NodeError> {
-        error!("challenge sync is not implemented yet");
-        unimplemented!();
+        let verifier = ChallengeVerifier::new(&self.config, self.challenge_events.clone())?;
+        self.supervise(move |mut driver| {
+            let events = driver.subscribe();
+            async move {
+                tokio::select! {
+                    res = driver.start() => res?,
+                    res = verifier.run(events) => res?,
+                }
+                Ok(())
+            }
+        })
+        .await
     }
 
     /// Full sync mode.
@@ -61,8 +138,7 @@ impl Node {
     /// the execution client has synced to.
     /// Otherwise syncs from genesis
     pub async fn full_sync(&self) -> Result<(), NodeError> {
-        self.start_driver().await?;
-        Ok(())
+        self.start_driver().await
     }
 
     /// Checkpoint sync mode.
@@ -70,17 +146,162 @@ impl Node {
     /// Syncs the execution client to a given checkpoint block, and then
     /// begins the normal derivation sync process via the [HiloDriver].
     ///
+    /// The checkpoint is the block with the configured `checkpoint_hash`, or the
+    /// latest finalized block of the trusted `checkpoint_sync_url` RPC.
+    ///
     /// Note: the `admin` RPC method must be available on the execution client
-    /// as checkpoint_sync relies on `admin_addPeer`
+    /// for the trusted node to be added as a peer via `admin_addPeer`. If it is
+    /// not, the execution client syncs from its existing peers.
     pub async fn checkpoint_sync(&self) -> Result<(), NodeError> {
-        unimplemented!();
+        let syncer = ElSyncer::new(&self.config)?;
+        let hash = self
+            .checkpoint_hash
+            .as_deref()
+            .map(B256::from_str)
+            .transpose()
+            .map_err(|e| NodeError::InvalidCheckpointHash(e.to_string()))?;
+        let id = hash.map_or(BlockId::Number(BlockNumberOrTag::Finalized), BlockId::hash);
+
+        let checkpoint = syncer.trusted_block(id).await?;
+        info!("Checkpoint syncing to L2 block {} ({})", checkpoint.number, checkpoint.hash);
+        syncer.add_trusted_peer().await;
+        syncer.sync_to(checkpoint, checkpoint.hash, checkpoint.hash).await?;
+
+        self.start_driver().await
     }
 
-    /// Creates and starts the [HiloDriver] which handles the derivation sync process.
-    async fn start_driver(&self) -> Result<(), NodeError> {
+    /// Runs a bounded derivation up to the `to` L2 block and returns a [DerivationSummary].
+    ///
+    /// Derivation starts from the `from` L2 block if provided, otherwise from the
+    /// latest block the execution client has synced to.
+    pub async fn derive(&self, from: Option<u64>, to: u64) -> Result<DerivationSummary, NodeError> {
         let cfg = self.config.clone().into();
         let mut driver = HiloDriver::standalone(cfg).await?;
-        driver.start().await?;
-        Ok(())
+        let summary = match from {
+            Some(from) => driver.derive_range(from, to).await?,
+            None => driver.derive_until(to).await?,
+        };
+        Ok(summary)
+    }
+
+    /// Creates and starts the [HiloDriver] which handles the derivation sync process.
+    async fn start_driver(&self) -> Result<(), NodeError> {
+        self.supervise(|mut driver| async move { driver.start().await.map_err(Into::into) }).await
+    }
+
+    /// Creates the standalone [HiloDriver] and runs it with `run`, alongside the
+    /// configured subsystems, under a [Supervisor] until a subsystem fails fatally
+    /// or the process is interrupted.
+    ///
+    /// The L1 notification task and the driver cannot be restarted without losing
+    /// their state, so their failures are fatal. The p2p network and the RPC and
+    /// health servers are restarted, and the metrics exporter is optional.
+    async fn supervise<F, Fut>(&self, run: F) -> Result<(), NodeError>
+    where
+        F: FnOnce(HiloDriver<StandaloneContext>) -> Fut,
+        Fut: Future<Output = Result<(), NodeError>> + Send + 'static,
+    {
+        let cfg: hilo_driver::Config = self.config.clone().into();
+        let mut ctx =
+            StandaloneContext::with_poll_interval(cfg.l1_rpc_url.clone(), cfg.poll_interval())
+                .await?;
+        let mut supervisor = Supervisor::default();
+        if let Some(handle) = ctx.take_handle() {
+            supervisor.add_once("l1-notifications", RestartPolicy::Fatal, async move {
+                let reason = match handle.await {
+                    Ok(()) => "stopped".to_string(),
+                    Err(e) => e.to_string(),
+                };
+                Err(NodeError::Provider(format!("L1 notification task {reason}")))
+            });
+        }
+        let mut driver = HiloDriver::new(cfg, ctx);
+
+        if let Some(network) = self.config.network.clone() {
+            let (blocks, unsafe_blocks) = mpsc::channel(UNSAFE_BLOCK_CHANNEL_SIZE);
+            let (peers, peer_count) = watch::channel(0);
+            self.health.track_peers(peer_count);
+            driver = driver.with_unsafe_payloads(unsafe_blocks);
+            let (chain_id, devnet) = (self.config.l2_chain_id, self.config.devnet);
+            let p2p_dir = self.config.data_dir.as_ref().map(|dir| dir.join("p2p"));
+            supervisor.add("network", RestartPolicy::default(), move || {
+                let (network, blocks, peers) = (network.clone(), blocks.clone(), peers.clone());
+                let p2p_dir = p2p_dir.clone();
+                async move { network.run(chain_id, devnet, p2p_dir, blocks, peers).await }
+            });
+        }
+
+        if let Some(url) = self.config.rpc_url.clone() {
+            let state = RollupState::default();
+            supervisor.add_once("rpc-state", RestartPolicy::Ignore, {
+                let state = state.clone();
+                let events = driver.subscribe();
+                async move {
+                    state.track(events).await;
+                    Ok(())
+                }
+            });
+            let config = self.config.clone();
+            supervisor.add("rpc", RestartPolicy::default(), move || {
+                let rpc = RollupRpc::new(&config, state.clone());
+                let url = url.clone();
+                async move {
+                    rpc.start(&url).await?.stopped().await;
+                    Err(NodeError::Rpc("server stopped".into()))
+                }
+            });
+        }
+        if let Some(url) = self.config.admin_rpc_url.clone() {
+            let (commands, log_filter) = (driver.command_sender(), self.log_filter.clone());
+            supervisor.add("admin-rpc", RestartPolicy::default(), move || {
+                let admin = AdminRpc::new(commands.clone(), log_filter.clone());
+                let url = url.clone();
+                async move {
+                    admin.start(&url).await?.stopped().await;
+                    Err(NodeError::Rpc("admin server stopped".into()))
+                }
+            });
+        }
+        if let Some(url) = self.config.health_url.clone() {
+            supervisor.add_once("health-state", RestartPolicy::Ignore, {
+                let (state, events) = (self.health.clone(), driver.subscribe());
+                async move {
+                    state.track(events).await;
+                    Ok(())
+                }
+            });
+            let engine = EngineClient::new_http(
+                self.config.l2_engine_url.clone(),
+                self.config.l2_rpc_url.clone(),
+                Arc::new(self.config.rollup_config.clone()),
+                self.config.jwt_secret,
+            );
+            let health = HealthServer::new(
+                self.config.health,
+                self.health.clone(),
+                driver.command_sender(),
+                engine,
+            );
+            supervisor.add("health", RestartPolicy::default(), move || {
+                let (health, url) = (health.clone(), url.clone());
+                async move { health.serve(&url).await }
+            });
+        }
+        if let Some(metrics) = self.metrics.lock().take() {
+            supervisor.add_once("metrics", RestartPolicy::Ignore, metrics);
+        }
+
+        // The driver is added last so it stops first, and no longer drives the engine
+        // while the other subsystems shut down.
+        supervisor.add_once("driver", RestartPolicy::Fatal, run(driver));
+        supervisor.run(shutdown_signal()).await
+    }
+}
+
+/// Resolves once the process receives an interrupt signal.
+async fn shutdown_signal() {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("Failed to listen for interrupt signals: {}", e);
+        std::future::pending::<()>().await;
     }
 }
@@ -0,0 +1,305 @@
+//! Startup consistency checks between the rollup config and the connected clients.
+
+use alloy_eips::BlockId;
+use alloy_primitives::B256;
+use alloy_provider::{network::primitives::BlockTransactionsKind, Provider, ReqwestProvider};
+use hilo_providers_alloy::{BeaconClient, OnlineBeaconClient};
+use std::{fmt::Display, future::Future, time::Duration};
+use url::Url;
+
+use crate::{Config, NodeError};
+
+/// The time each preflight query is given before the client is considered unreachable.
+pub const PREFLIGHT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A mismatch between the configuration and a connected client, found by [preflight].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PreflightError {
+    /// A client could not be queried.
+    #[error("{client} at {url} is unreachable: {error}; check that it is running and the url is correct")]
+    Unreachable {
+        /// The client that was queried.
+        client: &'static str,
+        /// The origin of the client url, without credentials or paths.
+        url: String,
+        /// The error returned by the query.
+        error: String,
+    },
+    /// The L1 RPC serves a different chain than the rollup config settles on.
+    #[error("the L1 RPC at {url} serves chain {actual}, but the rollup config expects L1 chain {expected}; set `--l1-rpc-url` to a node of chain {expected}")]
+    L1ChainId {
+        /// The origin of the L1 RPC url.
+        url: String,
+        /// The chain id reported by the L1 RPC.
+        actual: u64,
+        /// The L1 chain id of the rollup config.
+        expected: u64,
+    },
+    /// An L2 RPC serves a different chain than the configured L2 chain.
+    #[error("the {client} at {url} serves chain {actual}, but the node is configured for L2 chain {expected}; point it at a node of chain {expected} or fix `--l2-chain-id`")]
+    L2ChainId {
+        /// The L2 client that was queried.
+        client: &'static str,
+        /// The origin of the client url.
+        url: String,
+        /// The chain id reported by the client.
+        actual: u64,
+        /// The configured L2 chain id.
+        expected: u64,
+    },
+    /// The execution client was initialized with a different genesis.
+    #[error("the execution client's L2 block {number} has hash {actual}, but the rollup config genesis is {expected}; initialize the execution client with the genesis of this chain")]
+    L2GenesisHash {
+        /// The number of the L2 genesis block.
+        number: u64,
+        /// The hash of the block reported by the execution client.
+        actual: B256,
+        /// The L2 genesis hash of the rollup config.
+        expected: B256,
+    },
+    /// The beacon node follows a different chain than the L1 RPC.
+    #[error("the beacon node at {url} has genesis time {actual}, but L1 chain {l1_chain_id} has genesis time {expected}; set `--l1-beacon-url` to a beacon node of chain {l1_chain_id}")]
+    BeaconGenesis {
+        /// The origin of the beacon url.
+        url: String,
+        /// The L1 chain id of the rollup config.
+        l1_chain_id: u64,
+        /// The genesis time reported by the beacon node.
+        actual: u64,
+        /// The known genesis time of the L1 chain.
+        expected: u64,
+    },
+}
+
+/// Returns the beacon chain genesis time of well-known L1 chains.
+pub const fn beacon_genesis_time(l1_chain_id: u64) -> Option<u64> {
+    match l1_chain_id {
+        1 => Some(1606824023),
+        11155111 => Some(1655733600),
+        17000 => Some(1695902400),
+        _ => None,
+    }
+}
+
+/// Checks that the L1 RPC, beacon node, execution client and trusted L2 RPC serve
+/// the chains the [Config] describes.
+///
+/// All clients are queried concurrently, and every mismatch is logged and returned
+/// at once in [NodeError::Preflight], so they can be fixed in one go.
+pub async fn preflight(config: &Config) -> Result<(), NodeError> {
+    let (l1, beacon, l2, trusted) = tokio::join!(
+        check_l1(config),
+        check_beacon(config),
+        check_l2(config),
+        check_trusted(config)
+    );
+    let failures: Vec<_> = [l1, beacon, l2, trusted].into_iter().flatten().collect();
+    if failures.is_empty() {
+        info!("Preflight checks passed");
+        return Ok(());
+    }
+    for failure in &failures {
+        error!("Preflight check failed: {}", failure);
+    }
+    Err(NodeError::Preflight(failures))
+}
+
+/// Checks that the L1 RPC serves the L1 chain of the rollup config.
+async fn check_l1(config: &Config) -> Vec<PreflightError> {
+    let url = &config.l1_rpc_url;
+    let l1 = ReqwestProvider::new_http(url.clone());
+    let expected = config.rollup_config.l1_chain_id;
+    match query("L1 RPC", url, l1.get_chain_id()).await {
+        Ok(actual) if actual != expected => {
+            vec![PreflightError::L1ChainId { url: origin(url), actual, expected }]
+        }
+        Ok(_) => Vec::new(),
+        Err(e) => vec![e],
+    }
+}
+
+/// Checks that the beacon node follows the L1 chain of the rollup config.
+///
+/// Skipped if an L1 chain spec replaces the beacon node, or if the genesis time of
+/// the L1 chain is unknown.
+async fn check_beacon(config: &Config) -> Vec<PreflightError> {
+    let l1_chain_id = config.rollup_config.l1_chain_id;
+    if config.l1_chain_spec.is_some() {
+        return Vec::new();
+    }
+    let Some(expected) = beacon_genesis_time(l1_chain_id) else {
+        debug!("Skipping the beacon genesis check for unknown L1 chain {}", l1_chain_id);
+        return Vec::new();
+    };
+
+    let url = &config.l1_beacon_url;
+    let beacon = OnlineBeaconClient::new_http(url.to_string());
+    match query("beacon node", url, beacon.beacon_genesis()).await {
+        Ok(genesis) if genesis.data.genesis_time != expected => {
+            let actual = genesis.data.genesis_time;
+            vec![PreflightError::BeaconGenesis { url: origin(url), l1_chain_id, actual, expected }]
+        }
+        Ok(_) => Vec::new(),
+        Err(e) => vec![e],
+    }
+}
+
+/// Checks that the execution client serves the L2 chain and starts from the L2
+/// genesis of the rollup config.
+async fn check_l2(config: &Config) -> Vec<PreflightError> {
+    let url = &config.l2_rpc_url;
+    let l2 = ReqwestProvider::new_http(url.clone());
+    let mut failures = Vec::new();
+    if let Err(e) = check_l2_chain_id("execution client", url, &l2, config.l2_chain_id).await {
+        failures.push(e);
+        return failures;
+    }
+
+    let genesis = config.rollup_config.genesis.l2;
+    let block = l2.get_block(BlockId::number(genesis.number), BlockTransactionsKind::Hashes);
+    match query("execution client", url, block).await {
+        Ok(Some(block)) if block.header.hash != genesis.hash => {
+            failures.push(PreflightError::L2GenesisHash {
+                number: genesis.number,
+                actual: block.header.hash,
+                expected: genesis.hash,
+            });
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            // Execution clients that snap sync may not have the genesis block yet.
+            warn!("Execution client does not have the L2 genesis block {}", genesis.number);
+        }
+        Err(e) => failures.push(e),
+    }
+    failures
+}
+
+/// Checks that the trusted L2 RPC used by checkpoint and fast sync serves the L2 chain.
+async fn check_trusted(config: &Config) -> Vec<PreflightError> {
+    let Some(url) = &config.checkpoint_sync_url else { return Vec::new() };
+    let trusted = ReqwestProvider::new_http(url.clone());
+    check_l2_chain_id("checkpoint sync RPC", url, &trusted, config.l2_chain_id)
+        .await
+        .err()
+        .into_iter()
+        .collect()
+}
+
+/// Checks that an L2 client serves the `expected` chain.
+async fn check_l2_chain_id(
+    client: &'static str,
+    url: &Url,
+    provider: &ReqwestProvider,
+    expected: u64,
+) -> Result<(), PreflightError> {
+    let actual = query(client, url, provider.get_chain_id()).await?;
+    if actual != expected {
+        return Err(PreflightError::L2ChainId { client, url: origin(url), actual, expected });
+    }
+    Ok(())
+}
+
+/// Runs a query against a client, bounded by the [PREFLIGHT_TIMEOUT].
+async fn query<T, E: Display>(
+    client: &'static str,
+    url: &Url,
+    fut: impl Future<Output = Result<T, E>>,
+) -> Result<T, PreflightError> {
+    let error = match tokio::time::timeout(PREFLIGHT_TIMEOUT, fut).await {
+        Ok(Ok(value)) => return Ok(value),
+        Ok(Err(e)) => e.to_string(),
+        Err(_) => format!("no response within {}s", PREFLIGHT_TIMEOUT.as_secs()),
+    };
+    Err(PreflightError::Unreachable { client, url: origin(url), error })
+}
+
+/// Returns the origin of a url, leaving out credentials and API keys in its path or query.
+fn origin(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SyncMode;
+    use alloy_rpc_types_eth::{Block, Header};
+    use jsonrpsee::{
+        server::{Server, ServerHandle},
+        RpcModule,
+    };
+    use op_alloy_registry::ROLLUP_CONFIGS;
+
+    /// Starts a stand-in client serving the given chain id and blocks with the given hash.
+    async fn stand_in(chain_id: u64, hash: B256) -> (Url, ServerHandle) {
+        let mut module = RpcModule::new(());
+        module.register_method("eth_chainId", move |_, _, _| format!("{chain_id:#x}")).unwrap();
+        module
+            .register_method("eth_getBlockByNumber", move |_, _, _| {
+                let header = Header { hash, ..Default::default() };
+                Block { header, ..Default::default() }
+            })
+            .unwrap();
+        let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", server.local_addr().unwrap())).unwrap();
+        (url, server.start(module))
+    }
+
+    #[tokio::test]
+    async fn test_preflight_reports_all_mismatches() {
+        let rollup_config = ROLLUP_CONFIGS.get(&10).unwrap().clone();
+        let genesis = rollup_config.genesis.l2.hash;
+        let (l1_url, _l1) = stand_in(11155111, B256::ZERO).await;
+        let (l2_url, _l2) = stand_in(10, B256::with_last_byte(1)).await;
+        let (trusted_url, _trusted) = stand_in(8453, genesis).await;
+
+        let mut config = Config {
+            l1_rpc_url: l1_url.clone(),
+            l1_beacon_url: Url::parse("http://127.0.0.1:1").unwrap(),
+            l2_rpc_url: l2_url,
+            l2_engine_url: Url::parse("http://127.0.0.1:1").unwrap(),
+            rollup_config,
+            checkpoint_sync_url: Some(trusted_url.clone()),
+            sync_mode: SyncMode::Checkpoint,
+            cache_size: 16,
+            ..Config::test_default()
+        };
+
+        let Err(NodeError::Preflight(failures)) = preflight(&config).await else {
+            panic!("preflight should fail");
+        };
+        assert_eq!(failures.len(), 4);
+        assert!(failures.contains(&PreflightError::L1ChainId {
+            url: origin(&l1_url),
+            actual: 11155111,
+            expected: 1,
+        }));
+        assert!(failures.iter().any(
+            |f| matches!(f, PreflightError::Unreachable { client, .. } if *client == "beacon node")
+        ));
+        assert!(failures.contains(&PreflightError::L2GenesisHash {
+            number: config.rollup_config.genesis.l2.number,
+            actual: B256::with_last_byte(1),
+            expected: genesis,
+        }));
+        assert!(failures.contains(&PreflightError::L2ChainId {
+            client: "checkpoint sync RPC",
+            url: origin(&trusted_url),
+            actual: 8453,
+            expected: 10,
+        }));
+
+        // Pointing the clients at the right chains passes.
+        let (l1_url, _l1) = stand_in(1, B256::ZERO).await;
+        let (l2_url, _l2) = stand_in(10, genesis).await;
+        config.l1_rpc_url = l1_url;
+        config.l2_rpc_url = l2_url;
+        config.checkpoint_sync_url = None;
+        config.l1_chain_spec = Some(hilo_driver::L1ChainSpec {
+            chain_id: 1,
+            genesis_time: beacon_genesis_time(1).unwrap(),
+            seconds_per_slot: 12,
+        });
+        assert!(preflight(&config).await.is_ok());
+    }
+}
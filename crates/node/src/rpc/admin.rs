@@ -0,0 +1,197 @@
+//! The admin JSON-RPC namespace for operational control of a running node.
+
+use alloy_rpc_types_engine::{ForkchoiceState, PayloadStatus};
+use hilo_driver::DriverCommand;
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    server::{Server, ServerHandle},
+    types::ErrorObjectOwned,
+};
+use std::{fmt, sync::Arc};
+use tokio::sync::{mpsc, oneshot};
+use url::Url;
+
+use super::{bind_addr, internal};
+use crate::NodeError;
+
+/// A handle to change the log filter of the running node, such as `info,hilo=debug`.
+///
+/// The node does not own the tracing subscriber, so the binary provides the handle.
+#[derive(Clone)]
+pub struct LogFilterHandle(Arc<dyn Fn(&str) -> Result<(), String> + Send + Sync>);
+
+impl LogFilterHandle {
+    /// Creates a new [LogFilterHandle] from a function applying filter directives.
+    pub fn new(set: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(set))
+    }
+
+    /// Applies the given filter directives.
+    pub fn set(&self, directives: &str) -> Result<(), String> {
+        (self.0)(directives)
+    }
+}
+
+impl fmt::Debug for LogFilterHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogFilterHandle").finish_non_exhaustive()
+    }
+}
+
+/// The admin RPC, giving operators control over a running node.
+#[rpc(server, namespace = "admin")]
+pub trait AdminApi {
+    /// Resets the derivation pipeline to the current safe head.
+    #[method(name = "resetDerivationPipeline")]
+    async fn reset_derivation_pipeline(&self) -> RpcResult<()>;
+
+    /// Sets the log filter, e.g. `debug` or `info,hilo=trace`.
+    #[method(name = "setLogLevel")]
+    async fn set_log_level(&self, level: String) -> RpcResult<()>;
+
+    /// Pauses derivation.
+    #[method(name = "pauseDerivation")]
+    async fn pause_derivation(&self) -> RpcResult<()>;
+
+    /// Resumes derivation.
+    #[method(name = "resumeDerivation")]
+    async fn resume_derivation(&self) -> RpcResult<()>;
+
+    /// Sends a forkchoice update to the execution client, using the engine
+    /// controller's heads if no state is given.
+    #[method(name = "forceForkchoice")]
+    async fn force_forkchoice(&self, state: Option<ForkchoiceState>) -> RpcResult<PayloadStatus>;
+}
+
+/// The [AdminApiServer] implementation, forwarding commands to the driver.
+#[derive(Debug)]
+pub struct AdminRpc {
+    /// The sender [DriverCommand]s are issued on.
+    commands: mpsc::Sender<DriverCommand>,
+    /// The handle used to change the log filter, if provided.
+    log_filter: Option<LogFilterHandle>,
+}
+
+impl AdminRpc {
+    /// Creates a new [AdminRpc] issuing commands on the given sender.
+    pub const fn new(
+        commands: mpsc::Sender<DriverCommand>,
+        log_filter: Option<LogFilterHandle>,
+    ) -> Self {
+        Self { commands, log_filter }
+    }
+
+    /// Starts serving the admin RPC over HTTP and WebSocket at the given URL.
+    ///
+    /// The server stops when the returned [ServerHandle] is dropped.
+    pub async fn start(self, url: &Url) -> Result<ServerHandle, NodeError> {
+        let server = Server::builder()
+            .build(bind_addr(url)?)
+            .await
+            .map_err(|e| NodeError::Rpc(e.to_string()))?;
+        let addr = server.local_addr().map_err(|e| NodeError::Rpc(e.to_string()))?;
+        info!("Admin RPC listening on {}", addr);
+        Ok(server.start(self.into_rpc()))
+    }
+
+    /// Sends a command to the driver.
+    async fn send(&self, command: DriverCommand) -> Result<(), ErrorObjectOwned> {
+        self.commands.send(command).await.map_err(|_| internal("driver is not running"))
+    }
+}
+
+#[async_trait]
+impl AdminApiServer for AdminRpc {
+    async fn reset_derivation_pipeline(&self) -> RpcResult<()> {
+        self.send(DriverCommand::ResetPipeline).await
+    }
+
+    async fn set_log_level(&self, level: String) -> RpcResult<()> {
+        let log_filter =
+            self.log_filter.as_ref().ok_or_else(|| internal("log level is not adjustable"))?;
+        log_filter.set(&level).map_err(internal)?;
+        info!("Log filter set to {}", level);
+        Ok(())
+    }
+
+    async fn pause_derivation(&self) -> RpcResult<()> {
+        self.send(DriverCommand::PauseDerivation).await
+    }
+
+    async fn resume_derivation(&self) -> RpcResult<()> {
+        self.send(DriverCommand::ResumeDerivation).await
+    }
+
+    async fn force_forkchoice(&self, state: Option<ForkchoiceState>) -> RpcResult<PayloadStatus> {
+        let (reply, response) = oneshot::channel();
+        self.send(DriverCommand::ForceForkchoice { state, reply }).await?;
+        response.await.map_err(|_| internal("driver dropped the request"))?.map_err(internal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_rpc_types_engine::PayloadStatusEnum;
+    use parking_lot::Mutex;
+
+    #[tokio::test]
+    async fn test_commands_forwarded_to_driver() {
+        let (sender, mut commands) = mpsc::channel(4);
+        let admin = AdminRpc::new(sender, None);
+
+        admin.pause_derivation().await.unwrap();
+        admin.reset_derivation_pipeline().await.unwrap();
+        admin.resume_derivation().await.unwrap();
+        assert!(matches!(commands.recv().await, Some(DriverCommand::PauseDerivation)));
+        assert!(matches!(commands.recv().await, Some(DriverCommand::ResetPipeline)));
+        assert!(matches!(commands.recv().await, Some(DriverCommand::ResumeDerivation)));
+    }
+
+    #[tokio::test]
+    async fn test_force_forkchoice_returns_driver_reply() {
+        let (sender, mut commands) = mpsc::channel(4);
+        let admin = AdminRpc::new(sender, None);
+        tokio::spawn(async move {
+            if let Some(DriverCommand::ForceForkchoice { state, reply }) = commands.recv().await {
+                assert!(state.is_none());
+                _ = reply.send(Ok(PayloadStatus::from_status(PayloadStatusEnum::Syncing)));
+            }
+        });
+
+        let status = admin.force_forkchoice(None).await.unwrap();
+        assert_eq!(status.status, PayloadStatusEnum::Syncing);
+    }
+
+    #[tokio::test]
+    async fn test_commands_fail_without_driver() {
+        let (sender, commands) = mpsc::channel(4);
+        drop(commands);
+        let admin = AdminRpc::new(sender, None);
+        assert!(admin.reset_derivation_pipeline().await.is_err());
+        assert!(admin.force_forkchoice(None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_set_log_level() {
+        let (sender, _commands) = mpsc::channel(4);
+        assert!(AdminRpc::new(sender.clone(), None).set_log_level("debug".into()).await.is_err());
+
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let log_filter = LogFilterHandle::new({
+            let applied = applied.clone();
+            move |directives| {
+                if directives.is_empty() {
+                    return Err("empty filter".to_string());
+                }
+                applied.lock().push(directives.to_string());
+                Ok(())
+            }
+        });
+        let admin = AdminRpc::new(sender, Some(log_filter));
+        admin.set_log_level("info,hilo=debug".into()).await.unwrap();
+        assert!(admin.set_log_level(String::new()).await.is_err());
+        assert_eq!(*applied.lock(), ["info,hilo=debug"]);
+    }
+}
@@ -0,0 +1,218 @@
+//! The rollup node JSON-RPC server.
+
+use alloy_eips::{BlockId, BlockNumberOrTag};
+use alloy_primitives::{B256, U64};
+use alloy_provider::{network::primitives::BlockTransactionsKind, Provider, ReqwestProvider};
+use hilo_providers_alloy::AlloyL2ChainProvider;
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    server::{Server, ServerHandle},
+    types::{error::INTERNAL_ERROR_CODE, ErrorObject, ErrorObjectOwned},
+};
+use op_alloy_genesis::RollupConfig;
+use op_alloy_protocol::{BatchValidationProvider, BlockInfo};
+use std::{fmt::Display, net::SocketAddr, sync::Arc};
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::{challenge::L2_TO_L1_MESSAGE_PASSER, output_root_v0, Config, NodeError};
+
+mod admin;
+pub use admin::{AdminApiServer, AdminRpc, LogFilterHandle};
+
+mod state;
+pub use state::{RollupState, MAX_SAFE_HEAD_ENTRIES};
+
+mod types;
+pub use types::{OutputResponse, SafeHeadResponse, SyncStatus};
+
+/// The version reported by `optimism_version`.
+pub const NODE_VERSION: &str = concat!("hilo/v", env!("CARGO_PKG_VERSION"));
+
+/// The rollup node RPC, compatible with the `optimism` namespace of the op-node.
+#[rpc(server, namespace = "optimism")]
+pub trait RollupNodeApi {
+    /// Returns the sync status of the node.
+    #[method(name = "syncStatus")]
+    async fn sync_status(&self) -> RpcResult<SyncStatus>;
+
+    /// Returns the output root of the given L2 block.
+    #[method(name = "outputAtBlock")]
+    async fn output_at_block(&self, block: BlockNumberOrTag) -> RpcResult<OutputResponse>;
+
+    /// Returns the rollup config of the node.
+    #[method(name = "rollupConfig")]
+    async fn rollup_config(&self) -> RpcResult<RollupConfig>;
+
+    /// Returns the version of the node.
+    #[method(name = "version")]
+    async fn version(&self) -> RpcResult<String>;
+
+    /// Returns the latest safe head derived from an L1 block at or before the given one.
+    #[method(name = "safeHeadAtL1Block")]
+    async fn safe_head_at_l1_block(&self, l1_block: U64) -> RpcResult<SafeHeadResponse>;
+}
+
+/// The [RollupNodeApiServer] implementation.
+///
+/// Derivation progress is read from the [RollupState], while block heads are read
+/// from the L1 and L2 execution clients directly.
+#[derive(Debug)]
+pub struct RollupRpc {
+    /// The rollup config.
+    rollup_config: Arc<RollupConfig>,
+    /// The driver state.
+    state: RollupState,
+    /// The L1 execution client.
+    l1: ReqwestProvider,
+    /// The L2 execution client.
+    l2: ReqwestProvider,
+    /// The L2 chain provider used to construct L2 block infos.
+    l2_chain: Mutex<AlloyL2ChainProvider>,
+}
+
+impl RollupRpc {
+    /// Creates a new [RollupRpc] from the node [Config] and the [RollupState].
+    pub fn new(config: &Config, state: RollupState) -> Self {
+        let rollup_config = Arc::new(config.rollup_config.clone());
+        let l2 = ReqwestProvider::new_http(config.l2_rpc_url.clone());
+        let l2_chain = AlloyL2ChainProvider::new(l2.clone(), rollup_config.clone());
+        Self {
+            rollup_config,
+            state,
+            l1: ReqwestProvider::new_http(config.l1_rpc_url.clone()),
+            l2,
+            l2_chain: Mutex::new(l2_chain),
+        }
+    }
+
+    /// Starts serving the RPC over HTTP and WebSocket at the given URL.
+    ///
+    /// The server stops when the returned [ServerHandle] is dropped.
+    pub async fn start(self, url: &Url) -> Result<ServerHandle, NodeError> {
+        let server = Server::builder()
+            .build(bind_addr(url)?)
+            .await
+            .map_err(|e| NodeError::Rpc(e.to_string()))?;
+        let addr = server.local_addr().map_err(|e| NodeError::Rpc(e.to_string()))?;
+        info!("Rollup node RPC listening on {}", addr);
+        Ok(server.start(self.into_rpc()))
+    }
+
+    /// Fetches an L1 block from the L1 execution client.
+    async fn l1_block(&self, tag: BlockNumberOrTag) -> Result<BlockInfo, ErrorObjectOwned> {
+        let block = self
+            .l1
+            .get_block(BlockId::Number(tag), BlockTransactionsKind::Hashes)
+            .await
+            .map_err(internal)?
+            .ok_or_else(|| internal(format!("L1 block {tag} not found")))?;
+        Ok(BlockInfo {
+            hash: block.header.hash,
+            number: block.header.number,
+            parent_hash: block.header.parent_hash,
+            timestamp: block.header.timestamp,
+        })
+    }
+
+    /// Resolves an L2 block number or tag to a block number.
+    async fn l2_number(&self, tag: BlockNumberOrTag) -> Result<u64, ErrorObjectOwned> {
+        if let BlockNumberOrTag::Number(number) = tag {
+            return Ok(number);
+        }
+        let block = self
+            .l2
+            .get_block(BlockId::Number(tag), BlockTransactionsKind::Hashes)
+            .await
+            .map_err(internal)?
+            .ok_or_else(|| internal(format!("L2 block {tag} not found")))?;
+        Ok(block.header.number)
+    }
+}
+
+#[async_trait]
+impl RollupNodeApiServer for RollupRpc {
+    async fn sync_status(&self) -> RpcResult<SyncStatus> {
+        let head_l1 = self.l1_block(BlockNumberOrTag::Latest).await?;
+        let safe_l1 = self.l1_block(BlockNumberOrTag::Safe).await?;
+        let finalized_l1 = self.l1_block(BlockNumberOrTag::Finalized).await?;
+
+        let unsafe_number = self.l2_number(BlockNumberOrTag::Latest).await?;
+        let finalized_number = self.l2_number(BlockNumberOrTag::Finalized).await?;
+        let mut l2_chain = self.l2_chain.lock().await;
+        let unsafe_l2 = l2_chain.l2_block_info_by_number(unsafe_number).await.map_err(internal)?;
+        let finalized_l2 =
+            l2_chain.l2_block_info_by_number(finalized_number).await.map_err(internal)?;
+
+        let safe_l2 = self.state.safe_l2();
+        Ok(SyncStatus {
+            current_l1: self.state.current_l1(),
+            current_l1_finalized: finalized_l1,
+            head_l1,
+            safe_l1,
+            finalized_l1,
+            unsafe_l2,
+            safe_l2,
+            finalized_l2,
+            pending_safe_l2: safe_l2,
+        })
+    }
+
+    async fn output_at_block(&self, block: BlockNumberOrTag) -> RpcResult<OutputResponse> {
+        let number = self.l2_number(block).await?;
+        let block_ref =
+            self.l2_chain.lock().await.l2_block_info_by_number(number).await.map_err(internal)?;
+        let header = self
+            .l2
+            .get_block(BlockId::number(number), BlockTransactionsKind::Hashes)
+            .await
+            .map_err(internal)?
+            .ok_or_else(|| internal(format!("L2 block {number} not found")))?
+            .header;
+        let proof = self
+            .l2
+            .get_proof(L2_TO_L1_MESSAGE_PASSER, Vec::new())
+            .block_id(BlockId::number(number))
+            .await
+            .map_err(internal)?;
+
+        Ok(OutputResponse {
+            version: B256::ZERO,
+            output_root: output_root_v0(header.state_root, proof.storage_hash, header.hash),
+            block_ref,
+            withdrawal_storage_root: proof.storage_hash,
+            state_root: header.state_root,
+            sync_status: self.sync_status().await?,
+        })
+    }
+
+    async fn rollup_config(&self) -> RpcResult<RollupConfig> {
+        Ok((*self.rollup_config).clone())
+    }
+
+    async fn version(&self) -> RpcResult<String> {
+        Ok(NODE_VERSION.to_string())
+    }
+
+    async fn safe_head_at_l1_block(&self, l1_block: U64) -> RpcResult<SafeHeadResponse> {
+        let number = l1_block.to::<u64>();
+        self.state
+            .safe_head_at(number)
+            .ok_or_else(|| internal(format!("no safe head known for L1 block {number}")))
+    }
+}
+
+/// Resolves the socket address to bind an RPC server to from its URL.
+pub(crate) fn bind_addr(url: &Url) -> Result<SocketAddr, NodeError> {
+    url.socket_addrs(|| None)
+        .map_err(|e| NodeError::Rpc(e.to_string()))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| NodeError::Rpc(format!("invalid rpc url: {url}")))
+}
+
+/// Maps an error to an internal JSON-RPC error.
+fn internal(e: impl Display) -> ErrorObjectOwned {
+    ErrorObject::owned(INTERNAL_ERROR_CODE, e.to_string(), None::<()>)
+}
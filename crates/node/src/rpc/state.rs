@@ -0,0 +1,136 @@
+//! Driver state tracked for the rollup node RPC.
+
+use hilo_driver::DriverEvent;
+use op_alloy_protocol::{BlockInfo, L2BlockInfo};
+use parking_lot::RwLock;
+use std::{collections::BTreeMap, sync::Arc};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use super::SafeHeadResponse;
+
+/// The maximum number of L1 blocks the safe head is remembered for.
+pub const MAX_SAFE_HEAD_ENTRIES: usize = 16_384;
+
+/// The derivation state of the driver.
+#[derive(Debug, Default)]
+struct State {
+    /// The L1 origin of the derivation pipeline.
+    current_l1: BlockInfo,
+    /// The L2 safe head.
+    safe_l2: L2BlockInfo,
+    /// The latest safe head derived from each L1 block, keyed by L1 block number.
+    safe_heads: BTreeMap<u64, SafeHeadResponse>,
+}
+
+/// The driver state served over RPC, updated from [DriverEvent]s.
+///
+/// The driver owns its pipeline and engine, so the RPC follows its progress
+/// through the events it publishes instead.
+#[derive(Debug, Clone, Default)]
+pub struct RollupState(Arc<RwLock<State>>);
+
+impl RollupState {
+    /// Follows the given driver events until the channel is closed.
+    pub async fn track(self, mut events: broadcast::Receiver<DriverEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(event) => self.apply(&event),
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!("RPC state skipped {} driver events", skipped);
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Applies a [DriverEvent] to the state.
+    pub fn apply(&self, event: &DriverEvent) {
+        let mut state = self.0.write();
+        match event {
+            DriverEvent::L1OriginAdvanced(origin) => state.current_l1 = *origin,
+            DriverEvent::SafeHeadUpdated(head) => {
+                state.safe_l2 = *head;
+                let l1_block = state.current_l1.id();
+                let safe_head = head.block_info.id();
+                state.safe_heads.insert(l1_block.number, SafeHeadResponse { l1_block, safe_head });
+                while state.safe_heads.len() > MAX_SAFE_HEAD_ENTRIES {
+                    state.safe_heads.pop_first();
+                }
+            }
+            DriverEvent::PipelineReset { l1_origin, l2_safe_head } => {
+                state.current_l1 = *l1_origin;
+                state.safe_l2 = *l2_safe_head;
+                // Safe heads derived from L1 blocks past the reset point may have been reorged.
+                state.safe_heads.split_off(&(l1_origin.number + 1));
+            }
+            DriverEvent::AttributesDerived(_)
+            | DriverEvent::FinalizedUpdated(_)
+            | DriverEvent::L1HeadReceived(_) => {}
+        }
+    }
+
+    /// Returns the L1 origin of the derivation pipeline.
+    pub fn current_l1(&self) -> BlockInfo {
+        self.0.read().current_l1
+    }
+
+    /// Returns the L2 safe head.
+    pub fn safe_l2(&self) -> L2BlockInfo {
+        self.0.read().safe_l2
+    }
+
+    /// Returns the latest safe head derived from an L1 block at or before the given number.
+    pub fn safe_head_at(&self, l1_number: u64) -> Option<SafeHeadResponse> {
+        self.0.read().safe_heads.range(..=l1_number).next_back().map(|(_, head)| *head)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::B256;
+
+    fn l1(number: u64) -> BlockInfo {
+        BlockInfo { number, hash: B256::with_last_byte(number as u8), ..Default::default() }
+    }
+
+    fn l2(number: u64) -> L2BlockInfo {
+        L2BlockInfo {
+            block_info: BlockInfo {
+                number,
+                hash: B256::with_last_byte(number as u8),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_safe_head_at() {
+        let state = RollupState::default();
+        state.apply(&DriverEvent::L1OriginAdvanced(l1(10)));
+        state.apply(&DriverEvent::SafeHeadUpdated(l2(100)));
+        state.apply(&DriverEvent::SafeHeadUpdated(l2(101)));
+        state.apply(&DriverEvent::L1OriginAdvanced(l1(12)));
+        state.apply(&DriverEvent::SafeHeadUpdated(l2(106)));
+
+        assert_eq!(state.current_l1(), l1(12));
+        assert_eq!(state.safe_l2(), l2(106));
+        assert_eq!(state.safe_head_at(9), None);
+        assert_eq!(state.safe_head_at(11).unwrap().safe_head, l2(101).block_info.id());
+        assert_eq!(state.safe_head_at(12).unwrap().l1_block, l1(12).id());
+    }
+
+    #[test]
+    fn test_reset_drops_reorged_safe_heads() {
+        let state = RollupState::default();
+        state.apply(&DriverEvent::L1OriginAdvanced(l1(10)));
+        state.apply(&DriverEvent::SafeHeadUpdated(l2(100)));
+        state.apply(&DriverEvent::L1OriginAdvanced(l1(12)));
+        state.apply(&DriverEvent::SafeHeadUpdated(l2(106)));
+        state.apply(&DriverEvent::PipelineReset { l1_origin: l1(11), l2_safe_head: l2(100) });
+
+        assert_eq!(state.safe_l2(), l2(100));
+        assert_eq!(state.safe_head_at(20).unwrap().l1_block, l1(10).id());
+    }
+}
@@ -0,0 +1,59 @@
+//! Response types of the rollup node RPC.
+
+use alloy_eips::BlockNumHash;
+use alloy_primitives::B256;
+use op_alloy_protocol::{BlockInfo, L2BlockInfo};
+use serde::{Deserialize, Serialize};
+
+/// The sync status of the node, as returned by `optimism_syncStatus`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncStatus {
+    /// The L1 block the derivation pipeline is currently processing.
+    pub current_l1: BlockInfo,
+    /// The finalized L1 block at the time the pipeline started processing `current_l1`.
+    /// Kept for compatibility, this is the current finalized L1 block.
+    pub current_l1_finalized: BlockInfo,
+    /// The latest L1 block.
+    pub head_l1: BlockInfo,
+    /// The latest safe L1 block.
+    pub safe_l1: BlockInfo,
+    /// The latest finalized L1 block.
+    pub finalized_l1: BlockInfo,
+    /// The unsafe L2 head.
+    pub unsafe_l2: L2BlockInfo,
+    /// The safe L2 head.
+    pub safe_l2: L2BlockInfo,
+    /// The finalized L2 head.
+    pub finalized_l2: L2BlockInfo,
+    /// The pending safe L2 head. Equal to the safe head, since attributes are
+    /// applied as soon as they are derived.
+    pub pending_safe_l2: L2BlockInfo,
+}
+
+/// The output of an L2 block, as returned by `optimism_outputAtBlock`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputResponse {
+    /// The output root version.
+    pub version: B256,
+    /// The output root.
+    pub output_root: B256,
+    /// The L2 block the output root commits to.
+    pub block_ref: L2BlockInfo,
+    /// The storage root of the `L2ToL1MessagePasser`.
+    pub withdrawal_storage_root: B256,
+    /// The state root of the L2 block.
+    pub state_root: B256,
+    /// The sync status of the node.
+    pub sync_status: SyncStatus,
+}
+
+/// The safe head derived from an L1 block, as returned by `optimism_safeHeadAtL1Block`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeHeadResponse {
+    /// The L1 block the safe head was derived from.
+    pub l1_block: BlockNumHash,
+    /// The safe L2 head.
+    pub safe_head: BlockNumHash,
+}
@@ -0,0 +1,353 @@
+//! Supervision of the node's subsystem tasks.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::task::{AbortHandle, Id, JoinError, JoinSet};
+
+use crate::NodeError;
+
+/// The default number of times a restartable subsystem is restarted before
+/// its failure is considered fatal.
+pub const DEFAULT_MAX_RESTARTS: u32 = 5;
+
+/// The default delay before a failed subsystem is restarted.
+pub const DEFAULT_RESTART_BACKOFF: Duration = Duration::from_secs(5);
+
+/// A boxed subsystem future, resolving when the subsystem stops.
+pub struct TaskFuture(Pin<Box<dyn Future<Output = Result<(), NodeError>> + Send>>);
+
+impl TaskFuture {
+    /// Boxes the given subsystem future.
+    pub fn new(fut: impl Future<Output = Result<(), NodeError>> + Send + 'static) -> Self {
+        Self(Box::pin(fut))
+    }
+}
+
+impl Future for TaskFuture {
+    type Output = Result<(), NodeError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.as_mut().poll(cx)
+    }
+}
+
+impl fmt::Debug for TaskFuture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskFuture").finish_non_exhaustive()
+    }
+}
+
+/// What the [Supervisor] does when a subsystem fails, either by returning an
+/// error or by panicking.
+///
+/// Subsystems that return `Ok` are considered finished and are not restarted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// The failure stops the node.
+    Fatal,
+    /// The subsystem is restarted after `backoff`, and the failure stops the node
+    /// once it has been restarted `max_restarts` times.
+    Restart {
+        /// The number of restarts before a failure is fatal.
+        max_restarts: u32,
+        /// The delay before restarting.
+        backoff: Duration,
+    },
+    /// The failure is logged and the subsystem is not restarted.
+    Ignore,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self::Restart { max_restarts: DEFAULT_MAX_RESTARTS, backoff: DEFAULT_RESTART_BACKOFF }
+    }
+}
+
+/// Creates the future of a subsystem, or `None` if it cannot be started again.
+type Factory = Box<dyn FnMut() -> Option<TaskFuture> + Send>;
+
+/// A subsystem owned by the [Supervisor].
+struct Task {
+    /// The name of the subsystem, used in logs and errors.
+    name: &'static str,
+    /// What to do when the subsystem fails.
+    policy: RestartPolicy,
+    /// Creates the subsystem future on start and on every restart.
+    factory: Factory,
+    /// The number of times the subsystem was restarted.
+    restarts: u32,
+    /// The handle of the running subsystem.
+    running: Option<AbortHandle>,
+}
+
+/// Owns the subsystem tasks of the node.
+///
+/// The supervisor restarts failed subsystems according to their [RestartPolicy],
+/// stops the node on fatal failures, and on shutdown stops the subsystems one at a
+/// time in the reverse order they were added.
+#[derive(Default)]
+pub struct Supervisor {
+    /// The subsystems, in the order they were added.
+    tasks: Vec<Task>,
+    /// The running subsystem futures.
+    set: JoinSet<Result<(), NodeError>>,
+    /// The index of the subsystem each running future belongs to.
+    ids: HashMap<Id, usize>,
+}
+
+impl fmt::Debug for Supervisor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Supervisor")
+            .field("tasks", &self.tasks.iter().map(|task| task.name).collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Supervisor {
+    /// Adds a subsystem, created by `factory` on start and on every restart.
+    pub fn add<F, Fut>(&mut self, name: &'static str, policy: RestartPolicy, mut factory: F)
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), NodeError>> + Send + 'static,
+    {
+        self.push(name, policy, Box::new(move || Some(TaskFuture::new(factory()))));
+    }
+
+    /// Adds a subsystem that can only be started once.
+    ///
+    /// With [RestartPolicy::Restart], its failure is fatal since it cannot be restarted.
+    pub fn add_once(
+        &mut self,
+        name: &'static str,
+        policy: RestartPolicy,
+        fut: impl Future<Output = Result<(), NodeError>> + Send + 'static,
+    ) {
+        let mut fut = Some(TaskFuture::new(fut));
+        self.push(name, policy, Box::new(move || fut.take()));
+    }
+
+    /// Adds a subsystem task.
+    fn push(&mut self, name: &'static str, policy: RestartPolicy, factory: Factory) {
+        self.tasks.push(Task { name, policy, factory, restarts: 0, running: None });
+    }
+
+    /// Starts all subsystems and supervises them until one fails fatally or
+    /// `shutdown` resolves, then stops them in order.
+    ///
+    /// Returns the fatal failure, if any.
+    pub async fn run(mut self, shutdown: impl Future<Output = ()>) -> Result<(), NodeError> {
+        for index in 0..self.tasks.len() {
+            if let Some(fut) = (self.tasks[index].factory)() {
+                self.spawn(index, fut);
+            }
+        }
+
+        tokio::pin!(shutdown);
+        let result = loop {
+            if self.set.is_empty() {
+                info!("All subsystems finished");
+                break Ok(());
+            }
+            tokio::select! {
+                _ = &mut shutdown => {
+                    info!("Shutdown requested");
+                    break Ok(());
+                }
+                Some(joined) = self.set.join_next_with_id() => {
+                    let (id, result) = match joined {
+                        Ok((id, result)) => (id, result),
+                        Err(e) => (e.id(), Err(panicked(e))),
+                    };
+                    let Some(index) = self.ids.remove(&id) else { continue };
+                    self.tasks[index].running = None;
+                    if let Err(e) = self.handle_exit(index, result) {
+                        break Err(e);
+                    }
+                }
+            }
+        };
+
+        self.shutdown().await;
+        result
+    }
+
+    /// Applies the [RestartPolicy] of a subsystem that exited, returning an error
+    /// if its failure is fatal.
+    fn handle_exit(
+        &mut self,
+        index: usize,
+        result: Result<(), NodeError>,
+    ) -> Result<(), NodeError> {
+        let task = &mut self.tasks[index];
+        let name = task.name;
+        let Err(e) = result else {
+            info!("Subsystem {} finished", name);
+            return Ok(());
+        };
+
+        match task.policy {
+            RestartPolicy::Fatal => {
+                error!("Subsystem {} failed: {}", name, e);
+                Err(NodeError::TaskFailed(name, e.to_string()))
+            }
+            RestartPolicy::Ignore => {
+                warn!("Subsystem {} failed, continuing without it: {}", name, e);
+                Ok(())
+            }
+            RestartPolicy::Restart { max_restarts, backoff } => {
+                let fut = (task.restarts < max_restarts).then(|| (task.factory)()).flatten();
+                let Some(fut) = fut else {
+                    error!("Subsystem {} failed after {} restarts: {}", name, task.restarts, e);
+                    return Err(NodeError::TaskFailed(name, e.to_string()));
+                };
+                task.restarts += 1;
+                warn!(
+                    "Subsystem {} failed, restarting in {:?} ({}/{}): {}",
+                    name, backoff, task.restarts, max_restarts, e
+                );
+                self.spawn(index, async move {
+                    tokio::time::sleep(backoff).await;
+                    fut.await
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Spawns the future of a subsystem.
+    fn spawn(
+        &mut self,
+        index: usize,
+        fut: impl Future<Output = Result<(), NodeError>> + Send + 'static,
+    ) {
+        let handle = self.set.spawn(fut);
+        self.ids.insert(handle.id(), index);
+        self.tasks[index].running = Some(handle);
+    }
+
+    /// Stops the running subsystems in the reverse order they were added, waiting
+    /// for each to stop before stopping the next.
+    async fn shutdown(&mut self) {
+        for task in self.tasks.iter_mut().rev() {
+            let Some(handle) = task.running.take() else { continue };
+            handle.abort();
+            while let Some(joined) = self.set.join_next_with_id().await {
+                let id = match &joined {
+                    Ok((id, _)) => *id,
+                    Err(e) => e.id(),
+                };
+                if id == handle.id() {
+                    break;
+                }
+            }
+            info!("Stopped {}", task.name);
+        }
+        self.ids.clear();
+    }
+}
+
+/// Converts the [JoinError] of a panicked or cancelled task into a [NodeError].
+fn panicked(e: JoinError) -> NodeError {
+    match e.try_into_panic() {
+        Ok(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            NodeError::TaskPanicked(message)
+        }
+        Err(e) => NodeError::TaskPanicked(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+    use tokio::sync::{mpsc, oneshot};
+
+    #[tokio::test]
+    async fn test_restart_policies() {
+        let starts = Arc::new(AtomicU32::new(0));
+        let mut supervisor = Supervisor::default();
+        let counter = starts.clone();
+        supervisor.add(
+            "flaky",
+            RestartPolicy::Restart { max_restarts: 2, backoff: Duration::ZERO },
+            move || {
+                let starts = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    if starts == 1 {
+                        panic!("first start fails");
+                    }
+                    Err(NodeError::Network(format!("start {starts} failed")))
+                }
+            },
+        );
+        supervisor.add_once("ignored", RestartPolicy::Ignore, async {
+            Err(NodeError::Rpc("unavailable".into()))
+        });
+        supervisor.add_once("finished", RestartPolicy::Fatal, async { Ok(()) });
+
+        let err = supervisor.run(std::future::pending()).await.unwrap_err();
+        assert_eq!(starts.load(Ordering::SeqCst), 3);
+        assert!(matches!(err, NodeError::TaskFailed("flaky", e) if e.contains("start 3 failed")));
+    }
+
+    #[tokio::test]
+    async fn test_fatal_failure_and_ordered_shutdown() {
+        let (stopped, mut order) = mpsc::unbounded_channel();
+        let (fail, failed) = oneshot::channel::<()>();
+        let mut supervisor = Supervisor::default();
+        for name in ["context", "driver", "rpc"] {
+            let stopped = stopped.clone();
+            supervisor.add_once(name, RestartPolicy::Fatal, async move {
+                let _guard = OnDrop(Some(move || _ = stopped.send(name)));
+                std::future::pending().await
+            });
+        }
+        supervisor.add_once("network", RestartPolicy::Fatal, async move {
+            _ = failed.await;
+            Err(NodeError::Network("stopped".into()))
+        });
+
+        drop(fail);
+        let err = supervisor.run(std::future::pending()).await.unwrap_err();
+        assert!(matches!(err, NodeError::TaskFailed("network", _)));
+
+        drop(stopped);
+        let mut stops = Vec::new();
+        while let Some(name) = order.recv().await {
+            stops.push(name);
+        }
+        assert_eq!(stops, ["rpc", "driver", "context"]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_signal() {
+        let mut supervisor = Supervisor::default();
+        supervisor.add("server", RestartPolicy::default(), std::future::pending);
+        assert!(supervisor.run(async {}).await.is_ok());
+    }
+
+    /// Runs a closure when dropped.
+    struct OnDrop<F: FnOnce()>(Option<F>);
+
+    impl<F: FnOnce()> Drop for OnDrop<F> {
+        fn drop(&mut self) {
+            if let Some(f) = self.0.take() {
+                f();
+            }
+        }
+    }
+}
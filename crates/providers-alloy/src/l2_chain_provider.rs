@@ -1,6 +1,7 @@
 //! Providers that use alloy provider types on the backend.
 
-use alloy_primitives::{Bytes, U64};
+use alloy_consensus::Header;
+use alloy_primitives::{address, keccak256, Address, Bytes, B256, U64};
 use alloy_provider::{Provider, ReqwestProvider};
 use alloy_rlp::Decodable;
 use alloy_transport::{RpcError, TransportErrorKind, TransportResult};
@@ -17,6 +18,9 @@ use std::{boxed::Box, num::NonZeroUsize, sync::Arc};
 
 const CACHE_SIZE: usize = 16;
 
+/// The address of the `L2ToL1MessagePasser` predeploy.
+const L2_TO_L1_MESSAGE_PASSER: Address = address!("4200000000000000000000000000000000000016");
+
 /// The [AlloyL2ChainProvider] is a concrete implementation of the [L2ChainProvider] trait,
 /// providing data over Ethereum JSON-RPC using an alloy provider as the backend.
 ///
@@ -59,6 +63,25 @@ impl AlloyL2ChainProvider {
         self.inner.get_block_number().await
     }
 
+    /// Returns the output root of the L2 block with the given header.
+    ///
+    /// The output root commits to the state root, the storage root of the
+    /// `L2ToL1MessagePasser` predeploy and the hash of the block.
+    pub async fn output_root(
+        &mut self,
+        header: &Header,
+    ) -> Result<B256, RpcError<TransportErrorKind>> {
+        let hash = header.hash_slow();
+        let proof =
+            self.inner.get_proof(L2_TO_L1_MESSAGE_PASSER, Vec::new()).block_id(hash.into()).await?;
+        // The output root version 0 is an empty 32 byte word.
+        let mut preimage = [0u8; 128];
+        preimage[32..64].copy_from_slice(header.state_root.as_slice());
+        preimage[64..96].copy_from_slice(proof.storage_hash.as_slice());
+        preimage[96..].copy_from_slice(hash.as_slice());
+        Ok(keccak256(preimage))
+    }
+
     /// Creates a new [AlloyL2ChainProvider] from the provided [reqwest::Url].
     pub fn new_http(url: reqwest::Url, rollup_config: Arc<RollupConfig>) -> Self {
         let inner = ReqwestProvider::new_http(url);
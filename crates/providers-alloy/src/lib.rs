@@ -19,7 +19,7 @@ pub use blobs::{
 };
 
 mod chain_provider;
-pub use chain_provider::AlloyChainProvider;
+pub use chain_provider::{AlloyChainProvider, AlloyChainProviderError};
 
 mod l2_chain_provider;
 pub use l2_chain_provider::AlloyL2ChainProvider;
@@ -9,3 +9,8 @@
 #[cfg(feature = "driver")]
 #[doc(inline)]
 pub use hilo_driver as driver;
+
+#[cfg(feature = "registry")]
+mod registry;
+#[cfg(feature = "registry")]
+pub use registry::{network_chain_id, network_rollup_config, Registry, RegistryError, NETWORKS};
@@ -0,0 +1,452 @@
+//! Named chain selection and a loader for local superchain-registry checkouts.
+
+use alloy_eips::{eip1559::BaseFeeParams, BlockNumHash};
+use alloy_primitives::{Address, B256, U256};
+use op_alloy_genesis::{ChainGenesis, RollupConfig, SystemConfig};
+use op_alloy_registry::ROLLUP_CONFIGS;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The chains of the superchain-registry that can be selected by name without a
+/// local registry, with their L2 chain ids.
+///
+/// Names follow the `<chain>-<superchain>` format of the registry.
+pub const NETWORKS: &[(&str, u64)] = &[
+    ("op-mainnet", 10),
+    ("op-sepolia", 11155420),
+    ("base-mainnet", 8453),
+    ("base-sepolia", 84532),
+    ("mode-mainnet", 34443),
+    ("zora-mainnet", 7777777),
+    ("zora-sepolia", 999999999),
+    ("fraxtal-mainnet", 252),
+    ("lisk-mainnet", 1135),
+    ("lisk-sepolia", 4202),
+    ("worldchain-mainnet", 480),
+    ("worldchain-sepolia", 4801),
+    ("unichain-sepolia", 1301),
+    ("ink-sepolia", 763373),
+];
+
+/// The channel timeout of registry chains, in L1 blocks.
+const CHANNEL_TIMEOUT: u64 = 300;
+
+/// The channel timeout of registry chains after Granite, in L1 blocks.
+const GRANITE_CHANNEL_TIMEOUT: u64 = 50;
+
+/// The EIP-1559 parameters of chains that do not set them.
+const DEFAULT_EIP1559: Eip1559Toml =
+    Eip1559Toml { eip1559_elasticity: 6, eip1559_denominator: 50, eip1559_denominator_canyon: 250 };
+
+/// The file of a superchain directory holding the superchain-wide config.
+const SUPERCHAIN_FILE: &str = "superchain.toml";
+
+/// An error loading a superchain-registry directory.
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    /// A registry file or directory could not be read.
+    #[error("failed to read {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    /// A registry file is not valid.
+    #[error("failed to parse {0}: {1}")]
+    Parse(PathBuf, toml::de::Error),
+    /// The directory contains no superchain configs.
+    #[error("no superchain configs found in {0}, expected a superchain-registry checkout")]
+    Empty(PathBuf),
+}
+
+/// Returns the L2 chain id of a chain of the superchain-registry by name.
+pub fn network_chain_id(name: &str) -> Option<u64> {
+    NETWORKS.iter().find(|(network, _)| *network == name).map(|(_, chain_id)| *chain_id)
+}
+
+/// Returns the [RollupConfig] of a chain of the superchain-registry by name.
+pub fn network_rollup_config(name: &str) -> Option<RollupConfig> {
+    network_chain_id(name).and_then(|chain_id| ROLLUP_CONFIGS.get(&chain_id).cloned())
+}
+
+/// The rollup configs of a local superchain-registry checkout, keyed by name.
+///
+/// Lets chains added to the registry after a release be selected without a new release.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Registry {
+    /// The rollup configs by `<chain>-<superchain>` name.
+    chains: BTreeMap<String, RollupConfig>,
+}
+
+impl Registry {
+    /// Loads the chain configs of a superchain-registry checkout.
+    ///
+    /// The path is either the root of the checkout or its `superchain/configs`
+    /// directory, which holds a directory per superchain with a `superchain.toml`
+    /// and a TOML file per chain.
+    pub fn from_dir(path: impl AsRef<Path>) -> Result<Self, RegistryError> {
+        let path = path.as_ref();
+        let configs = path.join("superchain").join("configs");
+        let configs = if configs.is_dir() { configs } else { path.to_path_buf() };
+
+        let mut chains = BTreeMap::new();
+        for superchain in read_dir(&configs)? {
+            let superchain_file = superchain.join(SUPERCHAIN_FILE);
+            if !superchain_file.is_file() {
+                continue;
+            }
+            let parent: SuperchainToml = read_toml(&superchain_file)?;
+            let Some(superchain_name) = superchain.file_name().and_then(|name| name.to_str())
+            else {
+                continue;
+            };
+
+            for file in read_dir(&superchain)? {
+                let is_toml = file.extension().is_some_and(|extension| extension == "toml");
+                if !is_toml || file.ends_with(SUPERCHAIN_FILE) {
+                    continue;
+                }
+                let Some(chain_name) = file.file_stem().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+                let chain: ChainToml = read_toml(&file)?;
+                chains
+                    .insert(format!("{chain_name}-{superchain_name}"), chain.into_rollup(&parent));
+            }
+        }
+
+        if chains.is_empty() {
+            return Err(RegistryError::Empty(path.to_path_buf()));
+        }
+        Ok(Self { chains })
+    }
+
+    /// Returns the [RollupConfig] of the chain with the given `<chain>-<superchain>` name.
+    pub fn get(&self, name: &str) -> Option<&RollupConfig> {
+        self.chains.get(name)
+    }
+
+    /// Returns the [RollupConfig] of the chain with the given L2 chain id.
+    pub fn by_chain_id(&self, chain_id: u64) -> Option<&RollupConfig> {
+        self.chains.values().find(|config| config.l2_chain_id == chain_id)
+    }
+
+    /// Returns the names of the loaded chains.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.chains.keys().map(String::as_str)
+    }
+
+    /// Returns the number of loaded chains.
+    pub const fn len(&self) -> usize {
+        self.chains.len()
+    }
+
+    /// Returns whether no chains are loaded.
+    pub const fn is_empty(&self) -> bool {
+        self.chains.is_empty()
+    }
+}
+
+/// Returns the sorted entries of a directory.
+fn read_dir(path: &Path) -> Result<Vec<PathBuf>, RegistryError> {
+    let entries = fs::read_dir(path).map_err(|e| RegistryError::Io(path.to_path_buf(), e))?;
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| RegistryError::Io(path.to_path_buf(), e))?;
+    paths.sort();
+    Ok(paths)
+}
+
+/// Reads and parses a TOML file.
+fn read_toml<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T, RegistryError> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| RegistryError::Io(path.to_path_buf(), e))?;
+    toml::from_str(&contents).map_err(|e| RegistryError::Parse(path.to_path_buf(), e))
+}
+
+/// The superchain-wide config of a `superchain.toml`.
+#[derive(Debug, Deserialize)]
+struct SuperchainToml {
+    /// The address of the `ProtocolVersions` contract.
+    #[serde(default)]
+    protocol_versions_addr: Address,
+    /// The default hardfork activation times of the superchain's chains.
+    #[serde(default)]
+    hardforks: HardforksToml,
+    /// The L1 chain of the superchain.
+    l1: SuperchainL1Toml,
+}
+
+/// The L1 chain of a superchain.
+#[derive(Debug, Deserialize)]
+struct SuperchainL1Toml {
+    /// The L1 chain id.
+    chain_id: u64,
+}
+
+/// Hardfork activation timestamps.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+struct HardforksToml {
+    canyon_time: Option<u64>,
+    delta_time: Option<u64>,
+    ecotone_time: Option<u64>,
+    fjord_time: Option<u64>,
+    granite_time: Option<u64>,
+    holocene_time: Option<u64>,
+}
+
+impl HardforksToml {
+    /// Returns the hardfork times, falling back to the superchain's times for hardforks
+    /// scheduled at or after the time the chain joined the superchain.
+    ///
+    /// Chains that never joined the superchain inherit none of its hardforks.
+    fn inherit(self, superchain: Self, superchain_time: Option<u64>) -> Self {
+        let inherit = |time: Option<u64>, default: Option<u64>| {
+            time.or(default.filter(|default| superchain_time.is_some_and(|t| *default >= t)))
+        };
+        Self {
+            canyon_time: inherit(self.canyon_time, superchain.canyon_time),
+            delta_time: inherit(self.delta_time, superchain.delta_time),
+            ecotone_time: inherit(self.ecotone_time, superchain.ecotone_time),
+            fjord_time: inherit(self.fjord_time, superchain.fjord_time),
+            granite_time: inherit(self.granite_time, superchain.granite_time),
+            holocene_time: inherit(self.holocene_time, superchain.holocene_time),
+        }
+    }
+}
+
+/// The EIP-1559 parameters of a chain.
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Eip1559Toml {
+    eip1559_elasticity: u64,
+    eip1559_denominator: u64,
+    eip1559_denominator_canyon: u64,
+}
+
+/// The config of a chain in the registry.
+#[derive(Debug, Deserialize)]
+struct ChainToml {
+    chain_id: u64,
+    batch_inbox_addr: Address,
+    block_time: u64,
+    seq_window_size: u64,
+    max_sequencer_drift: u64,
+    /// The time the chain joined the superchain, from which on it follows the
+    /// superchain's hardforks.
+    superchain_time: Option<u64>,
+    #[serde(default)]
+    hardforks: HardforksToml,
+    optimism: Option<Eip1559Toml>,
+    genesis: GenesisToml,
+    #[serde(default)]
+    addresses: AddressesToml,
+    alt_da: Option<AltDaToml>,
+}
+
+/// The genesis of a chain.
+#[derive(Debug, Deserialize)]
+struct GenesisToml {
+    l2_time: u64,
+    l1: BlockToml,
+    l2: BlockToml,
+    system_config: Option<SystemConfigToml>,
+}
+
+/// A block reference.
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct BlockToml {
+    hash: B256,
+    number: u64,
+}
+
+/// The genesis system config of a chain.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemConfigToml {
+    batcher_address: Address,
+    overhead: U256,
+    scalar: U256,
+    gas_limit: u64,
+}
+
+/// The L1 contract addresses of a chain.
+#[derive(Debug, Default, Deserialize)]
+struct AddressesToml {
+    #[serde(rename = "OptimismPortalProxy", default)]
+    optimism_portal_proxy: Address,
+    #[serde(rename = "SystemConfigProxy", default)]
+    system_config_proxy: Address,
+}
+
+/// The alt-DA config of a chain.
+#[derive(Debug, Deserialize)]
+struct AltDaToml {
+    da_challenge_contract_address: Option<Address>,
+}
+
+impl ChainToml {
+    /// Converts the chain config into a [RollupConfig], falling back to the
+    /// superchain's hardfork times scheduled since the chain joined the superchain.
+    fn into_rollup(self, superchain: &SuperchainToml) -> RollupConfig {
+        let forks = self.hardforks.inherit(superchain.hardforks, self.superchain_time);
+        let eip1559 = self.optimism.unwrap_or(DEFAULT_EIP1559);
+        let system_config = self.genesis.system_config.map(|config| SystemConfig {
+            batcher_address: config.batcher_address,
+            overhead: config.overhead,
+            scalar: config.scalar,
+            gas_limit: config.gas_limit,
+            ..Default::default()
+        });
+
+        RollupConfig {
+            genesis: ChainGenesis {
+                l1: BlockNumHash { number: self.genesis.l1.number, hash: self.genesis.l1.hash },
+                l2: BlockNumHash { number: self.genesis.l2.number, hash: self.genesis.l2.hash },
+                l2_time: self.genesis.l2_time,
+                system_config,
+            },
+            block_time: self.block_time,
+            max_sequencer_drift: self.max_sequencer_drift,
+            seq_window_size: self.seq_window_size,
+            channel_timeout: CHANNEL_TIMEOUT,
+            granite_channel_timeout: GRANITE_CHANNEL_TIMEOUT,
+            l1_chain_id: superchain.l1.chain_id,
+            l2_chain_id: self.chain_id,
+            base_fee_params: BaseFeeParams::new(
+                eip1559.eip1559_denominator.into(),
+                eip1559.eip1559_elasticity.into(),
+            ),
+            canyon_base_fee_params: BaseFeeParams::new(
+                eip1559.eip1559_denominator_canyon.into(),
+                eip1559.eip1559_elasticity.into(),
+            ),
+            regolith_time: Some(0),
+            canyon_time: forks.canyon_time,
+            delta_time: forks.delta_time,
+            ecotone_time: forks.ecotone_time,
+            fjord_time: forks.fjord_time,
+            granite_time: forks.granite_time,
+            holocene_time: forks.holocene_time,
+            batch_inbox_address: self.batch_inbox_addr,
+            deposit_contract_address: self.addresses.optimism_portal_proxy,
+            l1_system_config_address: self.addresses.system_config_proxy,
+            protocol_versions_address: superchain.protocol_versions_addr,
+            da_challenge_address: self
+                .alt_da
+                .and_then(|alt_da| alt_da.da_challenge_contract_address),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUPERCHAIN: &str = r#"
+name = "Sepolia"
+protocol_versions_addr = "0x79ADD5713B383DAa0a138d3C4780C7A1804a8090"
+
+[hardforks]
+canyon_time = 1699981200
+delta_time = 1703203200
+
+[l1]
+chain_id = 11155111
+"#;
+
+    const CHAIN: &str = r#"
+name = "New Chain"
+chain_id = 4242
+batch_inbox_addr = "0xff00000000000000000000000000000000004242"
+block_time = 2
+seq_window_size = 3600
+max_sequencer_drift = 600
+superchain_time = 1699981200
+
+[hardforks]
+delta_time = 1703203201
+
+[genesis]
+l2_time = 1695768288
+[genesis.l1]
+hash = "0x0000000000000000000000000000000000000000000000000000000000000001"
+number = 4370868
+[genesis.l2]
+hash = "0x0000000000000000000000000000000000000000000000000000000000000002"
+number = 0
+[genesis.system_config]
+batcherAddress = "0x6CDEbe940BC0F26850285cacA097C11c33103E47"
+overhead = "0x0000000000000000000000000000000000000000000000000000000000000834"
+scalar = "0x00000000000000000000000000000000000000000000000000000000000f4240"
+gasLimit = 25000000
+
+[addresses]
+OptimismPortalProxy = "0x0000000000000000000000000000000000000003"
+SystemConfigProxy = "0x0000000000000000000000000000000000000004"
+"#;
+
+    #[test]
+    fn test_network_names() {
+        assert_eq!(network_chain_id("base-sepolia"), Some(84532));
+        assert_eq!(network_rollup_config("op-mainnet").unwrap().l2_chain_id, 10);
+        assert_eq!(network_chain_id("base"), None);
+    }
+
+    #[test]
+    fn test_registry_from_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let sepolia = root.path().join("superchain").join("configs").join("sepolia");
+        fs::create_dir_all(&sepolia).unwrap();
+        fs::write(sepolia.join(SUPERCHAIN_FILE), SUPERCHAIN).unwrap();
+        fs::write(sepolia.join("new.toml"), CHAIN).unwrap();
+
+        let registry = Registry::from_dir(root.path()).unwrap();
+
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["new-sepolia"]);
+        let config = registry.get("new-sepolia").unwrap();
+        assert_eq!(registry.by_chain_id(4242), Some(config));
+        assert_eq!(config.l1_chain_id, 11155111);
+        assert_eq!(config.genesis.l2.hash, B256::with_last_byte(2));
+        assert_eq!(config.genesis.system_config.as_ref().unwrap().gas_limit, 25000000);
+        assert_eq!(config.canyon_time, Some(1699981200));
+        assert_eq!(config.delta_time, Some(1703203201));
+        assert_eq!(config.ecotone_time, None);
+        assert_eq!(config.deposit_contract_address, Address::with_last_byte(3));
+        assert_eq!(config.canyon_base_fee_params.max_change_denominator, 250);
+    }
+
+    #[test]
+    fn test_registry_superchain_time() {
+        let root = tempfile::tempdir().unwrap();
+        let sepolia = root.path().join("sepolia");
+        fs::create_dir_all(&sepolia).unwrap();
+        fs::write(sepolia.join(SUPERCHAIN_FILE), SUPERCHAIN).unwrap();
+        let chain = CHAIN.replace("delta_time = 1703203201\n", "");
+        let joined_later =
+            chain.replace("superchain_time = 1699981200", "superchain_time = 1700000000");
+        fs::write(sepolia.join("late.toml"), joined_later).unwrap();
+        let standalone = chain.replace("superchain_time = 1699981200\n", "");
+        fs::write(sepolia.join("standalone.toml"), standalone).unwrap();
+
+        let registry = Registry::from_dir(root.path()).unwrap();
+
+        // Only hardforks scheduled since the chain joined are inherited.
+        let config = registry.get("late-sepolia").unwrap();
+        assert_eq!(config.canyon_time, None);
+        assert_eq!(config.delta_time, Some(1703203200));
+
+        // Chains outside the superchain only follow their own hardforks.
+        let config = registry.get("standalone-sepolia").unwrap();
+        assert_eq!(config.canyon_time, None);
+        assert_eq!(config.delta_time, None);
+    }
+
+    #[test]
+    fn test_registry_from_empty_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let result = Registry::from_dir(root.path());
+        assert!(matches!(result, Err(RegistryError::Empty(_))));
+    }
+}